@@ -52,6 +52,8 @@
 #define DEVICE_ADDRESS_BUFFER_BINDING 4
 #define SPECIAL_BUFFER_BINDING 3
 #define SPECIAL_IMAGE_BINDING 2
+#define SAMPLED_IMAGE_BINDING 1
+#define SAMPLER_BINDING 0
 
 struct BufferId {
 	u32 buffer_id_value;
@@ -61,6 +63,10 @@ struct ImageId {
 	u32 image_id_value;
 };
 
+struct SamplerId {
+	u32 sampler_id_value;
+};
+
 layout(scalar, binding = DEVICE_ADDRESS_BUFFER_BINDING, set = 0) readonly buffer BufferDeviceAddressBuffer
 {
     u64 addresses[];
@@ -68,6 +74,9 @@ layout(scalar, binding = DEVICE_ADDRESS_BUFFER_BINDING, set = 0) readonly buffer
 
 #define _buffer_reference_layout layout(buffer_reference, scalar, buffer_reference_align = 4)
 #define _storage_image_layout layout(binding = SPECIAL_IMAGE_BINDING, set = 0)
+#define _sampled_image_layout layout(binding = SAMPLED_IMAGE_BINDING, set = 0)
+
+layout(binding = SAMPLER_BINDING, set = 0) uniform sampler SamplerTable[];
 
 #define decl_buffer(STRUCT_TYPE) \
     _buffer_reference_layout buffer Buffer##STRUCT_TYPE { \
@@ -139,4 +148,37 @@ _decl_image_type(3D)
     _register_image_kind2(kind, dim, u32, u16)
 
 _register_image_types(2D, 2)
-_register_image_types(3D, 3)
\ No newline at end of file
+_register_image_types(3D, 3)
+
+#define _decl_texture_kind(name, kind, type) \
+	_sampled_image_layout uniform name TextureTable##kind##type[];
+
+#define _decl_texture_type(kind) \
+	_decl_texture_kind(texture##kind, kind, f32) \
+	_decl_texture_kind(utexture##kind, kind, u32) \
+	_decl_texture_kind(itexture##kind, kind, i32)
+
+_decl_texture_type(1D)
+_decl_texture_type(2D)
+_decl_texture_type(3D)
+_decl_texture_type(Cube)
+_decl_texture_type(2DArray)
+_decl_texture_type(CubeArray)
+
+#define _sampler_f32(kind) sampler##kind
+#define _sampler_u32(kind) usampler##kind
+#define _sampler_i32(kind) isampler##kind
+
+//Combines a sampled image from the table with a sampler, e.g. sampled(2D, f32, image_id, sampler_id).
+#define sampled(kind, type, image_id, sampler_id) \
+	_sampler_##type(kind)(TextureTable##kind##type[nonuniformEXT(image_id.image_id_value)], SamplerTable[nonuniformEXT(sampler_id.sampler_id_value)])
+
+#define _sampled_shadow(kind, image_id, sampler_id) \
+	sampler##kind##Shadow(TextureTable##kind##f32[nonuniformEXT(image_id.image_id_value)], SamplerTable[nonuniformEXT(sampler_id.sampler_id_value)])
+
+#define texture_sample(kind, type, image_id, sampler_id, uv) texture(sampled(kind, type, image_id, sampler_id), uv)
+#define texture_sample_lod(kind, type, image_id, sampler_id, uv, lod) textureLod(sampled(kind, type, image_id, sampler_id), uv, lod)
+#define texture_fetch(kind, type, image_id, sampler_id, index, lod) texelFetch(sampled(kind, type, image_id, sampler_id), index, lod)
+#define texture_size(kind, type, image_id, sampler_id, lod) textureSize(sampled(kind, type, image_id, sampler_id), lod)
+//The sampler must be created with a compare op, the reference is the last component of uv.
+#define texture_compare(kind, image_id, sampler_id, uv) texture(_sampled_shadow(kind, image_id, sampler_id), uv)
@@ -25,6 +25,8 @@ fn main() {
         enable_validation: false,
         //Give your application a name
         application_name: "Triangle",
+        //This tells the context which kind of windows you will present to, using the raw_window_handle crate.
+        display: window.raw_display_handle(),
         //Almost everything in this library has a default.
         ..Default::default()
     })
@@ -32,53 +34,39 @@ fn main() {
 
     //The device is your handle to the system GPU.
     //It automatically selects the most capable GPU. (this can be configured)
+    //It is not tied to a window, so one device can draw to as many windows as you like.
     let mut device = context
         .create_device(DeviceInfo {
-            //The following two parameters use the raw_window_handle crate.
-            //Compatible with winit or your favorite windowing system.
-            display: window.raw_display_handle(),
-            window: window.raw_window_handle(),
             ..Default::default()
         })
         .expect("failed to create device");
 
     //The swapchain manages the images which are sent to the monitor to be displayed.
     //You must use the device to acquire images from the swapchain in your render graph.
-    //We put this inside a function because we want to change it when its resized.
-    fn resize(
-        device: Device,
-        old_swapchain: Option<Swapchain>,
-        width: u32,
-        height: u32,
-    ) -> (Swapchain, RenderGraph<'static, RenderInfo>) {
-        let mut swapchain = device
+    //It is created once. When the window is resized, it is recreated in place and keeps its handle,
+    //so the render graph using it does not have to be rebuilt.
+    //Call `device.destroy_swapchain(..)` if the window is closed while the device lives on.
+    let swapchain = {
+        let winit::dpi::PhysicalSize { width, height } = window.inner_size();
+
+        device
             .create_swapchain(SwapchainInfo {
+                //The following two parameters use the raw_window_handle crate.
+                //Compatible with winit or your favorite windowing system.
+                display: window.raw_display_handle(),
+                window: window.raw_window_handle(),
                 width,
                 height,
                 present_mode: PresentMode::DoNotWaitForVBlank,
                 image_usage: ImageUsage::COLOR,
                 ..Default::default()
             })
-            .expect("failed to create swapchain");
-
-        //We will get to this later.
-        let render_graph = record(device.clone(), swapchain, width, height);
-
-        (swapchain, render_graph)
-    }
+            .expect("failed to create swapchain")
+    };
 
-    let mut swapchain = None;
     //We will get to this later.
-    let mut render_graph = None;
-
-    //Initial resize
-    {
-        let winit::dpi::PhysicalSize { width, height } = window.inner_size();
-        //The device is a handle so you can clone it whenever.
-        let (s, r) = resize(device.clone(), swapchain, width, height);
-        swapchain = Some(s);
-        render_graph = Some(r);
-    }
+    //The device is a handle so you can clone it whenever.
+    let mut render_graph = record(device.clone(), swapchain);
 
     //A pipeline compiler allows us to load and hot-reload pipelines.
     //This used to be a file manager too, however that has changed.
@@ -97,21 +85,19 @@ fn main() {
                 Shader{
                     ty: ShaderType::Vertex,
                     //Here we read the source file into a string. We provide this to the pipeline compiler whenever we want to load or hot reload.
-                    source: fs::read_to_string("./triangle.glsl").unwrap(),
+                    source: fs::read("./triangle.glsl").unwrap(),
                     defines: vec![]
                 },
                 Shader{
                     ty: ShaderType::Fragment,
                     //The fragment shader is in the same file.
-                    source: fs::read_to_string("./triangle.glsl").unwrap(),
+                    source: fs::read("./triangle.glsl").unwrap(),
                     defines: vec![]
                 },
             ],
             color: vec![Color {
                 format: device
-                    .presentation_format(
-                        swapchain.expect("should have been set in the initial resize"),
-                    )
+                    .presentation_format(swapchain)
                     .expect("this shouldnt fail"),
                 ..Default::default()
             }],
@@ -179,12 +165,7 @@ fn main() {
         time: f32,
     };
 
-    fn record(
-        device: Device,
-        swapchain: Swapchain,
-        width: u32,
-        height: u32,
-    ) -> RenderGraph<'static, RenderInfo> {
+    fn record(device: Device, swapchain: Swapchain) -> RenderGraph<'static, RenderInfo> {
         //Now here comes the fun part. We use the device to start building a render graph.
         //A render graph is just what it sounds like: a graph.
         //The nodes of this graph are the work that you do, and the edges are determined
@@ -193,7 +174,8 @@ fn main() {
         //(this may be removed in the future)
         let mut render_graph_builder = device
             .create_render_graph::<'_, RenderInfo>(RenderGraphInfo {
-                swapchain,
+                //A render graph can present to several swapchains at once, such as one per window.
+                swapchains: vec![swapchain],
                 ..Default::default()
             })
             .expect("failed to create render graph builder");
@@ -201,150 +183,174 @@ fn main() {
         //A task is a unit of work for the GPU. Synchronization happens between tasks.
         //Things inside a task may be reordered unless explicitly specified not to through the use of `commands.pipeline_barrier(..)`
         //Underneath the hood, the render graph tracks resource usage and calls `commands.pipeline_barrier(..)` for you.
-        render_graph_builder.add(Task {
-            resources: vec![Resource::Buffer(
+        //Resources are declared in the closure that builds the task. Each declaration hands back a token,
+        //which is how the task refers to the resource in its commands. Tokens only work in the task that declared them.
+        render_graph_builder.add(|resources| {
+            let staging = resources.buffer(
                 //This closure tells the render graph what resource you are using.
                 //It is polled every frame.
-                Box::new(|render_info| render_info.staging_buffer),
+                |render_info: &mut RenderInfo| render_info.staging_buffer,
                 //This tells the render graph how you use this resource.
                 //It is very important this is accurate to your usecase or you may get a crash or undefined behavior.
-                BufferAccess::HostTransferWrite,
-            )],
-            task: |render_info, commands| {
-                commands.write_buffer(BufferWrite {
-                    //This is the index of the buffer in the resources vector.
-                    //If you have multiple buffers, images, then you will want to make sure this is accurate.
-                    buffer: 0,
-                    //This is where you are writing in the buffer
-                    offset: 0,
-                    //This is a slice that is copied bit-for-bit into the buffer.
-                    src: &[render_info.time],
-                })
-            },
+                //The access decides the kind of token (`WriteOnly` here), and the commands only accept tokens of the right kind.
+                WriteBufferAccess::HostTransferWrite,
+            );
+
+            Task {
+                //The name shows up in graph exports, profilers, error messages and graphics debuggers.
+                debug_name: "update time",
+                //Tasks can also run on `Queue::Compute` or `Queue::Transfer`, which may run alongside graphics work.
+                queue: Queue::Graphics,
+                task: Box::new(move |render_info, commands| {
+                    commands.write_buffer(BufferWrite {
+                        //The token of the buffer we declared above.
+                        buffer: staging,
+                        //This is where you are writing in the buffer
+                        offset: 0,
+                        //This is a slice that is copied bit-for-bit into the buffer.
+                        src: &[render_info.time],
+                    })
+                }),
+            }
         });
 
         //This should start being self-explanatory.
-        render_graph_builder.add(Task {
-            resources: vec![
-                //Now we copy from this buffer.
-                Resource::Buffer(
-                    Box::new(|render_info| render_info.staging_buffer),
-                    BufferAccess::TransferWrite,
-                ),
-                //To this buffer
-                Resource::Buffer(
-                    Box::new(|render_info| render_info.time_buffer),
-                    BufferAccess::TransferRead,
-                ),
-            ],
-            task: |render_info, commands| {
-                commands.copy_buffer_to_buffer(BufferCopy {
-                    //Index of the buffer in the resource vector
-                    from: 0,
-                    //Index of the buffer in the resource vector
-                    to: 1,
-                    regions: vec![Region {
-                        //Source (from parameter) buffer offset.
-                        src: 0,
-                        //Destination (to parameter) buffer offset.
-                        dst: 0,
-                        size: std::mem::size_of::<f32>(),
-                    }]
-                })
-            },
+        render_graph_builder.add(|resources| {
+            //Now we copy from this buffer.
+            let staging = resources.buffer(
+                |render_info: &mut RenderInfo| render_info.staging_buffer,
+                ReadBufferAccess::TransferRead,
+            );
+            //To this buffer
+            let time = resources.buffer(
+                |render_info: &mut RenderInfo| render_info.time_buffer,
+                WriteBufferAccess::TransferWrite,
+            );
+
+            Task {
+                debug_name: "copy time",
+                queue: Queue::Graphics,
+                task: Box::new(move |render_info, commands| {
+                    commands.copy_buffer_to_buffer(BufferCopy {
+                        from: staging,
+                        to: time,
+                        regions: vec![Region {
+                            //Source (from parameter) buffer offset.
+                            src: 0,
+                            //Destination (to parameter) buffer offset.
+                            dst: 0,
+                            size: std::mem::size_of::<f32>(),
+                        }]
+                    })
+                }),
+            }
         });
 
         //Here comes the super fun part. Drawing to the screen.
-        render_graph_builder.add(Task {
-            resources: vec![
-                Resource::Image(
-                    //This acquires the next image from the swapchain, essentially the image we can draw to
-                    //and it is also the image which is drawn to the screen.
-                    Box::new(|render_info| {
-                        render_info
-                            .device
-                            .acquire_next_image(Acquire {
-                                swapchain: render_info.swapchain,
-                                semaphore: Some(render_info.acquire_semaphore),
-                            })
-                            .expect("failed to acquire next image")
-                    }),
-                    ImageAccess::ColorAttachment,
-                    //This is the image aspect. It defaults to color images.
-                    //If you are using a depth image, you will want `ImageAspect::DEPTH`
-                    //and if you use the stencil buffer you will want to add `ImageAspect::STENCIL`
-                    Default::default(),
-                ),
-                Resource::Buffer(
-                    Box::new(|render_info| render_info.time_buffer),
-                    BufferAccess::VertexShaderReadOnly,
-                ),
-            ],
-            //We specify the closure as `move` so that it can take the values of `width` and `height`
-            //See `commands.start_rendering` and `commands.set_resolution`
-            task: move |render_info, commands| {
-                //No need for renderpasses here! just start rendering.
-                commands.start_rendering(Render {
-                    color: vec![Attachment {
-                        //This is the index into the resource vector.
-                        image: 0,
-                        //What you want to do with the image to start.
-                        //In this case, we are setting it to `clear`...
-                        load_op: LoadOp::Clear,
-                        //...which is black.
-                        clear: Clear::Color(0.0, 0.0, 0.0, 1.0),
-                    }],
-                    depth: None,
-                    use_stencil: false,
-                    render_area: RenderArea {
-                        x: 0,
-                        y: 0,
-                        width,
-                        height,
-                    },
-                })?;
-                //^ There is (supposed) to be proper error handling, although its not all implemented yet.
-                //So add a question mark to the end of your statement like this.
-
-                commands.set_resolution(
-                    (width, height),
-                    /*flip the viewport on the y axis, if your coming from opengl, you will maybe want this*/ 
-                    true,
-                );
-
-                //A push constant is like data that hitches a ride with the command to the GPU.
-                //Make sure this is #[repr(C)]!
-                //Also needs to know the pipeline to set the push constant for.
-                #[repr(C)]
-                #[derive(Clone, Copy)]
-                struct Push {
-                    time_buffer_address: BufferAddress,
-                }
-
-                commands.push_constant(PushConstant {
-                    data: Push {
-                        time_buffer_address: render_info.time_buffer_address,
-                    },
-                    pipeline: &render_info.render_pipeline,
-                })?;
-
-                //Finally, before rendering, specify the pipeline you will use.
-                commands.set_pipeline(&render_info.render_pipeline)?;
-
-                commands.draw(Draw {
-                    //Three vertices in a triangle.
-                    vertex_count: 3,
-                })?;
-
-                commands.end_rendering()
-            },
+        render_graph_builder.add(|resources| {
+            let swapchain_image = resources.attachment(
+                //This acquires the next image from the swapchain, essentially the image we can draw to
+                //and it is also the image which is drawn to the screen.
+                |render_info: &mut RenderInfo| {
+                    render_info
+                        .device
+                        .acquire_next_image(Acquire {
+                            swapchain: render_info.swapchain,
+                            semaphore: Some(render_info.acquire_semaphore),
+                        })
+                        .expect("failed to acquire next image")
+                },
+                AttachmentAccess::ColorAttachment,
+                //This is the image aspect. It defaults to color images.
+                //If you are using a depth image, you will want `ImageAspect::DEPTH`
+                //and if you use the stencil buffer you will want to add `ImageAspect::STENCIL`
+                Default::default(),
+            );
+            //The shader reads the time through its buffer address, so the token is not needed in the task.
+            resources.buffer(
+                |render_info: &mut RenderInfo| render_info.time_buffer,
+                ReadBufferAccess::VertexShaderReadOnly,
+            );
+
+            Task {
+                debug_name: "draw triangle",
+                queue: Queue::Graphics,
+                //We specify the closure as `move` so that it can take the token of the swapchain image.
+                task: Box::new(move |render_info, commands| {
+                    //The size of the swapchain changes when the window is resized, so we ask for it every frame.
+                    //See `commands.start_rendering` and `commands.set_resolution`
+                    let (width, height) = render_info
+                        .device
+                        .swapchain_extent(render_info.swapchain)?;
+
+                    //No need for renderpasses here! just start rendering.
+                    commands.start_rendering(Render {
+                        color: vec![Attachment {
+                            //Only tokens declared as attachments can be rendered to.
+                            image: swapchain_image,
+                            //What you want to do with the image to start.
+                            //In this case, we are setting it to `clear`...
+                            load_op: LoadOp::Clear,
+                            //...which is black.
+                            clear: Clear::Color(0.0, 0.0, 0.0, 1.0),
+                            //Only multisampled images need to be resolved.
+                            resolve: None,
+                        }],
+                        depth: None,
+                        use_stencil: false,
+                        render_area: RenderArea {
+                            x: 0,
+                            y: 0,
+                            width,
+                            height,
+                        },
+                    })?;
+                    //^ There is (supposed) to be proper error handling, although its not all implemented yet.
+                    //So add a question mark to the end of your statement like this.
+
+                    commands.set_resolution(
+                        (width, height),
+                        /*flip the viewport on the y axis, if your coming from opengl, you will maybe want this*/ 
+                        true,
+                    );
+
+                    //A push constant is like data that hitches a ride with the command to the GPU.
+                    //Make sure this is #[repr(C)]!
+                    //Also needs to know the pipeline to set the push constant for.
+                    #[repr(C)]
+                    #[derive(Clone, Copy)]
+                    struct Push {
+                        time_buffer_address: BufferAddress,
+                    }
+
+                    commands.push_constant(PushConstant {
+                        data: Push {
+                            time_buffer_address: render_info.time_buffer_address,
+                        },
+                        pipeline: &render_info.render_pipeline,
+                    })?;
+
+                    //Finally, before rendering, specify the pipeline you will use.
+                    commands.set_pipeline(&render_info.render_pipeline)?;
+
+                    commands.draw(Draw {
+                        //Three vertices in a triangle.
+                        vertex_count: 3,
+                        instance_count: 1,
+                        first_vertex: 0,
+                        first_instance: 0,
+                    })?;
+
+                    commands.end_rendering()
+                }),
+            }
         });
 
-        render_graph_builder.add(Task {
-            resources: vec![Resource::Image(
+        render_graph_builder.add(|resources| {
+            resources.attachment(
                 //This will return the same image within the same frame. When you go to the next frame,
                 //it will send the next image. and so on and so forth.
-                Box::new(|render_info| {
+                |render_info: &mut RenderInfo| {
                     render_info
                         .device
                         .acquire_next_image(Acquire {
@@ -352,24 +358,30 @@ fn main() {
                             semaphore: Some(render_info.acquire_semaphore),
                         })
                         .expect("failed to acquire next image")
-                }),
-                ImageAccess::ColorAttachment,
+                },
+                AttachmentAccess::ColorAttachment,
                 Default::default(),
-            )],
-            task: |render_info, commands| {
-                //This tells the render graph to send the tasks to the GPU.
-                //Without it, nothing will happen.
-                commands.submit(Submit {
-                    wait_semaphore: Some(render_info.acquire_semaphore),
-                    signal_semaphore: Some(render_info.present_semaphore),
-                })?;
-
-                //This tells the GPU to show what we drew to the screen.
-                commands.present(Present {
-                    wait_semaphore: render_info.present_semaphore,
-                })?;
-                Ok(())
-            },
+            );
+
+            Task {
+                debug_name: "submit and present",
+                queue: Queue::Graphics,
+                task: Box::new(|render_info, commands| {
+                    //This tells the render graph to send the tasks to the GPU.
+                    //Without it, nothing will happen.
+                    commands.submit(Submit {
+                        //Each swapchain image we acquired signals its own semaphore.
+                        wait_semaphores: vec![render_info.acquire_semaphore],
+                        signal_semaphore: Some(render_info.present_semaphore),
+                    })?;
+
+                    //This tells the GPU to show what we drew to the screen.
+                    commands.present(Present {
+                        wait_semaphore: render_info.present_semaphore,
+                    })?;
+                    Ok(())
+                }),
+            }
         });
 
         render_graph_builder
@@ -379,7 +391,7 @@ fn main() {
 
     let mut render_info = RenderInfo {
         device: device.clone(),
-        swapchain: swapchain.unwrap(),
+        swapchain,
         acquire_semaphore,
         present_semaphore,
         render_pipeline,
@@ -403,6 +415,15 @@ fn main() {
                 println!("The close button was pressed; stopping");
                 control_flow.set_exit();
             }
+            Event::WindowEvent {
+                event: WindowEvent::Resized(size),
+                ..
+            } => {
+                //The swapchain is recreated with the new size before the next frame.
+                device
+                    .resize_swapchain(swapchain, size.width, size.height)
+                    .expect("failed to resize swapchain");
+            }
             Event::MainEventsCleared => {
                 window.request_redraw();
             }
@@ -410,7 +431,9 @@ fn main() {
                 render_info.time = std::time::Instant::now()
                     .duration_since(start_time)
                     .as_secs_f32();
-                render_graph.as_mut().unwrap().render(&mut render_info);
+                render_graph
+                    .render(&mut render_info)
+                    .expect("failed to render");
             }
             _ => (),
         }
@@ -11,6 +11,11 @@ bitflags! {
         const TRANSFER_DST = 0x00000002;
         const STORAGE = 0x00000004;
         const INDIRECT = 0x00000008;
+        const VERTEX = 0x00000010;
+        const INDEX = 0x00000020;
+        const UNIFORM = 0x00000040;
+        ///Read in shaders through a texel buffer view of a format.
+        const UNIFORM_TEXEL = 0x00000080;
     }
 }
 
@@ -34,6 +39,22 @@ impl From<BufferUsage> for vk::BufferUsageFlags {
             result |= vk::BufferUsageFlags::INDIRECT_BUFFER;
         }
 
+        if usage.contains(BufferUsage::VERTEX) {
+            result |= vk::BufferUsageFlags::VERTEX_BUFFER;
+        }
+
+        if usage.contains(BufferUsage::INDEX) {
+            result |= vk::BufferUsageFlags::INDEX_BUFFER;
+        }
+
+        if usage.contains(BufferUsage::UNIFORM) {
+            result |= vk::BufferUsageFlags::UNIFORM_BUFFER;
+        }
+
+        if usage.contains(BufferUsage::UNIFORM_TEXEL) {
+            result |= vk::BufferUsageFlags::UNIFORM_TEXEL_BUFFER;
+        }
+
         result
     }
 }
@@ -67,6 +88,42 @@ impl From<u32> for Buffer {
     }
 }
 
+///A range of bytes within a buffer.
+///A size of `usize::MAX` means the rest of the buffer.
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
+pub struct BufferRange {
+    pub offset: usize,
+    pub size: usize,
+}
+
+impl BufferRange {
+    ///The entire buffer.
+    pub const ALL: Self = Self {
+        offset: 0,
+        size: usize::MAX,
+    };
+
+    ///Clamps the range to a buffer of the given size.
+    pub(crate) fn resolve(self, buffer_size: usize) -> Self {
+        let offset = self.offset.min(buffer_size);
+
+        Self {
+            offset,
+            size: self.size.min(buffer_size - offset),
+        }
+    }
+
+    pub(crate) fn end(&self) -> usize {
+        self.offset + self.size
+    }
+}
+
+impl Default for BufferRange {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
 pub struct BufferInfo<'a> {
     pub size: usize,
     pub memory: Memory,
@@ -0,0 +1,173 @@
+use crate::prelude::*;
+
+use std::fs;
+use std::io::{self, Write};
+use std::path;
+
+use ash::vk;
+
+///The file format captured frames are written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CaptureFormat {
+    ///Binary PPM. Has no alpha channel, but needs no dependencies.
+    Ppm,
+    ///Requires the `png` feature.
+    #[cfg(feature = "png")]
+    Png,
+}
+
+impl CaptureFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Ppm => "ppm",
+            #[cfg(feature = "png")]
+            Self::Png => "png",
+        }
+    }
+}
+
+///Where and how a virtual swapchain writes the frames presented to it.
+///Frames are named `{file_prefix}{frame:05}.{extension}`, counting from zero.
+pub struct CaptureInfo<'a> {
+    pub directory: &'a path::Path,
+    pub file_prefix: &'a str,
+    pub file_format: CaptureFormat,
+    ///The number of frames to write. Frames presented afterwards are dropped.
+    ///`None` writes every frame.
+    pub frame_count: Option<usize>,
+}
+
+impl Default for CaptureInfo<'_> {
+    fn default() -> Self {
+        Self {
+            directory: path::Path::new("."),
+            file_prefix: "frame",
+            file_format: CaptureFormat::Ppm,
+            frame_count: None,
+        }
+    }
+}
+
+///The pixels of a swapchain image, from `Device::capture_swapchain`.
+pub struct Screenshot {
+    pub width: u32,
+    pub height: u32,
+    ///Tightly packed 8 bit RGBA, with rows from top to bottom.
+    pub pixels: Vec<u8>,
+}
+
+impl Screenshot {
+    pub fn save(&self, path: impl AsRef<path::Path>, file_format: CaptureFormat) -> io::Result<()> {
+        let Screenshot {
+            width,
+            height,
+            pixels,
+        } = self;
+
+        match file_format {
+            CaptureFormat::Ppm => write_ppm(path.as_ref(), pixels, *width, *height),
+            #[cfg(feature = "png")]
+            CaptureFormat::Png => write_png(path.as_ref(), pixels, *width, *height),
+        }
+    }
+}
+
+///Where the presented images of a swapchain are copied to, so they can be read on the host.
+pub(crate) struct Readback {
+    ///Host visible, and large enough for one image.
+    pub(crate) buffer: Buffer,
+    pub(crate) command_pool: vk::CommandPool,
+    pub(crate) command_buffer: vk::CommandBuffer,
+    ///Signaled once the last copy has finished.
+    pub(crate) fence: vk::Fence,
+    ///Whether a frame has been copied into `buffer`, which holds nothing before.
+    pub(crate) captured: bool,
+}
+
+///The file sequence a virtual swapchain writes.
+pub(crate) struct Capture {
+    pub(crate) directory: path::PathBuf,
+    pub(crate) file_prefix: String,
+    pub(crate) file_format: CaptureFormat,
+    pub(crate) frame_count: Option<usize>,
+    pub(crate) frames_written: usize,
+}
+
+impl Capture {
+    ///Whether `frame_count` frames have been written.
+    pub(crate) fn is_complete(&self) -> bool {
+        self.frame_count
+            .map(|frame_count| self.frames_written >= frame_count)
+            .unwrap_or(false)
+    }
+
+    ///Writes the next frame of the sequence.
+    pub(crate) fn write_frame(&mut self, screenshot: &Screenshot) -> io::Result<()> {
+        if self.is_complete() {
+            return Ok(());
+        }
+
+        let file_name = format!(
+            "{}{:05}.{}",
+            self.file_prefix,
+            self.frames_written,
+            self.file_format.extension()
+        );
+
+        let path = self.directory.join(file_name);
+
+        screenshot.save(path, self.file_format)?;
+
+        self.frames_written += 1;
+
+        Ok(())
+    }
+}
+
+///Whether frames of this format can be written to disk.
+pub(crate) fn is_capturable(format: Format) -> bool {
+    use Format::*;
+
+    matches!(format, Rgba8Unorm | Rgba8Srgb | Bgra8Unorm | Bgra8Srgb)
+}
+
+///Converts tightly packed pixels of a capturable format to RGBA.
+///sRGB formats keep their encoding, which is what image files expect.
+pub(crate) fn to_rgba8(pixels: &[u8], format: Format) -> Vec<u8> {
+    use Format::*;
+
+    match format {
+        Bgra8Unorm | Bgra8Srgb => pixels
+            .chunks_exact(4)
+            .flat_map(|bgra| [bgra[2], bgra[1], bgra[0], bgra[3]])
+            .collect(),
+        _ => pixels.to_vec(),
+    }
+}
+
+fn write_ppm(path: &path::Path, rgba: &[u8], width: u32, height: u32) -> io::Result<()> {
+    let mut file = io::BufWriter::new(fs::File::create(path)?);
+
+    write!(file, "P6\n{width} {height}\n255\n")?;
+
+    for rgba in rgba.chunks_exact(4) {
+        file.write_all(&rgba[..3])?;
+    }
+
+    file.flush()
+}
+
+#[cfg(feature = "png")]
+fn write_png(path: &path::Path, rgba: &[u8], width: u32, height: u32) -> io::Result<()> {
+    let file = io::BufWriter::new(fs::File::create(path)?);
+
+    let mut encoder = png::Encoder::new(file, width, height);
+
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(rgba))
+        .map_err(|error| io::Error::new(io::ErrorKind::Other, error))
+}
@@ -1,9 +1,11 @@
 use crate::device::DeviceInner;
+use crate::mipmap;
 use crate::pipeline::{PipelineInner, PipelineModify};
 use crate::prelude::*;
 use crate::renderpass::{Framebuffer, RenderPass};
 
 use std::collections::HashMap;
+use std::iter;
 use std::mem;
 use std::ops;
 use std::ptr;
@@ -14,13 +16,18 @@ use ash::vk::{self, Offset2D, Rect2D};
 
 use bitflags::bitflags;
 
-pub struct Commands<'a> {
+pub struct Commands<'a, 'id> {
     pub(crate) device: &'a DeviceInner,
+    ///The name of the task being recorded, reported when validation fails.
+    pub(crate) task: &'a str,
+    pub(crate) validate: bool,
     pub(crate) qualifiers: &'a [Qualifier],
     pub(crate) swapchain: &'a Swapchain,
     pub(crate) command_buffer: &'a vk::CommandBuffer,
     pub(crate) submit: &'a mut Option<Submit>,
     pub(crate) present: &'a mut Option<Present>,
+    ///Only tokens declared by the task being recorded are accepted.
+    pub(crate) brand: Brand<'id>,
 }
 bitflags! {
     pub struct Access: u32 {
@@ -80,6 +87,10 @@ fn stage_access_to_access((stage, access): (PipelineStage, Access)) -> vk::Acces
         if stage.contains(PipelineStage::ALL_COMMANDS) {
             result |= vk::AccessFlags::MEMORY_READ;
         }
+
+        if stage.contains(PipelineStage::DRAW_INDIRECT) {
+            result |= vk::AccessFlags::INDIRECT_COMMAND_READ;
+        }
     }
 
     if access.contains(Access::WRITE) {
@@ -135,6 +146,82 @@ fn stage_access_to_access((stage, access): (PipelineStage, Access)) -> vk::Acces
     result
 }
 
+///The bytes a buffer image copy reads or writes, with every block of one aspect tightly packed.
+///Compressed formats copy whole blocks, so the region has to be aligned to them
+///unless it ends at the edge of the mip level.
+fn buffer_image_copy_size(
+    format: Format,
+    aspect: ImageAspect,
+    offset: (usize, usize, usize),
+    size: (usize, usize, usize),
+    mip_extent: vk::Extent3D,
+    subresource: ImageSubresource,
+) -> Result<usize> {
+    let aspect = vk::ImageAspectFlags::from(aspect);
+
+    //Copies move a single aspect, which the format has to have.
+    if aspect.as_raw().count_ones() != 1 || !format.entire_aspect().contains(aspect) {
+        Err(Error::InvalidResource)?
+    }
+
+    let (block_width, block_height) = format.block_extent();
+
+    let aligned = |offset: usize, size: usize, block: u32, edge: u32| {
+        offset % block as usize == 0
+            && (size % block as usize == 0 || offset + size == edge as usize)
+    };
+
+    if !aligned(offset.0, size.0, block_width, mip_extent.width)
+        || !aligned(offset.1, size.1, block_height, mip_extent.height)
+    {
+        Err(Error::InvalidResource)?
+    }
+
+    let row_pitch = size.0.div_ceil(block_width as _) * format.aspect_bytes_per_block(aspect);
+
+    let rows = size.1.div_ceil(block_height as _);
+
+    Ok(row_pitch * rows * size.2 * subresource.array_layer_count as usize)
+}
+
+///The resolve mode and view of an attachment's resolve target, or none when it has no target.
+fn resolve_target(
+    qualifiers: &[Qualifier],
+    resources: &DeviceResources,
+    attachment: &InternalImage,
+    resolve: Option<ImageToken<'_, RenderTarget>>,
+) -> Result<(vk::ResolveModeFlags, vk::ImageView)> {
+    let Some(resolve) = resolve else {
+        return Ok((vk::ResolveModeFlags::NONE, vk::ImageView::null()));
+    };
+
+    let Qualifier::Image(resolve_handle, _, _, _) = qualifiers
+        .get(resolve.index)
+        .ok_or(Error::InvalidResource)?
+    else {
+        Err(Error::InvalidResource)?
+    };
+
+    let resolve_image = resources
+        .images
+        .get(*resolve_handle)
+        .ok_or(Error::ResourceNotFound)?;
+
+    if attachment.get_samples() == vk::SampleCountFlags::TYPE_1
+        || resolve_image.get_samples() != vk::SampleCountFlags::TYPE_1
+    {
+        Err(Error::InvalidAttachment)?
+    }
+
+    let resolve_mode = if attachment.get_format().is_depth_or_stencil() {
+        vk::ResolveModeFlags::SAMPLE_ZERO
+    } else {
+        vk::ResolveModeFlags::AVERAGE
+    };
+
+    Ok((resolve_mode, resolve_image.get_image_view()))
+}
+
 pub enum Barrier {
     Image {
         image: usize,
@@ -143,6 +230,7 @@ pub enum Barrier {
         src_access: Access,
         dst_access: Access,
         image_aspect: ImageAspect,
+        range: ImageRange,
     },
     Buffer {
         buffer: usize,
@@ -159,24 +247,31 @@ pub struct PipelineBarrier {
     pub barriers: Vec<Barrier>,
 }
 
+///The queue families a resource moves between.
+#[derive(Clone, Copy)]
+pub(crate) struct QueueTransfer {
+    pub(crate) src: u32,
+    pub(crate) dst: u32,
+}
+
 pub struct PushConstant<'a, T> {
     pub data: T,
     pub pipeline: &'a Pipeline,
 }
 
-pub struct BindIndexBuffer {
-    pub buffer: usize,
+pub struct BindIndexBuffer<'id> {
+    pub buffer: BufferToken<'id, ReadOnly>,
     pub offset: usize,
 }
 
-pub struct BufferWrite<'a, T: Copy> {
-    pub buffer: usize,
+pub struct BufferWrite<'a, 'id, T: Copy> {
+    pub buffer: BufferToken<'id, WriteOnly>,
     pub offset: usize,
     pub src: &'a [T],
 }
 
-pub struct BufferRead {
-    pub buffer: usize,
+pub struct BufferRead<'id> {
+    pub buffer: BufferToken<'id, ReadOnly>,
     pub offset: usize,
     pub size: usize,
 }
@@ -189,26 +284,38 @@ pub struct Region {
 }
 
 #[derive(Clone)]
-pub struct BufferCopy {
-    pub from: usize,
-    pub to: usize,
+pub struct BufferCopy<'id> {
+    pub from: BufferToken<'id, ReadOnly>,
+    pub to: BufferToken<'id, WriteOnly>,
     pub regions: Vec<Region>,
 }
 
-pub struct ImageCopy {
-    pub from: usize,
-    pub to: usize,
+pub struct ImageCopy<'id> {
+    pub from: BufferToken<'id, ReadOnly>,
+    pub to: ImageToken<'id, WriteOnly>,
     pub src: usize,
     pub dst: (usize, usize, usize),
     pub size: (usize, usize, usize),
+    ///Has to lie within the range the image was declared with.
+    pub subresource: ImageSubresource,
+}
+
+///Resolves a multisampled image into a single sample image of the same format.
+pub struct ImageResolve<'id> {
+    pub from: ImageToken<'id, ReadOnly>,
+    pub to: ImageToken<'id, WriteOnly>,
+    ///Has to lie within the range both images were declared with.
+    pub subresource: ImageSubresource,
 }
 
-pub struct BufferImageCopy {
-    pub from: usize,
-    pub to: usize,
+pub struct BufferImageCopy<'id> {
+    pub from: ImageToken<'id, ReadOnly>,
+    pub to: BufferToken<'id, WriteOnly>,
     pub src: (usize, usize, usize),
     pub dst: usize,
     pub size: (usize, usize, usize),
+    ///Has to lie within the range the image was declared with.
+    pub subresource: ImageSubresource,
 }
 
 pub struct Draw {
@@ -218,8 +325,8 @@ pub struct Draw {
     pub first_instance: usize,
 }
 
-pub struct DrawIndirect {
-    pub buffer: usize,
+pub struct DrawIndirect<'id> {
+    pub buffer: BufferToken<'id, ReadOnly>,
     pub offset: usize,
     pub draw_count: usize,
     pub stride: usize,
@@ -241,7 +348,13 @@ pub enum WriteBinding {
         range: usize,
         offset: usize,
     },
+    ///A storage image, in the `General` layout.
     Image(Image),
+    ///An image declared with a read only shader access, in the `ReadOnlyOptimal` layout.
+    SampledImage(Image),
+    Sampler(Sampler),
+    ///Like `SampledImage`, together with the sampler that reads it.
+    CombinedImageSampler(Image, Sampler),
 }
 
 #[repr(C)]
@@ -258,9 +371,9 @@ pub struct DrawIndexed {
     pub index_count: usize,
 }
 
-pub struct Render {
-    pub color: Vec<Attachment>,
-    pub depth: Option<Attachment>,
+pub struct Render<'id> {
+    pub color: Vec<Attachment<'id>>,
+    pub depth: Option<Attachment<'id>>,
     pub use_stencil: bool,
     pub render_area: RenderArea,
 }
@@ -302,20 +415,13 @@ impl From<LoadOp> for vk::AttachmentLoadOp {
     }
 }
 
-pub struct Attachment {
-    pub image: usize,
+pub struct Attachment<'id> {
+    pub image: ImageToken<'id, RenderTarget>,
     pub load_op: LoadOp,
     pub clear: Clear,
-}
-
-impl Default for Attachment {
-    fn default() -> Self {
-        Self {
-            image: usize::MAX,
-            load_op: Default::default(),
-            clear: Default::default(),
-        }
-    }
+    ///A single sample image the multisampled `image` is resolved into when rendering ends.
+    ///Declared like `image`. Color is averaged, depth takes the first sample.
+    pub resolve: Option<ImageToken<'id, RenderTarget>>,
 }
 
 #[derive(Default)]
@@ -326,7 +432,7 @@ pub struct RenderArea {
     pub height: u32,
 }
 
-impl Commands<'_> {
+impl<'id> Commands<'_, 'id> {
     ///This tells the render graph to send the tasks to the GPU.
     ///Without it, nothing will happen.
     pub fn submit(&mut self, submit: Submit) -> Result<()> {
@@ -467,7 +573,13 @@ impl Commands<'_> {
 
     ///Stops everything in the queue and writes to a buffer. If you use this in the middle of a render graph,
     ///it can create "bubbles" in the pipeline; essentially, instances where the gpu is waiting for work to be done.
-    pub fn write_buffer<T: Copy>(&mut self, write: BufferWrite<T>) -> Result<()> {
+    pub fn write_buffer<T: Copy>(&mut self, write: BufferWrite<'_, 'id, T>) -> Result<()> {
+        self.validate_buffer(
+            write.buffer.index,
+            &[BufferAccess::HostTransferWrite],
+            "write_buffer",
+        )?;
+
         let Commands {
             device,
             qualifiers,
@@ -489,7 +601,10 @@ impl Commands<'_> {
 
         let resources = resources.lock().unwrap();
 
-        let Qualifier::Buffer(buffer_handle, _) = qualifiers.get(buffer).ok_or(Error::ResourceNotFound)? else {
+        let Qualifier::Buffer(buffer_handle, _, _) = qualifiers
+            .get(buffer.index)
+            .ok_or(Error::ResourceNotFound)?
+        else {
             Err(Error::InvalidResource)?
         };
 
@@ -518,7 +633,13 @@ impl Commands<'_> {
 
     ///Stops everything in the queue and reads from a buffer. If you use this in the middle of a render graph,
     ///it can create "bubbles" in the pipeline; essentially, instances where the gpu is waiting for work to be done.
-    pub fn read_buffer(&mut self, read: BufferRead) -> Result<Vec<u8>> {
+    pub fn read_buffer(&mut self, read: BufferRead<'id>) -> Result<Vec<u8>> {
+        self.validate_buffer(
+            read.buffer.index,
+            &[BufferAccess::HostTransferRead],
+            "read_buffer",
+        )?;
+
         let Commands {
             device,
             qualifiers,
@@ -540,7 +661,10 @@ impl Commands<'_> {
 
         let resources = resources.lock().unwrap();
 
-        let Qualifier::Buffer(buffer_handle, _) = qualifiers.get(buffer).ok_or(Error::ResourceNotFound)? else {
+        let Qualifier::Buffer(buffer_handle, _, _) = qualifiers
+            .get(buffer.index)
+            .ok_or(Error::ResourceNotFound)?
+        else {
             Err(Error::InvalidResource)?
         };
 
@@ -615,7 +739,18 @@ impl Commands<'_> {
     }
 
     ///Copy from one buffer to another.
-    pub fn copy_buffer_to_buffer(&mut self, copy: BufferCopy) -> Result<()> {
+    pub fn copy_buffer_to_buffer(&mut self, copy: BufferCopy<'id>) -> Result<()> {
+        self.validate_buffer(
+            copy.from.index,
+            &[BufferAccess::TransferRead],
+            "copy_buffer_to_buffer",
+        )?;
+        self.validate_buffer(
+            copy.to.index,
+            &[BufferAccess::TransferWrite],
+            "copy_buffer_to_buffer",
+        )?;
+
         let Commands {
             device,
             qualifiers,
@@ -633,7 +768,9 @@ impl Commands<'_> {
 
         let resources = resources.lock().unwrap();
 
-        let Qualifier::Buffer(from_buffer_handle, _) = qualifiers.get(from).ok_or(Error::InvalidResource)? else {
+        let Qualifier::Buffer(from_buffer_handle, _, _) =
+            qualifiers.get(from.index).ok_or(Error::InvalidResource)?
+        else {
             Err(Error::InvalidResource)?
         };
 
@@ -645,7 +782,9 @@ impl Commands<'_> {
             .get(*from_buffer_handle)
             .ok_or(Error::ResourceNotFound)?;
 
-        let Qualifier::Buffer(to_buffer_handle, _) = qualifiers.get(to).ok_or(Error::InvalidResource)? else {
+        let Qualifier::Buffer(to_buffer_handle, _, _) =
+            qualifiers.get(to.index).ok_or(Error::InvalidResource)?
+        else {
             Err(Error::InvalidResource)?
         };
 
@@ -673,7 +812,19 @@ impl Commands<'_> {
     }
 
     ///Copy from one buffer to an image.
-    pub fn copy_buffer_to_image(&mut self, copy: ImageCopy) -> Result<()> {
+    pub fn copy_buffer_to_image(&mut self, copy: ImageCopy<'id>) -> Result<()> {
+        self.validate_buffer(
+            copy.from.index,
+            &[BufferAccess::TransferRead],
+            "copy_buffer_to_image",
+        )?;
+        self.validate_image(
+            copy.to.index,
+            &[ImageAccess::TransferWrite],
+            "copy_buffer_to_image",
+        )?;
+        self.validate_subresource(copy.to.index, copy.subresource, "copy_buffer_to_image")?;
+
         let Commands {
             device,
             qualifiers,
@@ -693,23 +844,29 @@ impl Commands<'_> {
             src,
             dst,
             size,
+            subresource,
         } = copy;
 
         let resources = resources.lock().unwrap();
 
-        let Qualifier::Buffer(from_buffer_handle, _) = qualifiers.get(from).ok_or(Error::InvalidResource)? else {
+        let Qualifier::Buffer(from_buffer_handle, _, _) =
+            qualifiers.get(from.index).ok_or(Error::InvalidResource)?
+        else {
             Err(Error::InvalidResource)?
         };
 
         let InternalBuffer {
             buffer: from_buffer,
+            size: buffer_size,
             ..
         } = resources
             .buffers
             .get(*from_buffer_handle)
             .ok_or(Error::ResourceNotFound)?;
 
-        let Qualifier::Image(to_image_handle, to_image_access, image_aspect) = qualifiers.get(to).ok_or(Error::InvalidResource)? else {
+        let Qualifier::Image(to_image_handle, to_image_access, image_aspect, _) =
+            qualifiers.get(to.index).ok_or(Error::InvalidResource)?
+        else {
             Err(Error::InvalidResource)?
         };
 
@@ -725,6 +882,35 @@ impl Commands<'_> {
             .ok_or(Error::ResourceNotFound)?
             .get_format();
 
+        let mip_extent = mipmap::mip_extent(
+            resources
+                .images
+                .get(*to_image_handle)
+                .ok_or(Error::ResourceNotFound)?
+                .get_extent(),
+            subresource.mip_level,
+        );
+
+        //Views are relative to the part of their image they cover.
+        let subresource = resources
+            .images
+            .get(*to_image_handle)
+            .ok_or(Error::ResourceNotFound)?
+            .subresource_to_parent(subresource);
+
+        let copy_size = buffer_image_copy_size(
+            to_image_format,
+            *image_aspect,
+            dst,
+            size,
+            mip_extent,
+            subresource,
+        )?;
+
+        if src + copy_size > *buffer_size {
+            Err(Error::InvalidResource)?
+        }
+
         let regions = [vk::BufferImageCopy {
             buffer_offset: src as _,
             image_offset: vk::Offset3D {
@@ -739,9 +925,9 @@ impl Commands<'_> {
             },
             image_subresource: vk::ImageSubresourceLayers {
                 aspect_mask: (*image_aspect).into(),
-                mip_level: 0,
-                base_array_layer: 0,
-                layer_count: 1,
+                mip_level: subresource.mip_level,
+                base_array_layer: subresource.base_array_layer,
+                layer_count: subresource.array_layer_count,
             },
             ..Default::default()
         }];
@@ -760,7 +946,19 @@ impl Commands<'_> {
     }
 
     ///Copy from an image to a buffer.
-    pub fn copy_image_to_buffer(&mut self, copy: BufferImageCopy) -> Result<()> {
+    pub fn copy_image_to_buffer(&mut self, copy: BufferImageCopy<'id>) -> Result<()> {
+        self.validate_image(
+            copy.from.index,
+            &[ImageAccess::TransferRead],
+            "copy_image_to_buffer",
+        )?;
+        self.validate_subresource(copy.from.index, copy.subresource, "copy_image_to_buffer")?;
+        self.validate_buffer(
+            copy.to.index,
+            &[BufferAccess::TransferWrite],
+            "copy_image_to_buffer",
+        )?;
+
         let Commands {
             device,
             qualifiers,
@@ -780,23 +978,29 @@ impl Commands<'_> {
             src,
             dst,
             size,
+            subresource,
         } = copy;
 
         let resources = resources.lock().unwrap();
 
-        let Qualifier::Buffer(from_buffer_handle, _) = qualifiers.get(to).ok_or(Error::InvalidResource)? else {
+        let Qualifier::Buffer(from_buffer_handle, _, _) =
+            qualifiers.get(to.index).ok_or(Error::InvalidResource)?
+        else {
             Err(Error::InvalidResource)?
         };
 
         let InternalBuffer {
             buffer: from_buffer,
+            size: buffer_size,
             ..
         } = resources
             .buffers
             .get(*from_buffer_handle)
             .ok_or(Error::ResourceNotFound)?;
 
-        let Qualifier::Image(to_image_handle, to_image_access, image_aspect) = qualifiers.get(from).ok_or(Error::InvalidResource)? else {
+        let Qualifier::Image(to_image_handle, to_image_access, image_aspect, _) =
+            qualifiers.get(from.index).ok_or(Error::InvalidResource)?
+        else {
             Err(Error::InvalidResource)?
         };
 
@@ -812,6 +1016,35 @@ impl Commands<'_> {
             .ok_or(Error::ResourceNotFound)?
             .get_format();
 
+        let mip_extent = mipmap::mip_extent(
+            resources
+                .images
+                .get(*to_image_handle)
+                .ok_or(Error::ResourceNotFound)?
+                .get_extent(),
+            subresource.mip_level,
+        );
+
+        //Views are relative to the part of their image they cover.
+        let subresource = resources
+            .images
+            .get(*to_image_handle)
+            .ok_or(Error::ResourceNotFound)?
+            .subresource_to_parent(subresource);
+
+        let copy_size = buffer_image_copy_size(
+            to_image_format,
+            *image_aspect,
+            src,
+            size,
+            mip_extent,
+            subresource,
+        )?;
+
+        if dst + copy_size > *buffer_size {
+            Err(Error::InvalidResource)?
+        }
+
         let regions = [vk::BufferImageCopy {
             buffer_offset: dst as _,
             image_offset: vk::Offset3D {
@@ -826,9 +1059,9 @@ impl Commands<'_> {
             },
             image_subresource: vk::ImageSubresourceLayers {
                 aspect_mask: (*image_aspect).into(),
-                mip_level: 0,
-                base_array_layer: 0,
-                layer_count: 1,
+                mip_level: subresource.mip_level,
+                base_array_layer: subresource.base_array_layer,
+                layer_count: subresource.array_layer_count,
             },
             ..Default::default()
         }];
@@ -846,9 +1079,249 @@ impl Commands<'_> {
         Ok(())
     }
 
+    ///Resolve a multisampled image into a single sample image of the same format.
+    ///The whole mip level is resolved, up to the size of the smaller image.
+    pub fn resolve_image(&mut self, resolve: ImageResolve<'id>) -> Result<()> {
+        self.validate_image(
+            resolve.from.index,
+            &[ImageAccess::TransferRead],
+            "resolve_image",
+        )?;
+        self.validate_image(
+            resolve.to.index,
+            &[ImageAccess::TransferWrite],
+            "resolve_image",
+        )?;
+        self.validate_subresource(resolve.from.index, resolve.subresource, "resolve_image")?;
+        self.validate_subresource(resolve.to.index, resolve.subresource, "resolve_image")?;
+
+        let Commands {
+            device,
+            qualifiers,
+            command_buffer,
+            ..
+        } = self;
+
+        let DeviceInner {
+            logical_device,
+            resources,
+            ..
+        } = &*device;
+
+        let ImageResolve {
+            from,
+            to,
+            subresource,
+        } = resolve;
+
+        let resources = resources.lock().unwrap();
+
+        let Qualifier::Image(from_image_handle, from_image_access, _, _) =
+            qualifiers.get(from.index).ok_or(Error::InvalidResource)?
+        else {
+            Err(Error::InvalidResource)?
+        };
+
+        let Qualifier::Image(to_image_handle, to_image_access, _, _) =
+            qualifiers.get(to.index).ok_or(Error::InvalidResource)?
+        else {
+            Err(Error::InvalidResource)?
+        };
+
+        let from_image = resources
+            .images
+            .get(*from_image_handle)
+            .ok_or(Error::ResourceNotFound)?;
+
+        let to_image = resources
+            .images
+            .get(*to_image_handle)
+            .ok_or(Error::ResourceNotFound)?;
+
+        if from_image.get_samples() == vk::SampleCountFlags::TYPE_1
+            || to_image.get_samples() != vk::SampleCountFlags::TYPE_1
+            || from_image.get_format() != to_image.get_format()
+        {
+            Err(Error::InvalidResource)?
+        }
+
+        let from_extent = mipmap::mip_extent(from_image.get_extent(), subresource.mip_level);
+        let to_extent = mipmap::mip_extent(to_image.get_extent(), subresource.mip_level);
+
+        let layers = |image: &InternalImage| {
+            //Views are relative to the part of their image they cover.
+            let subresource = image.subresource_to_parent(subresource);
+
+            vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: subresource.mip_level,
+                base_array_layer: subresource.base_array_layer,
+                layer_count: subresource.array_layer_count,
+            }
+        };
+
+        let regions = [vk::ImageResolve {
+            src_subresource: layers(from_image),
+            src_offset: vk::Offset3D::default(),
+            dst_subresource: layers(to_image),
+            dst_offset: vk::Offset3D::default(),
+            extent: vk::Extent3D {
+                width: from_extent.width.min(to_extent.width),
+                height: from_extent.height.min(to_extent.height),
+                depth: 1,
+            },
+        }];
+
+        unsafe {
+            logical_device.cmd_resolve_image(
+                **command_buffer,
+                from_image.get_image(),
+                ImageLayout::from(*from_image_access).into(),
+                to_image.get_image(),
+                ImageLayout::from(*to_image_access).into(),
+                &regions,
+            );
+        }
+
+        Ok(())
+    }
+
+    ///Fill every mip level of an image from the one before it, starting with the first.
+    ///The image must be declared `TransferWrite` over all of its mip levels and array layers,
+    ///and every level is back in that layout afterwards. Views of an image cannot generate its mipmaps.
+    ///Levels are blitted with linear filtering when the format allows it and the image has
+    ///`ImageUsage::TRANSFER_SRC | ImageUsage::TRANSFER_DST`.
+    ///Otherwise a built in compute shader averages them, which needs `ImageUsage::STORAGE`
+    ///and replaces the bound pipeline, so set your pipeline again before dispatching.
+    pub fn generate_mipmaps(&mut self, image: ImageToken<'id, WriteOnly>) -> Result<()> {
+        self.validate_image(
+            image.index,
+            &[ImageAccess::TransferWrite],
+            "generate_mipmaps",
+        )?;
+        self.validate_whole_image(image.index, "generate_mipmaps")?;
+
+        let Commands {
+            device,
+            qualifiers,
+            command_buffer,
+            ..
+        } = self;
+
+        let DeviceInner {
+            context,
+            physical_device,
+            logical_device,
+            resources,
+            mipmaps,
+            ..
+        } = &*device;
+
+        let resources = resources.lock().unwrap();
+
+        let Qualifier::Image(image_handle, _, _, _) =
+            qualifiers.get(image.index).ok_or(Error::InvalidResource)?
+        else {
+            Err(Error::InvalidResource)?
+        };
+
+        let internal_image = resources
+            .images
+            .get(*image_handle)
+            .ok_or(Error::ResourceNotFound)?;
+
+        //Views have to generate the mipmaps of their image instead.
+        if internal_image.get_parent().is_some() {
+            Err(Error::InvalidResource)?
+        }
+
+        let format = internal_image.get_format();
+        let extent = internal_image.get_extent();
+        let mip_levels = internal_image.get_mip_levels();
+        let array_layers = internal_image.get_array_layers();
+
+        if mip_levels < 2 {
+            return Ok(());
+        }
+
+        let format_properties = unsafe {
+            context
+                .instance
+                .get_physical_device_format_properties(*physical_device, format.into())
+        };
+
+        let blit = vk::FormatFeatureFlags::BLIT_SRC
+            | vk::FormatFeatureFlags::BLIT_DST
+            | vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR;
+
+        let features = format_properties.optimal_tiling_features;
+
+        let usage = internal_image.get_usage();
+
+        let transfer = vk::ImageUsageFlags::TRANSFER_SRC | vk::ImageUsageFlags::TRANSFER_DST;
+
+        if features.contains(blit) && usage.contains(transfer) {
+            mipmap::record_blits(
+                logical_device,
+                **command_buffer,
+                internal_image.get_image(),
+                extent,
+                mip_levels,
+                array_layers,
+            );
+        } else if features.contains(vk::FormatFeatureFlags::STORAGE_IMAGE)
+            && usage.contains(vk::ImageUsageFlags::STORAGE)
+            && extent.depth == 1
+        {
+            mipmaps.lock().unwrap().record_downsample(
+                logical_device,
+                **command_buffer,
+                *image_handle,
+                internal_image,
+            )?;
+        } else {
+            Err(Error::UnsupportedMipmapFormat)?
+        }
+
+        Ok(())
+    }
+
     ///Tell the GPU we would like to start rendering.
     ///After this command, set your pipeline and push constant, then draw.
-    pub fn start_rendering(&mut self, render: Render) -> Result<()> {
+    pub fn start_rendering(&mut self, render: Render<'id>) -> Result<()> {
+        for color in &render.color {
+            for image in iter::once(color.image).chain(color.resolve) {
+                self.validate_image(
+                    image.index,
+                    &[ImageAccess::ColorAttachment],
+                    "start_rendering",
+                )?;
+            }
+        }
+
+        if let Some(depth) = &render.depth {
+            self.validate_image(
+                depth.image.index,
+                &[
+                    ImageAccess::DepthAttachment,
+                    ImageAccess::DepthAttachmentReadOnly,
+                    ImageAccess::DepthStencilAttachment,
+                ],
+                "start_rendering",
+            )?;
+
+            if let Some(resolve) = depth.resolve {
+                self.validate_image(
+                    resolve.index,
+                    &[
+                        ImageAccess::DepthAttachment,
+                        ImageAccess::DepthStencilAttachment,
+                    ],
+                    "start_rendering",
+                )?;
+            }
+        }
+
         let Commands {
             device,
             qualifiers,
@@ -874,15 +1347,22 @@ impl Commands<'_> {
         let mut color_rendering_attachment_infos = vec![Default::default(); color.len()];
 
         for (i, color) in color.iter().enumerate() {
-            let Qualifier::Image(color_handle, _, _) = qualifiers.get(color.image).ok_or(Error::InvalidResource)? else {
+            let Qualifier::Image(color_handle, _, _, _) = qualifiers
+                .get(color.image.index)
+                .ok_or(Error::InvalidResource)?
+            else {
                 Err(Error::InvalidResource)?
             };
 
-            let image_view = resources
+            let internal_image = resources
                 .images
                 .get(*color_handle)
-                .ok_or(Error::ResourceNotFound)?
-                .get_image_view();
+                .ok_or(Error::ResourceNotFound)?;
+
+            let image_view = internal_image.get_image_view();
+
+            let (resolve_mode, resolve_image_view) =
+                resolve_target(qualifiers, &resources, internal_image, color.resolve)?;
 
             let Clear::Color(clear_r, clear_g, clear_b, clear_a) = color.clear else {
                 Err(Error::InvalidAttachment)?
@@ -897,6 +1377,9 @@ impl Commands<'_> {
             color_rendering_attachment_infos[i] = vk::RenderingAttachmentInfoKHR {
                 image_view,
                 image_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                resolve_mode,
+                resolve_image_view,
+                resolve_image_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
                 load_op: color.load_op.into(),
                 store_op: vk::AttachmentStoreOp::STORE,
                 clear_value,
@@ -905,15 +1388,22 @@ impl Commands<'_> {
         }
 
         let depth_rendering_attachment_info = if let Some(depth) = depth {
-            let Qualifier::Image(depth_handle, _, _) = qualifiers.get(depth.image).ok_or(Error::InvalidResource)? else {
+            let Qualifier::Image(depth_handle, _, _, _) = qualifiers
+                .get(depth.image.index)
+                .ok_or(Error::InvalidResource)?
+            else {
                 Err(Error::InvalidResource)?
             };
 
-            let image_view = resources
+            let internal_image = resources
                 .images
                 .get(*depth_handle)
-                .ok_or(Error::ResourceNotFound)?
-                .get_image_view();
+                .ok_or(Error::ResourceNotFound)?;
+
+            let image_view = internal_image.get_image_view();
+
+            let (resolve_mode, resolve_image_view) =
+                resolve_target(qualifiers, &resources, internal_image, depth.resolve)?;
 
             let clear_value = match depth.clear {
                 Clear::Depth(clear_d) => vk::ClearValue {
@@ -934,6 +1424,9 @@ impl Commands<'_> {
             Some(vk::RenderingAttachmentInfoKHR {
                 image_view,
                 image_layout: vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
+                resolve_mode,
+                resolve_image_view,
+                resolve_image_layout: vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
                 load_op: depth.load_op.into(),
                 store_op: vk::AttachmentStoreOp::STORE,
                 clear_value,
@@ -1013,6 +1506,8 @@ impl Commands<'_> {
         pipeline: &Pipeline,
         bindings: Vec<WriteBinding>,
     ) -> Result<()> {
+        self.validate_bindings(&bindings)?;
+
         let Commands {
             device,
             command_buffer,
@@ -1054,6 +1549,12 @@ impl Commands<'_> {
                 WriteBinding::Image(image) => {
                     let internal_image =
                         resources.images.get(*image).ok_or(Error::InvalidResource)?;
+                    if !internal_image
+                        .get_usage()
+                        .contains(vk::ImageUsageFlags::STORAGE)
+                    {
+                        Err(Error::InvalidResource)?
+                    }
                     image_infos.insert(
                         i,
                         vk::DescriptorImageInfo {
@@ -1063,6 +1564,59 @@ impl Commands<'_> {
                         },
                     );
                 }
+                WriteBinding::SampledImage(image) => {
+                    let internal_image =
+                        resources.images.get(*image).ok_or(Error::InvalidResource)?;
+                    if !internal_image
+                        .get_usage()
+                        .contains(vk::ImageUsageFlags::SAMPLED)
+                    {
+                        Err(Error::InvalidResource)?
+                    }
+                    image_infos.insert(
+                        i,
+                        vk::DescriptorImageInfo {
+                            sampler: vk::Sampler::null(),
+                            image_view: internal_image.get_image_view(),
+                            image_layout: vk::ImageLayout::READ_ONLY_OPTIMAL,
+                        },
+                    );
+                }
+                WriteBinding::Sampler(sampler) => {
+                    let internal_sampler = resources
+                        .samplers
+                        .get(*sampler)
+                        .ok_or(Error::InvalidResource)?;
+                    image_infos.insert(
+                        i,
+                        vk::DescriptorImageInfo {
+                            sampler: internal_sampler.sampler,
+                            ..Default::default()
+                        },
+                    );
+                }
+                WriteBinding::CombinedImageSampler(image, sampler) => {
+                    let internal_image =
+                        resources.images.get(*image).ok_or(Error::InvalidResource)?;
+                    if !internal_image
+                        .get_usage()
+                        .contains(vk::ImageUsageFlags::SAMPLED)
+                    {
+                        Err(Error::InvalidResource)?
+                    }
+                    let internal_sampler = resources
+                        .samplers
+                        .get(*sampler)
+                        .ok_or(Error::InvalidResource)?;
+                    image_infos.insert(
+                        i,
+                        vk::DescriptorImageInfo {
+                            sampler: internal_sampler.sampler,
+                            image_view: internal_image.get_image_view(),
+                            image_layout: vk::ImageLayout::READ_ONLY_OPTIMAL,
+                        },
+                    );
+                }
             }
         }
 
@@ -1088,6 +1642,11 @@ impl Commands<'_> {
                     descriptor_type: match binding {
                         WriteBinding::Buffer { .. } => vk::DescriptorType::STORAGE_BUFFER,
                         WriteBinding::Image(_) => vk::DescriptorType::STORAGE_IMAGE,
+                        WriteBinding::SampledImage(_) => vk::DescriptorType::SAMPLED_IMAGE,
+                        WriteBinding::Sampler(_) => vk::DescriptorType::SAMPLER,
+                        WriteBinding::CombinedImageSampler(..) => {
+                            vk::DescriptorType::COMBINED_IMAGE_SAMPLER
+                        }
                     },
                     p_buffer_info: buffer,
                     p_image_info: image,
@@ -1152,7 +1711,14 @@ impl Commands<'_> {
         Ok(())
     }
 
-    pub fn draw_indirect(&mut self, draw_indirect: DrawIndirect) -> Result<()> {
+    ///The buffer is declared `BufferAccess::IndirectRead`, or `BufferAccess::ShaderReadOnly`.
+    pub fn draw_indirect(&mut self, draw_indirect: DrawIndirect<'id>) -> Result<()> {
+        self.validate_buffer(
+            draw_indirect.buffer.index,
+            &[BufferAccess::IndirectRead, BufferAccess::ShaderReadOnly],
+            "draw_indirect",
+        )?;
+
         let Commands {
             device,
             command_buffer,
@@ -1173,7 +1739,9 @@ impl Commands<'_> {
             stride,
         } = draw_indirect;
 
-        let Qualifier::Buffer(buffer_handle, _) = qualifiers.get(buffer).ok_or(Error::InvalidResource)? else {
+        let Qualifier::Buffer(buffer_handle, _, _) =
+            qualifiers.get(buffer.index).ok_or(Error::InvalidResource)?
+        else {
             Err(Error::InvalidResource)?
         };
 
@@ -1198,7 +1766,14 @@ impl Commands<'_> {
         Ok(())
     }
 
-    pub fn draw_indexed_indirect(&mut self, draw_indirect: DrawIndirect) -> Result<()> {
+    ///The buffer is declared `BufferAccess::IndirectRead`, or `BufferAccess::ShaderReadOnly`.
+    pub fn draw_indexed_indirect(&mut self, draw_indirect: DrawIndirect<'id>) -> Result<()> {
+        self.validate_buffer(
+            draw_indirect.buffer.index,
+            &[BufferAccess::IndirectRead, BufferAccess::ShaderReadOnly],
+            "draw_indexed_indirect",
+        )?;
+
         let Commands {
             device,
             command_buffer,
@@ -1219,7 +1794,9 @@ impl Commands<'_> {
             stride,
         } = draw_indirect;
 
-        let Qualifier::Buffer(buffer_handle, _) = qualifiers.get(buffer).ok_or(Error::InvalidResource)? else {
+        let Qualifier::Buffer(buffer_handle, _, _) =
+            qualifiers.get(buffer.index).ok_or(Error::InvalidResource)?
+        else {
             Err(Error::InvalidResource)?
         };
 
@@ -1276,6 +1853,23 @@ impl Commands<'_> {
 
     ///Sets-up synchronization of commands in the same task.
     pub fn pipeline_barrier(&mut self, pipeline_barrier: PipelineBarrier) -> Result<()> {
+        self.record_barrier(pipeline_barrier, None)
+    }
+
+    ///Records one half of a queue family ownership transfer of the barriers' resources.
+    pub(crate) fn queue_transfer_barrier(
+        &mut self,
+        pipeline_barrier: PipelineBarrier,
+        queue_transfer: QueueTransfer,
+    ) -> Result<()> {
+        self.record_barrier(pipeline_barrier, Some(queue_transfer))
+    }
+
+    fn record_barrier(
+        &mut self,
+        pipeline_barrier: PipelineBarrier,
+        queue_transfer: Option<QueueTransfer>,
+    ) -> Result<()> {
         let PipelineBarrier {
             src_stage,
             dst_stage,
@@ -1297,6 +1891,14 @@ impl Commands<'_> {
 
         let resources = resources.lock().unwrap();
 
+        let QueueTransfer {
+            src: src_queue_family_index,
+            dst: dst_queue_family_index,
+        } = queue_transfer.unwrap_or(QueueTransfer {
+            src: vk::QUEUE_FAMILY_IGNORED,
+            dst: vk::QUEUE_FAMILY_IGNORED,
+        });
+
         let memory_barriers = vec![];
 
         let mut buffer_barriers = vec![];
@@ -1312,22 +1914,26 @@ impl Commands<'_> {
                     src_access,
                     dst_access,
                     image_aspect,
+                    range,
                 } => {
-                    let Qualifier::Image(image_handle, image_access, image_aspect) = qualifiers.get(image).ok_or(Error::InvalidResource)? else {
-                    Err(Error::InvalidResource)?
-                };
+                    let Qualifier::Image(image_handle, _, image_aspect, _) =
+                        qualifiers.get(image).ok_or(Error::InvalidResource)?
+                    else {
+                        Err(Error::InvalidResource)?
+                    };
 
-                    let image = resources
+                    let internal_image = resources
                         .images
                         .get(*image_handle)
-                        .ok_or(Error::ResourceNotFound)?
-                        .get_image();
+                        .ok_or(Error::ResourceNotFound)?;
 
-                    let format = resources
-                        .images
-                        .get(*image_handle)
-                        .ok_or(Error::ResourceNotFound)?
-                        .get_format();
+                    let image = internal_image.get_image();
+
+                    //Views are relative to the part of their image they cover.
+                    let range = internal_image.to_parent(range.resolve(
+                        internal_image.get_mip_levels(),
+                        internal_image.get_array_layers(),
+                    ));
 
                     let src_access_mask = stage_access_to_access((src_stage, src_access));
 
@@ -1339,10 +1945,7 @@ impl Commands<'_> {
 
                     let subresource_range = vk::ImageSubresourceRange {
                         aspect_mask: (*image_aspect).into(),
-                        base_mip_level: 0,
-                        level_count: 1,
-                        base_array_layer: 0,
-                        layer_count: 1,
+                        ..range.into()
                     };
 
                     image_barriers.push(vk::ImageMemoryBarrier {
@@ -1351,6 +1954,8 @@ impl Commands<'_> {
                         dst_access_mask,
                         old_layout,
                         new_layout,
+                        src_queue_family_index,
+                        dst_queue_family_index,
                         subresource_range,
                         ..Default::default()
                     });
@@ -1362,9 +1967,11 @@ impl Commands<'_> {
                     src_access,
                     dst_access,
                 } => {
-                    let Qualifier::Buffer(buffer_handle, _) = qualifiers.get(buffer).ok_or(Error::InvalidResource)? else {
-                    Err(Error::InvalidResource)?
-                };
+                    let Qualifier::Buffer(buffer_handle, _, _) =
+                        qualifiers.get(buffer).ok_or(Error::InvalidResource)?
+                    else {
+                        Err(Error::InvalidResource)?
+                    };
 
                     let buffer = resources
                         .buffers
@@ -1386,6 +1993,8 @@ impl Commands<'_> {
                         offset,
                         src_access_mask,
                         dst_access_mask,
+                        src_queue_family_index,
+                        dst_queue_family_index,
                         ..Default::default()
                     });
                 }
@@ -1406,4 +2015,188 @@ impl Commands<'_> {
 
         Ok(())
     }
+
+    ///Fails if validation is on and the buffer behind `index` was not declared with one of the `expected` accesses.
+    fn validate_buffer(
+        &self,
+        index: usize,
+        expected: &[BufferAccess],
+        operation: &str,
+    ) -> Result<()> {
+        if !self.validate {
+            return Ok(());
+        }
+
+        let resources = self.device.resources.lock().unwrap();
+
+        match self.qualifiers.get(index) {
+            Some(Qualifier::Buffer(_, access, _)) if expected.contains(access) => Ok(()),
+            Some(Qualifier::Buffer(buffer, access, _)) => Err(self.undeclared_access(
+                resources
+                    .buffers
+                    .get(*buffer)
+                    .map(|x| x.debug_name.as_str()),
+                format!("{operation} needs {expected:?}, but it was declared {access:?}"),
+            )),
+            _ => Err(self.undeclared_access(
+                None,
+                format!(
+                    "{operation} needs a buffer, but resource {index} is not a declared buffer"
+                ),
+            )),
+        }
+    }
+
+    ///Fails if validation is on and the image behind `index` was not declared with one of the `expected` accesses.
+    fn validate_image(
+        &self,
+        index: usize,
+        expected: &[ImageAccess],
+        operation: &str,
+    ) -> Result<()> {
+        if !self.validate {
+            return Ok(());
+        }
+
+        let resources = self.device.resources.lock().unwrap();
+
+        match self.qualifiers.get(index) {
+            Some(Qualifier::Image(_, access, _, _)) if expected.contains(access) => Ok(()),
+            Some(Qualifier::Image(image, access, _, _)) => Err(self.undeclared_access(
+                resources.images.get(*image).map(|x| x.get_debug_name()),
+                format!("{operation} needs {expected:?}, but it was declared {access:?}"),
+            )),
+            _ => Err(self.undeclared_access(
+                None,
+                format!("{operation} needs an image, but resource {index} is not a declared image"),
+            )),
+        }
+    }
+
+    ///Fails if validation is on and `subresource` lies outside the range the image behind `index` was declared with.
+    fn validate_subresource(
+        &self,
+        index: usize,
+        subresource: ImageSubresource,
+        operation: &str,
+    ) -> Result<()> {
+        if !self.validate {
+            return Ok(());
+        }
+
+        let resources = self.device.resources.lock().unwrap();
+
+        let Some(Qualifier::Image(image, _, _, range)) = self.qualifiers.get(index) else {
+            return Ok(());
+        };
+
+        let internal_image = resources
+            .images
+            .get(*image)
+            .ok_or(Error::ResourceNotFound)?;
+
+        let range = range.resolve(
+            internal_image.get_mip_levels(),
+            internal_image.get_array_layers(),
+        );
+
+        if subresource.within(range) {
+            return Ok(());
+        }
+
+        Err(self.undeclared_access(
+            Some(internal_image.get_debug_name()),
+            format!("{operation} addresses {subresource:?}, but only {range:?} was declared"),
+        ))
+    }
+
+    ///Fails if validation is on and the image behind `index` was not declared over all of its mip levels and layers.
+    fn validate_whole_image(&self, index: usize, operation: &str) -> Result<()> {
+        if !self.validate {
+            return Ok(());
+        }
+
+        let resources = self.device.resources.lock().unwrap();
+
+        let Some(Qualifier::Image(image, _, _, range)) = self.qualifiers.get(index) else {
+            return Ok(());
+        };
+
+        let internal_image = resources
+            .images
+            .get(*image)
+            .ok_or(Error::ResourceNotFound)?;
+
+        let mip_levels = internal_image.get_mip_levels();
+        let array_layers = internal_image.get_array_layers();
+
+        let range = range.resolve(mip_levels, array_layers);
+
+        if range == ImageRange::ALL.resolve(mip_levels, array_layers) {
+            return Ok(());
+        }
+
+        Err(self.undeclared_access(
+            Some(internal_image.get_debug_name()),
+            format!("{operation} needs every mip level and layer, but only {range:?} was declared"),
+        ))
+    }
+
+    ///Fails if validation is on and a binding refers to a resource the task did not declare,
+    ///or samples an image that was not declared with a read only layout.
+    fn validate_bindings(&self, bindings: &[WriteBinding]) -> Result<()> {
+        if !self.validate {
+            return Ok(());
+        }
+
+        let resources = self.device.resources.lock().unwrap();
+
+        for binding in bindings {
+            let (declared, debug_name, message) = match binding {
+                WriteBinding::Buffer { buffer, .. } => (
+                    self.qualifiers
+                        .iter()
+                        .any(|x| matches!(x, Qualifier::Buffer(b, _, _) if b == buffer)),
+                    resources
+                        .buffers
+                        .get(*buffer)
+                        .map(|x| x.debug_name.as_str()),
+                    "write_bindings binds it, but it was not declared",
+                ),
+                WriteBinding::Image(image) => (
+                    self.qualifiers
+                        .iter()
+                        .any(|x| matches!(x, Qualifier::Image(i, _, _, _) if i == image)),
+                    resources.images.get(*image).map(|x| x.get_debug_name()),
+                    "write_bindings binds it, but it was not declared",
+                ),
+                WriteBinding::SampledImage(image)
+                | WriteBinding::CombinedImageSampler(image, _) => (
+                    self.qualifiers.iter().any(|x| match x {
+                        Qualifier::Image(i, access, _, _) if i == image => {
+                            matches!(ImageLayout::from(*access), ImageLayout::ReadOnlyOptimal)
+                        }
+                        _ => false,
+                    }),
+                    resources.images.get(*image).map(|x| x.get_debug_name()),
+                    "write_bindings samples it, but it was not declared with a read only access",
+                ),
+                WriteBinding::Sampler(_) => continue,
+            };
+
+            if !declared {
+                Err(self.undeclared_access(debug_name, message.to_owned()))?
+            }
+        }
+
+        Ok(())
+    }
+
+    fn undeclared_access(&self, resource: Option<&str>, message: String) -> Error {
+        Error::UndeclaredAccess {
+            task: self.task.to_owned(),
+            resource: resource.unwrap_or("<unknown>").to_owned(),
+            message,
+        }
+    }
 }
@@ -1,3 +1,5 @@
+#[cfg(all(feature = "bindless"))]
+use crate::device::Bindless;
 use crate::device::DeviceInner;
 use crate::memory;
 use crate::prelude::*;
@@ -27,8 +29,15 @@ use raw_window_handle::{
 
 const API_VERSION: u32 = vk::make_api_version(0, 1, 3, 0);
 
+#[cfg(all(feature = "bindless"))]
+pub(crate) const SAMPLER_BINDING: u32 = 0;
+#[cfg(all(feature = "bindless"))]
+pub(crate) const SAMPLED_IMAGE_BINDING: u32 = 1;
+#[cfg(all(feature = "bindless"))]
 pub(crate) const SPECIAL_IMAGE_BINDING: u32 = 2;
+#[cfg(all(feature = "bindless"))]
 pub(crate) const SPECIAL_BUFFER_BINDING: u32 = 3;
+#[cfg(all(feature = "bindless"))]
 pub(crate) const DEVICE_ADDRESS_BUFFER_BINDING: u32 = 4;
 
 pub(crate) const DESCRIPTOR_COUNT: u32 = 200;
@@ -77,6 +86,10 @@ pub struct ContextInner {
     pub(crate) entry: Entry,
     pub(crate) instance: Instance,
     debug: Option<(ext::DebugUtils, vk::DebugUtilsMessengerEXT)>,
+    ///Used to label command buffer regions, if the extension is available.
+    pub(crate) debug_utils: Option<ext::DebugUtils>,
+    ///Set for headless contexts, whose swapchains present to surfaces without a window.
+    pub(crate) headless_surface: Option<ext::HeadlessSurface>,
 }
 
 pub struct ContextInfo<'a> {
@@ -86,6 +99,10 @@ pub struct ContextInfo<'a> {
     pub engine_name: &'a str,
     pub engine_version: Version,
     pub display: RawDisplayHandle,
+    ///Creates swapchains without a windowing system, using `VK_EXT_headless_surface`.
+    ///`display` is ignored, and so are the display and window of every swapchain.
+    ///Useful for testing presentation in CI, for example under lavapipe.
+    pub headless: bool,
 }
 
 impl Default for ContextInfo<'_> {
@@ -102,6 +119,7 @@ impl Default for ContextInfo<'_> {
             display: RawDisplayHandle::Xlib(XlibDisplayHandle::empty()),
             #[cfg(target_os = "android")]
             display: RawDisplayHandle::Android(AndroidDisplayHandle::empty()),
+            headless: false,
         }
     }
 }
@@ -158,8 +176,29 @@ impl Context {
         #[cfg(not(target_os = "android"))]
         extensions.push(ext::DebugUtils::name());
 
-        let surface_extension_names =
-            ash_window::enumerate_required_extensions(info.display).expect("Unsupported Surface");
+        //Needed for swapchains in color spaces other than sRGB, such as HDR10 and scRGB.
+        let swapchain_colorspace_supported = entry
+            .enumerate_instance_extension_properties(None)
+            .map_err(|_| Error::Creation)?
+            .iter()
+            .any(|properties| {
+                let name = unsafe { ffi::CStr::from_ptr(properties.extension_name.as_ptr()) };
+
+                name == vk::ExtSwapchainColorspaceFn::name()
+            });
+
+        if swapchain_colorspace_supported {
+            extensions.push(vk::ExtSwapchainColorspaceFn::name());
+        }
+
+        let surface_extension_names = if info.headless {
+            extensions.push(khr::Surface::name());
+            extensions.push(ext::HeadlessSurface::name());
+
+            &[]
+        } else {
+            ash_window::enumerate_required_extensions(info.display).expect("Unsupported Surface")
+        };
 
         let p_application_info = &application_info;
 
@@ -222,11 +261,23 @@ impl Context {
             None
         };
 
+        #[cfg(not(target_os = "android"))]
+        let debug_utils = Some(ext::DebugUtils::new(&entry, &instance));
+
+        #[cfg(target_os = "android")]
+        let debug_utils = None;
+
+        let headless_surface = info
+            .headless
+            .then(|| ext::HeadlessSurface::new(&entry, &instance));
+
         Ok(Self {
             inner: Arc::new(ContextInner {
                 entry,
                 instance,
                 debug,
+                debug_utils,
+                headless_surface,
             }),
         })
     }
@@ -237,11 +288,8 @@ impl Context {
             entry, instance, ..
         } = &*self.inner;
 
+        //Surfaces are created per swapchain, so any device that can draw is considered.
         let surface_loader = khr::Surface::new(&entry, &instance);
-        let surface_handle = unsafe {
-            ash_window::create_surface(&entry, &instance, info.display, info.window, None)
-        }
-        .map_err(|_| Error::CreateSurface)?;
 
         //SAFETY instance is initialized
         let mut physical_devices = unsafe { instance.enumerate_physical_devices() }
@@ -254,16 +302,8 @@ impl Context {
                     .find_map(|(index, info)| {
                         let graphics_support = info.queue_flags.contains(vk::QueueFlags::GRAPHICS);
                         let compute_support = info.queue_flags.contains(vk::QueueFlags::COMPUTE);
-                        let surface_support = unsafe {
-                            surface_loader.get_physical_device_surface_support(
-                                physical_device,
-                                index as u32,
-                                surface_handle,
-                            )
-                        }
-                        .ok()?;
 
-                        if graphics_support && compute_support && surface_support {
+                        if graphics_support && compute_support {
                             Some((physical_device, index))
                         } else {
                             None
@@ -297,7 +337,15 @@ impl Context {
 
         let queue_family_index = queue_family_index as u32;
 
-        let queue_family_indices = vec![queue_family_index];
+        let queue_family_properties =
+            unsafe { instance.get_physical_device_queue_family_properties(physical_device) };
+
+        let queue_family_indices =
+            queue_family_indices(&queue_family_properties, queue_family_index);
+
+        let mut unique_queue_family_indices = queue_family_indices.clone();
+        unique_queue_family_indices.sort();
+        unique_queue_family_indices.dedup();
 
         let mut layers = vec![];
 
@@ -370,33 +418,38 @@ impl Context {
             }
         };
 
+        let enabled_features = vk::PhysicalDeviceFeatures {
+            multi_draw_indirect: true as _,
+            ..info.features.into()
+        };
+
         let mut features = {
             #[cfg(all(feature = "bindless"))]
             let p_next = &mut dynamic_rendering_features as *mut _ as *mut _;
 
             vk::PhysicalDeviceFeatures2 {
                 p_next: &mut synchronization2_features as *mut _ as *mut _,
-                features: vk::PhysicalDeviceFeatures {
-                    multi_draw_indirect: true as _,
-                    ..info.features.into()
-                },
+                features: enabled_features,
                 ..Default::default()
             }
         };
 
         let priorities = [1.0];
 
-        let device_queue_create_infos = [{
-            let queue_count = 1;
-            let p_queue_priorities = priorities.as_ptr();
-
-            vk::DeviceQueueCreateInfo {
-                queue_family_index,
-                queue_count,
-                p_queue_priorities,
-                ..Default::default()
-            }
-        }];
+        let device_queue_create_infos = unique_queue_family_indices
+            .iter()
+            .map(|&queue_family_index| {
+                let queue_count = 1;
+                let p_queue_priorities = priorities.as_ptr();
+
+                vk::DeviceQueueCreateInfo {
+                    queue_family_index,
+                    queue_count,
+                    p_queue_priorities,
+                    ..Default::default()
+                }
+            })
+            .collect::<Vec<_>>();
 
         let enabled_layer_names = layers.iter().map(|s| s.as_ptr()).collect::<Vec<_>>();
         let enabled_layer_count = enabled_layer_names.len() as u32;
@@ -447,7 +500,7 @@ impl Context {
         let bindless = {
             let descriptor_set_layout_bindings = [
                 vk::DescriptorSetLayoutBinding {
-                    binding: 0,
+                    binding: SAMPLER_BINDING,
                     descriptor_type: vk::DescriptorType::SAMPLER,
                     descriptor_count: DESCRIPTOR_COUNT,
                     stage_flags: vk::ShaderStageFlags::VERTEX
@@ -456,7 +509,7 @@ impl Context {
                     ..Default::default()
                 },
                 vk::DescriptorSetLayoutBinding {
-                    binding: 1,
+                    binding: SAMPLED_IMAGE_BINDING,
                     descriptor_type: vk::DescriptorType::SAMPLED_IMAGE,
                     descriptor_count: DESCRIPTOR_COUNT,
                     stage_flags: vk::ShaderStageFlags::VERTEX
@@ -534,6 +587,25 @@ impl Context {
             }
             .map_err(|_| Error::CreateDescriptorSetLayout)?;
 
+            let descriptor_pool_sizes =
+                descriptor_set_layout_bindings.map(|binding| vk::DescriptorPoolSize {
+                    ty: binding.descriptor_type,
+                    descriptor_count: binding.descriptor_count,
+                });
+
+            let descriptor_pool_create_info = vk::DescriptorPoolCreateInfo {
+                flags: vk::DescriptorPoolCreateFlags::UPDATE_AFTER_BIND,
+                max_sets: 1,
+                pool_size_count: descriptor_pool_sizes.len() as _,
+                p_pool_sizes: descriptor_pool_sizes.as_ptr(),
+                ..Default::default()
+            };
+
+            let descriptor_pool = unsafe {
+                logical_device.create_descriptor_pool(&descriptor_pool_create_info, None)
+            }
+            .map_err(|_| Error::CreateDescriptorPool)?;
+
             let set_layouts = [descriptor_set_layout];
 
             let descriptor_set_allocate_info = {
@@ -648,15 +720,21 @@ impl Context {
             }
         };
 
-        let command_pool_create_info = vk::CommandPoolCreateInfo {
-            flags: vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER,
-            queue_family_index,
-            ..Default::default()
-        };
+        let mut command_pools = vec![];
+
+        for &queue_family_index in &queue_family_indices {
+            let command_pool_create_info = vk::CommandPoolCreateInfo {
+                flags: vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER,
+                queue_family_index,
+                ..Default::default()
+            };
 
-        let command_pool =
-            unsafe { logical_device.create_command_pool(&command_pool_create_info, None) }
-                .map_err(|_| Error::CreateCommandPool)?;
+            let command_pool =
+                unsafe { logical_device.create_command_pool(&command_pool_create_info, None) }
+                    .map_err(|_| Error::CreateCommandPool)?;
+
+            command_pools.push(command_pool);
+        }
 
         let resources = Mutex::new(DeviceResources::new());
 
@@ -665,13 +743,91 @@ impl Context {
                 #[cfg(all(feature = "bindless"))]
                 bindless,
                 context: self.inner.clone(),
-                surface: (surface_loader, surface_handle),
+                surface_loader,
                 physical_device,
                 logical_device,
                 queue_family_indices,
                 resources,
-                command_pool,
+                command_pools,
+                features: enabled_features,
+                mipmaps: Default::default(),
             }),
         })
     }
 }
+
+///The queue family of every `Queue`, indexed by it.
+///Prefers families that do nothing but the requested work, so that it runs asynchronously to graphics,
+///and falls back to the graphics family, or compute for transfers, when the device has none.
+fn queue_family_indices(
+    queue_family_properties: &[vk::QueueFamilyProperties],
+    graphics: u32,
+) -> Vec<u32> {
+    let find_dedicated_family = |required: vk::QueueFlags, excluded: vk::QueueFlags| {
+        queue_family_properties
+            .iter()
+            .position(|properties| {
+                properties.queue_flags.contains(required)
+                    && !properties.queue_flags.intersects(excluded)
+            })
+            .map(|index| index as u32)
+    };
+
+    let compute = find_dedicated_family(vk::QueueFlags::COMPUTE, vk::QueueFlags::GRAPHICS)
+        .unwrap_or(graphics);
+
+    let transfer = find_dedicated_family(
+        vk::QueueFlags::TRANSFER,
+        vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE,
+    )
+    .unwrap_or(compute);
+
+    vec![graphics, compute, transfer]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn families(flags: &[vk::QueueFlags]) -> Vec<vk::QueueFamilyProperties> {
+        flags
+            .iter()
+            .map(|&queue_flags| vk::QueueFamilyProperties {
+                queue_flags,
+                queue_count: 1,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    const ALL: vk::QueueFlags = vk::QueueFlags::from_raw(
+        vk::QueueFlags::GRAPHICS.as_raw()
+            | vk::QueueFlags::COMPUTE.as_raw()
+            | vk::QueueFlags::TRANSFER.as_raw(),
+    );
+
+    #[test]
+    fn dedicated_families_are_preferred() {
+        let properties = families(&[
+            ALL,
+            vk::QueueFlags::COMPUTE | vk::QueueFlags::TRANSFER,
+            vk::QueueFlags::TRANSFER,
+        ]);
+
+        assert_eq!(queue_family_indices(&properties, 0), [0, 1, 2]);
+    }
+
+    #[test]
+    fn transfers_fall_back_to_compute() {
+        let properties = families(&[ALL, vk::QueueFlags::COMPUTE | vk::QueueFlags::TRANSFER]);
+
+        assert_eq!(queue_family_indices(&properties, 0), [0, 1, 1]);
+    }
+
+    #[test]
+    fn without_dedicated_families_everything_runs_on_graphics() {
+        let properties = families(&[ALL, ALL]);
+
+        assert_eq!(queue_family_indices(&properties, 1), [1, 1, 1]);
+    }
+}
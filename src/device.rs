@@ -1,5 +1,7 @@
+use crate::capture::{self, Readback};
 use crate::context::ContextInner;
 use crate::memory;
+use crate::mipmap;
 use crate::pipeline::PipelineCompilerInner;
 use crate::prelude::*;
 use crate::renderpass::Framebuffer;
@@ -16,6 +18,7 @@ use std::mem;
 use std::ops;
 use std::os::raw;
 use std::ptr;
+use std::slice;
 use std::sync::{Arc, Mutex};
 
 use ash::extensions::{ext, khr};
@@ -26,17 +29,7 @@ use ash::{vk, Entry, Instance};
 
 use bitflags::bitflags;
 
-use raw_window_handle::AndroidDisplayHandle;
-use raw_window_handle::AndroidNdkWindowHandle;
-use raw_window_handle::{
-    HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle,
-};
-
-#[cfg(target_os = "windows")]
-use raw_window_handle::{Win32WindowHandle, WindowsDisplayHandle};
-
-#[cfg(target_os = "linux")]
-use raw_window_handle::{XlibDisplayHandle, XlibWindowHandle};
+use raw_window_handle::{RawDisplayHandle, RawWindowHandle};
 
 pub(crate) const MAX_FRAMES_IN_FLIGHT: usize = 3;
 
@@ -127,6 +120,7 @@ pub(crate) struct DeviceResources {
     pub(crate) swapchains: DeviceResource<InternalSwapchain, Swapchain>,
     pub(crate) binary_semaphores: DeviceResource<InternalSemaphore, BinarySemaphore>,
     pub(crate) timeline_semaphores: DeviceResource<InternalSemaphore, TimelineSemaphore>,
+    pub(crate) samplers: DeviceResource<InternalSampler, Sampler>,
 }
 
 impl DeviceResources {
@@ -137,6 +131,7 @@ impl DeviceResources {
             swapchains: DeviceResource::<InternalSwapchain, Swapchain>::new(),
             binary_semaphores: DeviceResource::<InternalSemaphore, BinarySemaphore>::new(),
             timeline_semaphores: DeviceResource::<InternalSemaphore, TimelineSemaphore>::new(),
+            samplers: DeviceResource::<InternalSampler, Sampler>::new(),
         }
     }
 }
@@ -151,286 +146,1687 @@ pub struct DeviceInner {
     pub(crate) resources: Mutex<DeviceResources>,
     pub(crate) physical_device: vk::PhysicalDevice,
     pub(crate) logical_device: ash::Device,
-    pub(crate) surface: (khr::Surface, vk::SurfaceKHR),
+    pub(crate) surface_loader: khr::Surface,
+    ///Indexed by `Queue`.
     pub(crate) queue_family_indices: Vec<u32>,
-    pub(crate) command_pool: vk::CommandPool,
+    ///Indexed by `Queue`.
+    pub(crate) command_pools: Vec<vk::CommandPool>,
+    ///The features the device was created with.
+    pub(crate) features: vk::PhysicalDeviceFeatures,
+    ///Used by `Commands::generate_mipmaps` for formats that cannot be blitted.
+    pub(crate) mipmaps: Mutex<Mipmaps>,
     #[cfg(all(feature = "bindless"))]
     pub(crate) bindless: Bindless,
 }
 
-#[cfg(all(feature = "bindless"))]
-pub struct Bindless {
-    pub(crate) descriptor_set: vk::DescriptorSet,
-    pub(crate) descriptor_set_layout: vk::DescriptorSetLayout,
-    pub(crate) general_address_buffer: vk::Buffer,
-    pub(crate) general_address_memory: vk::DeviceMemory,
-    pub(crate) staging_address_buffer: vk::Buffer,
-    pub(crate) staging_address_memory: vk::DeviceMemory,
-}
+impl Drop for DeviceInner {
+    fn drop(&mut self) {
+        let DeviceInner {
+            logical_device,
+            mipmaps,
+            ..
+        } = self;
 
-pub struct DeviceInfo<'a> {
-    pub display: RawDisplayHandle,
-    pub window: RawWindowHandle,
-    pub selector: &'a dyn ops::Fn(Details) -> usize,
-    pub features: Features,
-    pub debug_name: &'a str,
-}
+        unsafe { logical_device.device_wait_idle() }.ok();
 
-impl Default for DeviceInfo<'_> {
-    fn default() -> Self {
-        Self {
-            #[cfg(target_os = "windows")]
-            display: RawDisplayHandle::Windows(WindowsDisplayHandle::empty()),
-            #[cfg(target_os = "windows")]
-            window: RawWindowHandle::Win32(Win32WindowHandle::empty()),
-            #[cfg(target_os = "linux")]
-            display: RawDisplayHandle::Xlib(XlibDisplayHandle::empty()),
-            #[cfg(target_os = "linux")]
-            window: RawWindowHandle::Xlib(XlibWindowHandle::empty()),
-            #[cfg(target_os = "android")]
-            display: RawDisplayHandle::Android(AndroidDisplayHandle::empty()),
-            #[cfg(target_os = "android")]
-            window: RawWindowHandle::AndroidNdk(AndroidNdkWindowHandle::empty()),
-            selector: &default_device_selector,
-            features: Default::default(),
-            debug_name: "Device",
-        }
+        mipmaps.get_mut().unwrap().destroy(logical_device);
     }
 }
 
-bitflags! {
-    pub struct SampleCount: u32 {
-        const TYPE_1 = 0x0000_0001;
-        const TYPE_2 = 0x0000_0010;
-        const TYPE_4 = 0x0000_0100;
-        const TYPE_8 = 0x0000_1000;
-        const TYPE_16 = 0x0001_0000;
-        const TYPE_32 = 0x0010_0000;
-        const TYPE_64 = 0x0100_0000;
-    }
+///The queue work is submitted to.
+///Compute and transfer work runs on dedicated queue families when the device has them,
+///otherwise it falls back to a queue that can do more.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub enum Queue {
+    #[default]
+    Graphics,
+    Compute,
+    Transfer,
 }
 
-pub struct Details {
-    pub properties: Properties,
-    pub features: Features,
+///What an `ImageInfo` resolves to once it is validated.
+pub(crate) struct ImageDesc {
+    pub(crate) image_type: vk::ImageType,
+    pub(crate) view_type: vk::ImageViewType,
+    pub(crate) extent: vk::Extent3D,
+    pub(crate) mip_levels: u32,
+    pub(crate) array_layers: u32,
+    pub(crate) flags: vk::ImageCreateFlags,
+    pub(crate) usage: vk::ImageUsageFlags,
+    pub(crate) samples: vk::SampleCountFlags,
 }
 
-pub struct Properties {
-    pub device_type: DeviceType,
-    pub limits: Limits,
+///What `DeviceInner::build_swapchain` creates a swapchain from.
+pub(crate) struct SwapchainDesc<'a> {
+    pub(crate) surface: vk::SurfaceKHR,
+    pub(crate) surface_format: vk::SurfaceFormatKHR,
+    pub(crate) present_mode: vk::PresentModeKHR,
+    ///Used when the surface has no extent of its own.
+    pub(crate) requested_extent: vk::Extent2D,
+    pub(crate) image_usage: ImageUsage,
+    pub(crate) old_swapchain: vk::SwapchainKHR,
+    pub(crate) debug_name: &'a str,
 }
 
-impl From<vk::PhysicalDeviceProperties> for Properties {
-    fn from(properties: vk::PhysicalDeviceProperties) -> Self {
-        Self {
-            device_type: properties.device_type.into(),
-            limits: properties.limits.into(),
+impl DeviceInner {
+    pub(crate) fn queue_family_index(&self, queue: Queue) -> u32 {
+        self.queue_family_indices[queue as usize]
+    }
+
+    pub(crate) fn queue(&self, queue: Queue) -> vk::Queue {
+        unsafe {
+            self.logical_device
+                .get_device_queue(self.queue_family_index(queue), 0)
         }
     }
-}
 
-#[derive(Default)]
-pub struct Features {
-    pub robust_buffer_access: bool,
-    pub full_draw_index_uint32: bool,
-    pub image_cube_array: bool,
-    pub independent_blend: bool,
-    pub geometry_shader: bool,
-    pub tessellation_shader: bool,
-    pub sample_rate_shading: bool,
-    pub dual_src_blend: bool,
-    pub logic_op: bool,
-    pub multi_draw_indirect: bool,
-    pub draw_indirect_first_instance: bool,
-    pub depth_clamp: bool,
-    pub depth_bias_clamp: bool,
-    pub fill_mode_non_solid: bool,
-    pub depth_bounds: bool,
-    pub wide_lines: bool,
-    pub large_points: bool,
-    pub alpha_to_one: bool,
-    pub multi_viewport: bool,
-    pub sampler_anisotropy: bool,
-    pub texture_compression_etc2: bool,
-    pub texture_compression_astc_ldr: bool,
-    pub texture_compression_bc: bool,
-    pub occlusion_query_precise: bool,
-    pub pipeline_statistics_query: bool,
-    pub vertex_pipeline_stores_and_atomics: bool,
-    pub fragment_stores_and_atomics: bool,
-    pub shader_tessellation_and_geometry_point_size: bool,
-    pub shader_image_gather_extended: bool,
-    pub shader_storage_image_extended_formats: bool,
-    pub shader_storage_image_multisample: bool,
-    pub shader_storage_image_read_without_format: bool,
-    pub shader_storage_image_write_without_format: bool,
-    pub shader_uniform_buffer_array_dynamic_indexing: bool,
-    pub shader_sampled_image_array_dynamic_indexing: bool,
-    pub shader_storage_buffer_array_dynamic_indexing: bool,
-    pub shader_storage_image_array_dynamic_indexing: bool,
-    pub shader_clip_distance: bool,
-    pub shader_cull_distance: bool,
-    pub shader_float64: bool,
-    pub shader_int64: bool,
-    pub shader_int16: bool,
-    pub shader_resource_residency: bool,
-    pub shader_resource_min_lod: bool,
-    pub sparse_binding: bool,
-    pub sparse_residency_buffer: bool,
-    pub sparse_residency_image2_d: bool,
-    pub sparse_residency_image3_d: bool,
-    pub sparse_residency2_samples: bool,
-    pub sparse_residency4_samples: bool,
-    pub sparse_residency8_samples: bool,
-    pub sparse_residency16_samples: bool,
-    pub sparse_residency_aliased: bool,
-    pub variable_multisample_rate: bool,
-    pub inherited_queries: bool,
-}
+    ///Opens a labelled region of a command buffer, which shows up in graphics debuggers such as RenderDoc.
+    pub(crate) fn begin_label(&self, command_buffer: vk::CommandBuffer, name: &str) {
+        let Some(debug_utils) = &self.context.debug_utils else {
+            return;
+        };
 
-impl From<Features> for vk::PhysicalDeviceFeatures {
-    fn from(features: Features) -> Self {
-        Self {
-            robust_buffer_access: features.robust_buffer_access as _,
-            full_draw_index_uint32: features.full_draw_index_uint32 as _,
-            image_cube_array: features.image_cube_array as _,
-            independent_blend: features.independent_blend as _,
-            geometry_shader: features.geometry_shader as _,
-            tessellation_shader: features.tessellation_shader as _,
-            sample_rate_shading: features.sample_rate_shading as _,
-            dual_src_blend: features.dual_src_blend as _,
-            logic_op: features.logic_op as _,
-            multi_draw_indirect: features.multi_draw_indirect as _,
-            draw_indirect_first_instance: features.draw_indirect_first_instance as _,
-            depth_clamp: features.depth_clamp as _,
-            depth_bias_clamp: features.depth_bias_clamp as _,
-            fill_mode_non_solid: features.fill_mode_non_solid as _,
-            depth_bounds: features.depth_bounds as _,
-            wide_lines: features.wide_lines as _,
-            large_points: features.large_points as _,
-            alpha_to_one: features.alpha_to_one as _,
-            multi_viewport: features.multi_viewport as _,
-            sampler_anisotropy: features.sampler_anisotropy as _,
-            texture_compression_etc2: features.texture_compression_etc2 as _,
-            texture_compression_astc_ldr: features.texture_compression_astc_ldr as _,
-            texture_compression_bc: features.texture_compression_bc as _,
-            occlusion_query_precise: features.occlusion_query_precise as _,
-            pipeline_statistics_query: features.pipeline_statistics_query as _,
-            vertex_pipeline_stores_and_atomics: features.vertex_pipeline_stores_and_atomics as _,
-            fragment_stores_and_atomics: features.fragment_stores_and_atomics as _,
-            shader_tessellation_and_geometry_point_size: features
-                .shader_tessellation_and_geometry_point_size
-                as _,
-            shader_image_gather_extended: features.shader_image_gather_extended as _,
-            shader_storage_image_extended_formats: features.shader_storage_image_extended_formats
-                as _,
-            shader_storage_image_multisample: features.shader_storage_image_multisample as _,
-            shader_storage_image_read_without_format: features
-                .shader_storage_image_read_without_format
-                as _,
-            shader_storage_image_write_without_format: features
-                .shader_storage_image_write_without_format
-                as _,
-            shader_uniform_buffer_array_dynamic_indexing: features
-                .shader_uniform_buffer_array_dynamic_indexing
-                as _,
-            shader_sampled_image_array_dynamic_indexing: features
-                .shader_sampled_image_array_dynamic_indexing
-                as _,
-            shader_storage_buffer_array_dynamic_indexing: features
-                .shader_storage_buffer_array_dynamic_indexing
-                as _,
-            shader_storage_image_array_dynamic_indexing: features
-                .shader_storage_image_array_dynamic_indexing
-                as _,
-            shader_clip_distance: features.shader_clip_distance as _,
-            shader_cull_distance: features.shader_cull_distance as _,
-            shader_float64: features.shader_float64 as _,
-            shader_int64: features.shader_int64 as _,
-            shader_int16: features.shader_int16 as _,
-            shader_resource_residency: features.shader_resource_residency as _,
-            shader_resource_min_lod: features.shader_resource_min_lod as _,
-            sparse_binding: features.sparse_binding as _,
-            sparse_residency_buffer: features.sparse_residency_buffer as _,
-            sparse_residency_image2_d: features.sparse_residency_image2_d as _,
-            sparse_residency_image3_d: features.sparse_residency_image3_d as _,
-            sparse_residency2_samples: features.sparse_residency2_samples as _,
-            sparse_residency4_samples: features.sparse_residency4_samples as _,
-            sparse_residency8_samples: features.sparse_residency8_samples as _,
-            sparse_residency16_samples: features.sparse_residency16_samples as _,
-            sparse_residency_aliased: features.sparse_residency_aliased as _,
-            variable_multisample_rate: features.variable_multisample_rate as _,
-            inherited_queries: features.inherited_queries as _,
+        let name = ffi::CString::new(name).unwrap_or_default();
+
+        let label = vk::DebugUtilsLabelEXT {
+            p_label_name: name.as_ptr(),
+            ..Default::default()
+        };
+
+        unsafe { debug_utils.cmd_begin_debug_utils_label(command_buffer, &label) };
+    }
+
+    pub(crate) fn surface_capabilities(
+        &self,
+        surface: vk::SurfaceKHR,
+    ) -> Result<vk::SurfaceCapabilitiesKHR> {
+        let DeviceInner {
+            surface_loader,
+            physical_device,
+            ..
+        } = self;
+
+        unsafe {
+            surface_loader.get_physical_device_surface_capabilities(*physical_device, surface)
         }
+        .map_err(|_| Error::Creation)
     }
-}
 
-impl From<vk::PhysicalDeviceFeatures> for Features {
-    fn from(features: vk::PhysicalDeviceFeatures) -> Self {
-        Self {
-            robust_buffer_access: features.robust_buffer_access != 0,
-            full_draw_index_uint32: features.full_draw_index_uint32 != 0,
-            image_cube_array: features.image_cube_array != 0,
-            independent_blend: features.independent_blend != 0,
-            geometry_shader: features.geometry_shader != 0,
-            tessellation_shader: features.tessellation_shader != 0,
-            sample_rate_shading: features.sample_rate_shading != 0,
-            dual_src_blend: features.dual_src_blend != 0,
-            logic_op: features.logic_op != 0,
-            multi_draw_indirect: features.multi_draw_indirect != 0,
-            draw_indirect_first_instance: features.draw_indirect_first_instance != 0,
-            depth_clamp: features.depth_clamp != 0,
-            depth_bias_clamp: features.depth_bias_clamp != 0,
-            fill_mode_non_solid: features.fill_mode_non_solid != 0,
-            depth_bounds: features.depth_bounds != 0,
-            wide_lines: features.wide_lines != 0,
-            large_points: features.large_points != 0,
-            alpha_to_one: features.alpha_to_one != 0,
-            multi_viewport: features.multi_viewport != 0,
-            sampler_anisotropy: features.sampler_anisotropy != 0,
-            texture_compression_etc2: features.texture_compression_etc2 != 0,
-            texture_compression_astc_ldr: features.texture_compression_astc_ldr != 0,
-            texture_compression_bc: features.texture_compression_bc != 0,
-            occlusion_query_precise: features.occlusion_query_precise != 0,
-            pipeline_statistics_query: features.pipeline_statistics_query != 0,
-            vertex_pipeline_stores_and_atomics: features.vertex_pipeline_stores_and_atomics != 0,
-            fragment_stores_and_atomics: features.fragment_stores_and_atomics != 0,
-            shader_tessellation_and_geometry_point_size: features
-                .shader_tessellation_and_geometry_point_size
-                != 0,
-            shader_image_gather_extended: features.shader_image_gather_extended != 0,
-            shader_storage_image_extended_formats: features.shader_storage_image_extended_formats
-                != 0,
-            shader_storage_image_multisample: features.shader_storage_image_multisample != 0,
-            shader_storage_image_read_without_format: features
-                .shader_storage_image_read_without_format
-                != 0,
-            shader_storage_image_write_without_format: features
-                .shader_storage_image_write_without_format
-                != 0,
-            shader_uniform_buffer_array_dynamic_indexing: features
-                .shader_uniform_buffer_array_dynamic_indexing
-                != 0,
-            shader_sampled_image_array_dynamic_indexing: features
-                .shader_sampled_image_array_dynamic_indexing
-                != 0,
-            shader_storage_buffer_array_dynamic_indexing: features
-                .shader_storage_buffer_array_dynamic_indexing
-                != 0,
-            shader_storage_image_array_dynamic_indexing: features
-                .shader_storage_image_array_dynamic_indexing
-                != 0,
-            shader_clip_distance: features.shader_clip_distance != 0,
-            shader_cull_distance: features.shader_cull_distance != 0,
-            shader_float64: features.shader_float64 != 0,
-            shader_int64: features.shader_int64 != 0,
-            shader_int16: features.shader_int16 != 0,
-            shader_resource_residency: features.shader_resource_residency != 0,
-            shader_resource_min_lod: features.shader_resource_min_lod != 0,
-            sparse_binding: features.sparse_binding != 0,
-            sparse_residency_buffer: features.sparse_residency_buffer != 0,
-            sparse_residency_image2_d: features.sparse_residency_image2_d != 0,
-            sparse_residency_image3_d: features.sparse_residency_image3_d != 0,
+    ///Creates a surface for the window, or a headless surface when the context is headless.
+    pub(crate) fn create_surface(
+        &self,
+        display: RawDisplayHandle,
+        window: RawWindowHandle,
+    ) -> Result<vk::SurfaceKHR> {
+        let ContextInner {
+            entry,
+            instance,
+            headless_surface,
+            ..
+        } = &*self.context;
+
+        match headless_surface {
+            Some(headless_surface) => unsafe {
+                headless_surface
+                    .create_headless_surface(&vk::HeadlessSurfaceCreateInfoEXT::default(), None)
+            },
+            None => unsafe { ash_window::create_surface(entry, instance, display, window, None) },
+        }
+        .map_err(|_| Error::CreateSurface)
+    }
+
+    pub(crate) fn surface_support(&self, surface: vk::SurfaceKHR) -> Result<SurfaceCapabilities> {
+        let DeviceInner {
+            surface_loader,
+            physical_device,
+            ..
+        } = self;
+
+        let vk::SurfaceCapabilitiesKHR {
+            min_image_count,
+            max_image_count,
+            current_extent,
+            min_image_extent,
+            max_image_extent,
+            ..
+        } = self.surface_capabilities(surface)?;
+
+        let formats = unsafe {
+            surface_loader.get_physical_device_surface_formats(*physical_device, surface)
+        }
+        .map_err(|_| Error::Creation)?
+        .into_iter()
+        .filter_map(|surface_format| surface_format.try_into().ok())
+        .collect();
+
+        let present_modes = unsafe {
+            surface_loader.get_physical_device_surface_present_modes(*physical_device, surface)
+        }
+        .map_err(|_| Error::Creation)?
+        .into_iter()
+        .filter_map(|present_mode| present_mode.try_into().ok())
+        .collect();
+
+        let max_image_count = (max_image_count != 0).then_some(max_image_count);
+
+        let current_extent = (current_extent.width != u32::MAX)
+            .then_some((current_extent.width, current_extent.height));
+
+        Ok(SurfaceCapabilities {
+            formats,
+            present_modes,
+            min_image_count,
+            max_image_count,
+            current_extent,
+            min_extent: (min_image_extent.width, min_image_extent.height),
+            max_extent: (max_image_extent.width, max_image_extent.height),
+        })
+    }
+
+    ///Creates a swapchain and views of its images for the current size of the surface.
+    ///`old_swapchain` is retired, but not destroyed.
+    pub(crate) fn build_swapchain(
+        &self,
+        resources: &mut DeviceResources,
+        loader: &khr::Swapchain,
+        desc: SwapchainDesc<'_>,
+    ) -> Result<(vk::SwapchainKHR, vk::Extent2D, Vec<Image>)> {
+        let DeviceInner {
+            logical_device,
+            queue_family_indices,
+            ..
+        } = self;
+
+        let SwapchainDesc {
+            surface,
+            surface_format,
+            present_mode,
+            requested_extent,
+            image_usage,
+            old_swapchain,
+            debug_name,
+        } = desc;
+
+        let surface_capabilities = self.surface_capabilities(surface)?;
+
+        let vk::SurfaceFormatKHR {
+            format,
+            color_space,
+        } = surface_format;
+
+        let image_extent = match surface_capabilities.current_extent.width {
+            u32::MAX => requested_extent,
+            _ => surface_capabilities.current_extent,
+        };
+
+        let swapchain_create_info = {
+            //Three images, so mailbox presentation never waits, within what the surface allows.
+            let min_image_count = match surface_capabilities.max_image_count {
+                0 => 3.max(surface_capabilities.min_image_count),
+                max_image_count => 3.clamp(surface_capabilities.min_image_count, max_image_count),
+            };
+
+            let image_format = format;
+            let image_color_space = color_space;
+
+            let image_array_layers = 1;
+            let image_usage = vk::ImageUsageFlags::from(image_usage);
+
+            if !surface_capabilities
+                .supported_usage_flags
+                .contains(image_usage)
+            {
+                Err(Error::Creation)?
+            }
+
+            let image_sharing_mode = vk::SharingMode::EXCLUSIVE;
+
+            let queue_family_index_count = queue_family_indices.len() as _;
+
+            let p_queue_family_indices = queue_family_indices.as_ptr();
+
+            let pre_transform = if surface_capabilities
+                .supported_transforms
+                .contains(vk::SurfaceTransformFlagsKHR::IDENTITY)
+            {
+                vk::SurfaceTransformFlagsKHR::IDENTITY
+            } else {
+                surface_capabilities.current_transform
+            };
+
+            let composite_alpha = vk::CompositeAlphaFlagsKHR::OPAQUE;
+
+            let clipped = true as _;
+
+            vk::SwapchainCreateInfoKHR {
+                surface,
+                min_image_count,
+                image_format,
+                image_color_space,
+                image_extent,
+                image_array_layers,
+                image_usage,
+                image_sharing_mode,
+                queue_family_index_count,
+                p_queue_family_indices,
+                pre_transform,
+                composite_alpha,
+                present_mode,
+                clipped,
+                old_swapchain,
+                ..Default::default()
+            }
+        };
+
+        let handle = unsafe { loader.create_swapchain(&swapchain_create_info, None) }
+            .map_err(|_| Error::Creation)?;
+
+        let swapchain_images = unsafe { loader.get_swapchain_images(handle) }.map_err(|_| {
+            unsafe { loader.destroy_swapchain(handle, None) };
+
+            Error::Creation
+        })?;
+
+        let images = swapchain_images
+            .into_iter()
+            .map(|image| {
+                let image_view_create_info = vk::ImageViewCreateInfo {
+                    image,
+                    view_type: vk::ImageViewType::TYPE_2D,
+                    format,
+                    components: vk::ComponentMapping {
+                        r: vk::ComponentSwizzle::IDENTITY,
+                        g: vk::ComponentSwizzle::IDENTITY,
+                        b: vk::ComponentSwizzle::IDENTITY,
+                        a: vk::ComponentSwizzle::IDENTITY,
+                    },
+                    subresource_range: vk::ImageSubresourceRange {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        base_mip_level: 0,
+                        level_count: 1,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    },
+                    ..Default::default()
+                };
+
+                let image_view =
+                    unsafe { logical_device.create_image_view(&image_view_create_info, None) }
+                        .unwrap();
+
+                (image, image_view)
+            })
+            .enumerate()
+            .map(|(i, (image, view))| InternalImage::Swapchain {
+                image,
+                view,
+                format: format.try_into().unwrap(),
+                extent: vk::Extent3D {
+                    width: image_extent.width,
+                    height: image_extent.height,
+                    depth: 1,
+                },
+                usage: swapchain_create_info.image_usage,
+                debug_name: format!("{debug_name}[{i}]"),
+            })
+            .map(|internal_image| resources.images.add(internal_image))
+            .collect::<Vec<_>>();
+
+        Ok((handle, image_extent, images))
+    }
+
+    ///Checks that `info` describes an image boson can create, and resolves it to what Vulkan needs.
+    ///Shared by `create_image` and `Device::image_format_supported`, so that both reject the same images.
+    pub(crate) fn describe_image(&self, info: &ImageInfo<'_>) -> Result<ImageDesc> {
+        let DeviceInner { features, .. } = self;
+
+        let ImageInfo {
+            extent,
+            usage,
+            format,
+            mip_levels,
+            array_layers,
+            cube,
+            view_formats,
+            samples,
+            ..
+        } = *info;
+
+        if !format.is_enabled(features) {
+            Err(Error::UnsupportedFormat)?
+        }
+
+        let mip_levels = mip_levels.clamp(1, extent.full_mip_levels());
+
+        let array_layers = array_layers.max(1);
+
+        let (image_type, view_type, extent) = match extent {
+            ImageExtent::OneDim(x) => (
+                vk::ImageType::TYPE_1D,
+                vk::ImageViewType::TYPE_1D,
+                vk::Extent3D {
+                    width: x as _,
+                    height: 1,
+                    depth: 1,
+                },
+            ),
+            ImageExtent::TwoDim(x, y) => (
+                vk::ImageType::TYPE_2D,
+                vk::ImageViewType::TYPE_2D,
+                vk::Extent3D {
+                    width: x as _,
+                    height: y as _,
+                    depth: 1,
+                },
+            ),
+            ImageExtent::ThreeDim(x, y, z) => (
+                vk::ImageType::TYPE_3D,
+                vk::ImageViewType::TYPE_3D,
+                vk::Extent3D {
+                    width: x as _,
+                    height: y as _,
+                    depth: z as _,
+                },
+            ),
+        };
+
+        let view_type = match (view_type, array_layers, cube) {
+            (vk::ImageViewType::TYPE_2D, 6, true) => vk::ImageViewType::CUBE,
+            (vk::ImageViewType::TYPE_2D, _, true) if array_layers % 6 == 0 => {
+                vk::ImageViewType::CUBE_ARRAY
+            }
+            (_, _, true) => Err(Error::Creation)?,
+            (_, 1, _) => view_type,
+            (vk::ImageViewType::TYPE_1D, _, _) => vk::ImageViewType::TYPE_1D_ARRAY,
+            (vk::ImageViewType::TYPE_2D, _, _) => vk::ImageViewType::TYPE_2D_ARRAY,
+            _ => Err(Error::Creation)?,
+        };
+
+        if cube && extent.width != extent.height {
+            Err(Error::Creation)?
+        }
+
+        if view_type == vk::ImageViewType::CUBE_ARRAY && features.image_cube_array == 0 {
+            Err(Error::Creation)?
+        }
+
+        let mut flags = vk::ImageCreateFlags::empty();
+
+        if cube {
+            flags |= vk::ImageCreateFlags::CUBE_COMPATIBLE;
+        }
+
+        if !view_formats.is_empty() {
+            flags |= vk::ImageCreateFlags::MUTABLE_FORMAT;
+        }
+
+        //Multisampled images have a single mip level of a 2D image.
+        if samples != SampleCount::TYPE_1
+            && (mip_levels != 1 || image_type != vk::ImageType::TYPE_2D || cube)
+        {
+            Err(Error::UnsupportedSampleCount)?
+        }
+
+        let samples = vk::SampleCountFlags::from(samples);
+
+        if samples.as_raw().count_ones() != 1 {
+            Err(Error::UnsupportedSampleCount)?
+        }
+
+        Ok(ImageDesc {
+            image_type,
+            view_type,
+            extent,
+            mip_levels,
+            array_layers,
+            flags,
+            usage: usage.into(),
+            samples,
+        })
+    }
+
+    pub(crate) fn create_image(
+        &self,
+        resources: &mut DeviceResources,
+        info: ImageInfo<'_>,
+    ) -> Result<Image> {
+        let DeviceInner {
+            context,
+            physical_device,
+            logical_device,
+            ..
+        } = self;
+
+        let ContextInner { instance, .. } = &**context;
+
+        let ImageInfo {
+            format,
+            view_formats,
+            debug_name,
+            ..
+        } = info;
+
+        let ImageDesc {
+            image_type,
+            view_type,
+            extent,
+            mip_levels,
+            array_layers,
+            flags,
+            usage,
+            samples,
+        } = self.describe_image(&info)?;
+
+        let format_list = [format]
+            .into_iter()
+            .chain(view_formats.iter().copied())
+            .map(vk::Format::from)
+            .collect::<Vec<_>>();
+
+        let image_format_list_create_info = vk::ImageFormatListCreateInfo {
+            view_format_count: format_list.len() as _,
+            p_view_formats: format_list.as_ptr(),
+            ..Default::default()
+        };
+
+        let p_next = if format_list.len() > 1 {
+            &image_format_list_create_info as *const _ as *const _
+        } else {
+            ptr::null()
+        };
+
+        let multisampled = samples != vk::SampleCountFlags::TYPE_1;
+
+        if multisampled {
+            let image_format_properties = unsafe {
+                instance.get_physical_device_image_format_properties(
+                    *physical_device,
+                    format.into(),
+                    image_type,
+                    vk::ImageTiling::OPTIMAL,
+                    usage,
+                    flags,
+                )
+            }
+            .map_err(|_| Error::UnsupportedSampleCount)?;
+
+            if !image_format_properties.sample_counts.contains(samples) {
+                Err(Error::UnsupportedSampleCount)?
+            }
+        }
+
+        let image_create_info = vk::ImageCreateInfo {
+            p_next,
+            flags,
+            image_type,
+            extent,
+            format: format.into(),
+            usage,
+            array_layers,
+            mip_levels,
+            tiling: vk::ImageTiling::OPTIMAL,
+            sharing_mode: vk::SharingMode::EXCLUSIVE,
+            samples,
+            ..Default::default()
+        };
+
+        let image = unsafe { logical_device.create_image(&image_create_info, None) }
+            .map_err(|_| Error::Creation)?;
+
+        let memory_requirements = unsafe { logical_device.get_image_memory_requirements(image) };
+
+        let memory_properties =
+            unsafe { instance.get_physical_device_memory_properties(*physical_device) };
+
+        let memory_type_index = memory::type_index(
+            &memory_requirements,
+            &memory_properties,
+            Memory::empty().into(),
+        )?;
+
+        let allocation_size = memory_requirements.size;
+
+        let memory_allocate_info = {
+            vk::MemoryAllocateInfo {
+                allocation_size,
+                memory_type_index,
+                ..Default::default()
+            }
+        };
+
+        let memory = unsafe { logical_device.allocate_memory(&memory_allocate_info, None) }
+            .map_err(|_| Error::Creation)?;
+
+        unsafe { logical_device.bind_image_memory(image, memory, 0) }
+            .map_err(|_| Error::Creation)?;
+
+        let image_view_create_info = vk::ImageViewCreateInfo {
+            image,
+            view_type,
+            format: format.into(),
+            components: vk::ComponentMapping {
+                r: vk::ComponentSwizzle::IDENTITY,
+                g: vk::ComponentSwizzle::IDENTITY,
+                b: vk::ComponentSwizzle::IDENTITY,
+                a: vk::ComponentSwizzle::IDENTITY,
+            },
+            subresource_range: vk::ImageSubresourceRange {
+                aspect_mask: format.entire_aspect(),
+                base_mip_level: 0,
+                level_count: mip_levels,
+                base_array_layer: 0,
+                layer_count: array_layers,
+            },
+
+            ..Default::default()
+        };
+
+        let view =
+            unsafe { logical_device.create_image_view(&image_view_create_info, None) }.unwrap();
+
+        Ok(resources.images.add(InternalImage::Managed {
+            image,
+            memory,
+            view,
+            view_type,
+            format,
+            extent,
+            mip_levels,
+            array_layers,
+            samples,
+            usage,
+            view_formats: view_formats.to_vec(),
+            debug_name: debug_name.to_owned(),
+        }))
+    }
+
+    pub(crate) fn create_buffer(
+        &self,
+        resources: &mut DeviceResources,
+        info: BufferInfo<'_>,
+    ) -> Result<Buffer> {
+        let DeviceInner {
+            context,
+            physical_device,
+            logical_device,
+            ..
+        } = self;
+
+        let ContextInner { instance, .. } = &**context;
+
+        let BufferInfo {
+            size,
+            usage,
+            memory: properties,
+            debug_name,
+        } = info;
+
+        let size = size as _;
+
+        let allocation_size = size as _;
+
+        let mut usage = usage.into();
+
+        //Every buffer has an address, for `Device::address` and the bindless address book.
+        usage |= vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS;
+
+        let debug_name = debug_name.to_owned();
+
+        let sharing_mode = vk::SharingMode::EXCLUSIVE;
+
+        let buffer_create_info = vk::BufferCreateInfo {
+            size,
+            usage,
+            sharing_mode,
+            ..Default::default()
+        };
+
+        let buffer = unsafe { logical_device.create_buffer(&buffer_create_info, None) }
+            .map_err(|_| Error::Creation)?;
+
+        let memory_requirements = unsafe { logical_device.get_buffer_memory_requirements(buffer) };
+
+        let memory_properties =
+            unsafe { instance.get_physical_device_memory_properties(*physical_device) };
+
+        let memory_type_index =
+            memory::type_index(&memory_requirements, &memory_properties, properties.into())?;
+
+        let mut memory_allocate_flags_info = vk::MemoryAllocateFlagsInfo {
+            flags: vk::MemoryAllocateFlags::DEVICE_ADDRESS,
+            ..Default::default()
+        };
+
+        let memory_allocate_info = {
+            let p_next = &mut memory_allocate_flags_info as *mut _ as *mut _;
+            vk::MemoryAllocateInfo {
+                p_next,
+                allocation_size,
+                memory_type_index,
+                ..Default::default()
+            }
+        };
+
+        let memory = unsafe { logical_device.allocate_memory(&memory_allocate_info, None) }
+            .map_err(|_| Error::AllocateMemory)?;
+
+        unsafe { logical_device.bind_buffer_memory(buffer, memory, 0) }
+            .map_err(|_| Error::Creation)?;
+
+        let memory = InternalMemory { memory, properties };
+
+        let BufferInfo { size, usage, .. } = info;
+
+        Ok(resources.buffers.add(InternalBuffer {
+            buffer,
+            memory,
+            size,
+            usage,
+            debug_name,
+        }))
+    }
+
+    ///Creates the images of a virtual swapchain.
+    pub(crate) fn build_virtual_swapchain(
+        &self,
+        resources: &mut DeviceResources,
+        format: Format,
+        image_usage: ImageUsage,
+        image_count: usize,
+        extent: vk::Extent2D,
+        debug_name: &str,
+    ) -> Result<Vec<Image>> {
+        let mut images = Vec::with_capacity(image_count);
+
+        for i in 0..image_count {
+            let image = self.create_image(
+                resources,
+                ImageInfo {
+                    extent: ImageExtent::TwoDim(extent.width as _, extent.height as _),
+                    //Every presented frame is copied back.
+                    usage: image_usage | ImageUsage::TRANSFER_SRC,
+                    format,
+                    debug_name: &format!("{debug_name}[{i}]"),
+                    ..Default::default()
+                },
+            );
+
+            match image {
+                Ok(image) => images.push(image),
+                Err(error) => {
+                    self.destroy_virtual_swapchain(resources, &images);
+
+                    return Err(error);
+                }
+            }
+        }
+
+        Ok(images)
+    }
+
+    ///Destroys images created by `build_virtual_swapchain`.
+    pub(crate) fn destroy_virtual_swapchain(
+        &self,
+        resources: &mut DeviceResources,
+        images: &[Image],
+    ) {
+        let DeviceInner {
+            logical_device,
+            mipmaps,
+            ..
+        } = self;
+
+        let mut mipmaps = mipmaps.lock().unwrap();
+
+        for &image in images {
+            mipmaps.evict(logical_device, image);
+
+            self.destroy_child_views(resources, image);
+
+            if let Some(InternalImage::Managed {
+                image,
+                memory,
+                view,
+                ..
+            }) = resources.images.remove(image)
+            {
+                unsafe {
+                    logical_device.destroy_image_view(view, None);
+                    logical_device.destroy_image(image, None);
+                    logical_device.free_memory(memory, None);
+                }
+            }
+        }
+    }
+
+    ///Removes the images of a swapchain, which are destroyed with it, along with everything created for them.
+    pub(crate) fn remove_swapchain_images(
+        &self,
+        resources: &mut DeviceResources,
+        images: &[Image],
+    ) {
+        let DeviceInner {
+            logical_device,
+            mipmaps,
+            ..
+        } = self;
+
+        let mut mipmaps = mipmaps.lock().unwrap();
+
+        for &image in images {
+            mipmaps.evict(logical_device, image);
+
+            self.destroy_child_views(resources, image);
+
+            if let Some(internal_image) = resources.images.remove(image) {
+                unsafe {
+                    logical_device.destroy_image_view(internal_image.get_image_view(), None);
+                }
+            }
+        }
+    }
+
+    ///Destroys the views created of an image with `Device::create_image_view`, which cannot outlive it.
+    pub(crate) fn destroy_child_views(&self, resources: &mut DeviceResources, image: Image) {
+        let DeviceInner { logical_device, .. } = self;
+
+        let views = (0..resources.images.count() as u32)
+            .map(Image::from)
+            .filter(|&view| {
+                matches!(
+                    resources.images.get(view),
+                    Some(InternalImage::View { parent, .. }) if *parent == image
+                )
+            })
+            .collect::<Vec<_>>();
+
+        for view in views {
+            if let Some(internal_image) = resources.images.remove(view) {
+                unsafe {
+                    logical_device.destroy_image_view(internal_image.get_image_view(), None);
+                }
+            }
+        }
+    }
+
+    ///A host visible buffer that fits one swapchain image.
+    pub(crate) fn create_readback_buffer(
+        &self,
+        resources: &mut DeviceResources,
+        extent: vk::Extent2D,
+        debug_name: &str,
+    ) -> Result<Buffer> {
+        self.create_buffer(
+            resources,
+            BufferInfo {
+                size: extent.width as usize * extent.height as usize * 4,
+                memory: Memory::HOST_ACCESS,
+                usage: BufferUsage::TRANSFER_DST,
+                debug_name: &format!("{debug_name} Readback"),
+            },
+        )
+    }
+
+    pub(crate) fn destroy_readback_buffer(&self, resources: &mut DeviceResources, buffer: Buffer) {
+        let DeviceInner { logical_device, .. } = self;
+
+        if let Some(internal_buffer) = resources.buffers.remove(buffer) {
+            unsafe {
+                logical_device.destroy_buffer(internal_buffer.buffer, None);
+                logical_device.free_memory(internal_buffer.memory.memory, None);
+            }
+        }
+    }
+
+    pub(crate) fn create_readback(
+        &self,
+        resources: &mut DeviceResources,
+        extent: vk::Extent2D,
+        debug_name: &str,
+    ) -> Result<Readback> {
+        let DeviceInner { logical_device, .. } = self;
+
+        let buffer = self.create_readback_buffer(resources, extent, debug_name)?;
+
+        let command_pool_create_info = vk::CommandPoolCreateInfo {
+            flags: vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER,
+            queue_family_index: self.queue_family_index(Queue::Graphics),
+            ..Default::default()
+        };
+
+        let command_pool =
+            match unsafe { logical_device.create_command_pool(&command_pool_create_info, None) } {
+                Ok(command_pool) => command_pool,
+                Err(_) => {
+                    self.destroy_readback_buffer(resources, buffer);
+
+                    Err(Error::CreateCommandPool)?
+                }
+            };
+
+        //Destroying the pool frees the command buffer too.
+        let discard = |resources: &mut DeviceResources| {
+            unsafe { logical_device.destroy_command_pool(command_pool, None) };
+
+            self.destroy_readback_buffer(resources, buffer);
+
+            Error::Creation
+        };
+
+        let command_buffer_allocate_info = vk::CommandBufferAllocateInfo {
+            command_pool,
+            level: vk::CommandBufferLevel::PRIMARY,
+            command_buffer_count: 1,
+            ..Default::default()
+        };
+
+        let command_buffer =
+            unsafe { logical_device.allocate_command_buffers(&command_buffer_allocate_info) }
+                .map_err(|_| discard(resources))?[0];
+
+        let fence_create_info = vk::FenceCreateInfo {
+            flags: vk::FenceCreateFlags::SIGNALED,
+            ..Default::default()
+        };
+
+        let fence = unsafe { logical_device.create_fence(&fence_create_info, None) }
+            .map_err(|_| discard(resources))?;
+
+        Ok(Readback {
+            buffer,
+            command_pool,
+            command_buffer,
+            fence,
+            captured: false,
+        })
+    }
+
+    pub(crate) fn destroy_readback(&self, resources: &mut DeviceResources, readback: Readback) {
+        let DeviceInner { logical_device, .. } = self;
+
+        let Readback {
+            buffer,
+            command_pool,
+            fence,
+            ..
+        } = readback;
+
+        self.destroy_readback_buffer(resources, buffer);
+
+        unsafe {
+            logical_device.destroy_command_pool(command_pool, None);
+            logical_device.destroy_fence(fence, None);
+        }
+    }
+
+    ///Virtual swapchains hand out their images in turn.
+    ///Nothing has to finish before an image can be reused, so `semaphores` are signaled right away.
+    pub(crate) fn acquire_virtual_image(
+        &self,
+        resources: &mut DeviceResources,
+        swapchain: Swapchain,
+        semaphores: &[vk::Semaphore],
+    ) -> Result<Image> {
+        let DeviceInner { logical_device, .. } = self;
+
+        let InternalSwapchain {
+            images,
+            last_acquisition_index,
+            current_frame,
+            allow_acquisition,
+            ..
+        } = resources.swapchains.get_mut(swapchain).unwrap();
+
+        let next_image_index = last_acquisition_index
+            .map(|i| (i + 1) % images.len() as u32)
+            .unwrap_or(0);
+
+        if let Some(semaphore) = semaphores.get(*current_frame) {
+            let submit_info = vk::SubmitInfo {
+                signal_semaphore_count: 1,
+                p_signal_semaphores: semaphore,
+                ..Default::default()
+            };
+
+            unsafe {
+                logical_device.queue_submit(
+                    self.queue(Queue::Graphics),
+                    &[submit_info],
+                    vk::Fence::null(),
+                )
+            }
+            .map_err(|_| Error::FailedToAcquire)?;
+        }
+
+        *allow_acquisition = false;
+        *last_acquisition_index = Some(next_image_index);
+
+        Ok(images[next_image_index as usize])
+    }
+
+    ///Copies the images about to be presented to the readback buffers of their swapchains.
+    ///Each copy waits on `wait_semaphore` and signals it again, except the last, which only does if `signal` is set.
+    ///Frames of virtual swapchains are then waited for and written to disk.
+    pub(crate) fn read_back(
+        &self,
+        resources: &mut DeviceResources,
+        acquired: &[(Swapchain, u32)],
+        wait_semaphore: vk::Semaphore,
+        signal: bool,
+    ) -> Result<()> {
+        profiling::scope!("read back", "ev");
+
+        let DeviceInner { logical_device, .. } = self;
+
+        for (i, &(swapchain, image_index)) in acquired.iter().enumerate() {
+            let InternalSwapchain {
+                images,
+                extent,
+                readback:
+                    Some(Readback {
+                        buffer,
+                        command_buffer,
+                        fence,
+                        ..
+                    }),
+                ..
+            } = resources.swapchains.get(swapchain).unwrap()
+            else {
+                continue;
+            };
+
+            let image = resources
+                .images
+                .get(images[image_index as usize])
+                .ok_or(Error::InvalidResource)?
+                .get_image();
+
+            let buffer = resources
+                .buffers
+                .get(*buffer)
+                .ok_or(Error::InvalidResource)?
+                .buffer;
+
+            let subresource_range = vk::ImageSubresourceRange {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                base_mip_level: 0,
+                level_count: 1,
+                base_array_layer: 0,
+                layer_count: 1,
+            };
+
+            let to_transfer = vk::ImageMemoryBarrier {
+                src_access_mask: vk::AccessFlags::empty(),
+                dst_access_mask: vk::AccessFlags::TRANSFER_READ,
+                old_layout: vk::ImageLayout::PRESENT_SRC_KHR,
+                new_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                image,
+                subresource_range,
+                ..Default::default()
+            };
+
+            //The image goes back to the layout the render graph left it in.
+            let to_present = vk::ImageMemoryBarrier {
+                src_access_mask: vk::AccessFlags::TRANSFER_READ,
+                dst_access_mask: vk::AccessFlags::empty(),
+                old_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                new_layout: vk::ImageLayout::PRESENT_SRC_KHR,
+                ..to_transfer
+            };
+
+            let to_host = vk::BufferMemoryBarrier {
+                src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+                dst_access_mask: vk::AccessFlags::HOST_READ,
+                src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                buffer,
+                offset: 0,
+                size: vk::WHOLE_SIZE,
+                ..Default::default()
+            };
+
+            let region = vk::BufferImageCopy {
+                buffer_offset: 0,
+                buffer_row_length: 0,
+                buffer_image_height: 0,
+                image_subresource: vk::ImageSubresourceLayers {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    mip_level: 0,
+                    base_array_layer: 0,
+                    layer_count: 1,
+                },
+                image_offset: vk::Offset3D::default(),
+                image_extent: vk::Extent3D {
+                    width: extent.width,
+                    height: extent.height,
+                    depth: 1,
+                },
+            };
+
+            let begin_info = vk::CommandBufferBeginInfo {
+                flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
+                ..Default::default()
+            };
+
+            unsafe {
+                //The last copy has to finish before its command buffer is recorded again.
+                logical_device
+                    .wait_for_fences(&[*fence], true, u64::MAX)
+                    .map_err(|_| Error::CaptureFailed)?;
+
+                logical_device
+                    .reset_fences(&[*fence])
+                    .map_err(|_| Error::CaptureFailed)?;
+
+                logical_device
+                    .begin_command_buffer(*command_buffer, &begin_info)
+                    .map_err(|_| Error::CaptureFailed)?;
+
+                logical_device.cmd_pipeline_barrier(
+                    *command_buffer,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &[],
+                    &[to_transfer],
+                );
+
+                logical_device.cmd_copy_image_to_buffer(
+                    *command_buffer,
+                    image,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    buffer,
+                    &[region],
+                );
+
+                logical_device.cmd_pipeline_barrier(
+                    *command_buffer,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::PipelineStageFlags::HOST | vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &[to_host],
+                    &[to_present],
+                );
+
+                logical_device
+                    .end_command_buffer(*command_buffer)
+                    .map_err(|_| Error::CaptureFailed)?;
+            }
+
+            let wait_dst_stage_mask = vk::PipelineStageFlags::TRANSFER;
+
+            let signal = signal || i + 1 < acquired.len();
+
+            let submit_info = vk::SubmitInfo {
+                wait_semaphore_count: 1,
+                p_wait_semaphores: &wait_semaphore,
+                p_wait_dst_stage_mask: &wait_dst_stage_mask,
+                command_buffer_count: 1,
+                p_command_buffers: command_buffer,
+                signal_semaphore_count: signal as _,
+                p_signal_semaphores: &wait_semaphore,
+                ..Default::default()
+            };
+
+            unsafe {
+                logical_device.queue_submit(self.queue(Queue::Graphics), &[submit_info], *fence)
+            }
+            .map_err(|_| Error::CaptureFailed)?;
+
+            let InternalSwapchain { readback, .. } =
+                resources.swapchains.get_mut(swapchain).unwrap();
+
+            readback.as_mut().unwrap().captured = true;
+        }
+
+        for &(swapchain, _) in acquired {
+            if resources
+                .swapchains
+                .get(swapchain)
+                .unwrap()
+                .capture
+                .is_none()
+            {
+                continue;
+            }
+
+            let screenshot = self.screenshot(resources, swapchain)?;
+
+            let InternalSwapchain { capture, .. } =
+                resources.swapchains.get_mut(swapchain).unwrap();
+
+            capture
+                .as_mut()
+                .unwrap()
+                .write_frame(&screenshot)
+                .map_err(|_| Error::CaptureFailed)?;
+        }
+
+        Ok(())
+    }
+
+    ///Waits for the last copy to the readback buffer of a swapchain, and converts it.
+    pub(crate) fn screenshot(
+        &self,
+        resources: &DeviceResources,
+        swapchain: Swapchain,
+    ) -> Result<Screenshot> {
+        let DeviceInner { logical_device, .. } = self;
+
+        let InternalSwapchain {
+            format,
+            extent,
+            readback,
+            ..
+        } = resources
+            .swapchains
+            .get(swapchain)
+            .ok_or(Error::ResourceNotFound)?;
+
+        let Readback {
+            buffer,
+            fence,
+            captured,
+            ..
+        } = readback.as_ref().ok_or(Error::InvalidResource)?;
+
+        if !captured {
+            Err(Error::NothingCaptured)?
+        }
+
+        unsafe { logical_device.wait_for_fences(&[*fence], true, u64::MAX) }
+            .map_err(|_| Error::CaptureFailed)?;
+
+        let internal_buffer = resources
+            .buffers
+            .get(*buffer)
+            .ok_or(Error::InvalidResource)?;
+
+        let size = extent.width as usize * extent.height as usize * 4;
+
+        let src = unsafe {
+            logical_device.map_memory(
+                internal_buffer.memory.memory,
+                0,
+                size as _,
+                vk::MemoryMapFlags::empty(),
+            )
+        }
+        .map_err(|_| Error::MemoryMapFailed)?;
+
+        let pixels = capture::to_rgba8(
+            unsafe { slice::from_raw_parts(src as *const u8, size) },
+            *format,
+        );
+
+        unsafe { logical_device.unmap_memory(internal_buffer.memory.memory) };
+
+        Ok(Screenshot {
+            width: extent.width,
+            height: extent.height,
+            pixels,
+        })
+    }
+
+    ///Replaces a swapchain that no longer matches its surface, keeping its handle.
+    ///Its images are replaced too, so they have to be acquired again.
+    pub(crate) fn recreate_swapchain(
+        &self,
+        resources: &mut DeviceResources,
+        swapchain: Swapchain,
+    ) -> Result<()> {
+        profiling::scope!("recreate swapchain", "ev");
+
+        let DeviceInner { logical_device, .. } = self;
+
+        if resources
+            .swapchains
+            .get(swapchain)
+            .ok_or(Error::ResourceNotFound)?
+            .capture
+            .is_some()
+        {
+            return self.recreate_virtual_swapchain(resources, swapchain);
+        }
+
+        let InternalSwapchain {
+            loader,
+            handle: old_handle,
+            images: old_images,
+            surface,
+            surface_format,
+            present_mode,
+            requested_extent,
+            image_usage,
+            debug_name,
+            ..
+        } = resources
+            .swapchains
+            .get(swapchain)
+            .ok_or(Error::ResourceNotFound)?;
+
+        let (loader, old_handle, old_images) = (loader.clone(), *old_handle, old_images.clone());
+        let (surface, surface_format, present_mode, requested_extent, image_usage) = (
+            *surface,
+            *surface_format,
+            *present_mode,
+            *requested_extent,
+            *image_usage,
+        );
+        let debug_name = debug_name.clone();
+
+        //A minimized window has nothing to present to, so the swapchain is recreated once it has a size again.
+        let vk::Extent2D { width, height } = self.surface_capabilities(surface)?.current_extent;
+
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+
+        //The old images may still be in use by frames in flight.
+        unsafe { logical_device.device_wait_idle() }.map_err(|_| Error::Creation)?;
+
+        let (handle, extent, images) = self.build_swapchain(
+            resources,
+            &loader,
+            SwapchainDesc {
+                surface,
+                surface_format,
+                present_mode,
+                requested_extent,
+                image_usage,
+                old_swapchain: old_handle,
+                debug_name: &debug_name,
+            },
+        )?;
+
+        self.remove_swapchain_images(resources, &old_images);
+
+        unsafe { loader.destroy_swapchain(old_handle, None) };
+
+        self.resize_readback(resources, swapchain, extent)?;
+
+        let internal_swapchain = resources.swapchains.get_mut(swapchain).unwrap();
+
+        internal_swapchain.handle = handle;
+        internal_swapchain.extent = extent;
+        internal_swapchain.images = images;
+        internal_swapchain.last_acquisition_index = None;
+        internal_swapchain.allow_acquisition = true;
+        internal_swapchain.recreate = false;
+        internal_swapchain.generation += 1;
+
+        Ok(())
+    }
+
+    ///Replaces the images of a virtual swapchain with ones of the requested size.
+    pub(crate) fn recreate_virtual_swapchain(
+        &self,
+        resources: &mut DeviceResources,
+        swapchain: Swapchain,
+    ) -> Result<()> {
+        let DeviceInner { logical_device, .. } = self;
+
+        let InternalSwapchain {
+            format,
+            images: old_images,
+            image_usage,
+            requested_extent,
+            debug_name,
+            ..
+        } = resources.swapchains.get(swapchain).unwrap();
+
+        let (format, image_usage, requested_extent) = (*format, *image_usage, *requested_extent);
+        let old_images = old_images.clone();
+        let debug_name = debug_name.clone();
+
+        if requested_extent.width == 0 || requested_extent.height == 0 {
+            return Ok(());
+        }
+
+        //The old images may still be in use by frames in flight.
+        unsafe { logical_device.device_wait_idle() }.map_err(|_| Error::Creation)?;
+
+        let images = self.build_virtual_swapchain(
+            resources,
+            format,
+            image_usage,
+            old_images.len(),
+            requested_extent,
+            &debug_name,
+        )?;
+
+        self.destroy_virtual_swapchain(resources, &old_images);
+
+        self.resize_readback(resources, swapchain, requested_extent)?;
+
+        let internal_swapchain = resources.swapchains.get_mut(swapchain).unwrap();
+
+        internal_swapchain.extent = requested_extent;
+        internal_swapchain.images = images;
+        internal_swapchain.last_acquisition_index = None;
+        internal_swapchain.allow_acquisition = true;
+        internal_swapchain.recreate = false;
+        internal_swapchain.generation += 1;
+
+        Ok(())
+    }
+
+    ///Replaces the readback buffer of a recreated swapchain, if it has one.
+    pub(crate) fn resize_readback(
+        &self,
+        resources: &mut DeviceResources,
+        swapchain: Swapchain,
+        extent: vk::Extent2D,
+    ) -> Result<()> {
+        let InternalSwapchain {
+            readback,
+            debug_name,
+            ..
+        } = resources.swapchains.get(swapchain).unwrap();
+
+        let Some(Readback {
+            buffer: old_buffer, ..
+        }) = readback
+        else {
+            return Ok(());
+        };
+
+        let (old_buffer, debug_name) = (*old_buffer, debug_name.clone());
+
+        let buffer = self.create_readback_buffer(resources, extent, &debug_name)?;
+
+        self.destroy_readback_buffer(resources, old_buffer);
+
+        let internal_swapchain = resources.swapchains.get_mut(swapchain).unwrap();
+
+        let readback = internal_swapchain.readback.as_mut().unwrap();
+
+        readback.buffer = buffer;
+        readback.captured = false;
+
+        Ok(())
+    }
+
+    ///Closes the region opened by the last `begin_label`.
+    pub(crate) fn end_label(&self, command_buffer: vk::CommandBuffer) {
+        let Some(debug_utils) = &self.context.debug_utils else {
+            return;
+        };
+
+        unsafe { debug_utils.cmd_end_debug_utils_label(command_buffer) };
+    }
+}
+
+#[cfg(all(feature = "bindless"))]
+pub struct Bindless {
+    pub(crate) descriptor_set: vk::DescriptorSet,
+    pub(crate) descriptor_set_layout: vk::DescriptorSetLayout,
+    pub(crate) general_address_buffer: vk::Buffer,
+    pub(crate) general_address_memory: vk::DeviceMemory,
+    pub(crate) staging_address_buffer: vk::Buffer,
+    pub(crate) staging_address_memory: vk::DeviceMemory,
+}
+
+pub struct DeviceInfo<'a> {
+    pub selector: &'a dyn ops::Fn(Details) -> usize,
+    pub features: Features,
+    pub debug_name: &'a str,
+}
+
+impl Default for DeviceInfo<'_> {
+    fn default() -> Self {
+        Self {
+            selector: &default_device_selector,
+            features: Default::default(),
+            debug_name: "Device",
+        }
+    }
+}
+
+bitflags! {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct SampleCount: u32 {
+        const TYPE_1 = 0x0000_0001;
+        const TYPE_2 = 0x0000_0002;
+        const TYPE_4 = 0x0000_0004;
+        const TYPE_8 = 0x0000_0008;
+        const TYPE_16 = 0x0000_0010;
+        const TYPE_32 = 0x0000_0020;
+        const TYPE_64 = 0x0000_0040;
+    }
+}
+
+impl From<SampleCount> for vk::SampleCountFlags {
+    fn from(samples: SampleCount) -> Self {
+        Self::from_raw(samples.bits())
+    }
+}
+
+pub struct Details {
+    pub properties: Properties,
+    pub features: Features,
+}
+
+pub struct Properties {
+    pub device_type: DeviceType,
+    pub limits: Limits,
+}
+
+impl From<vk::PhysicalDeviceProperties> for Properties {
+    fn from(properties: vk::PhysicalDeviceProperties) -> Self {
+        Self {
+            device_type: properties.device_type.into(),
+            limits: properties.limits.into(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Features {
+    pub robust_buffer_access: bool,
+    pub full_draw_index_uint32: bool,
+    pub image_cube_array: bool,
+    pub independent_blend: bool,
+    pub geometry_shader: bool,
+    pub tessellation_shader: bool,
+    pub sample_rate_shading: bool,
+    pub dual_src_blend: bool,
+    pub logic_op: bool,
+    pub multi_draw_indirect: bool,
+    pub draw_indirect_first_instance: bool,
+    pub depth_clamp: bool,
+    pub depth_bias_clamp: bool,
+    pub fill_mode_non_solid: bool,
+    pub depth_bounds: bool,
+    pub wide_lines: bool,
+    pub large_points: bool,
+    pub alpha_to_one: bool,
+    pub multi_viewport: bool,
+    pub sampler_anisotropy: bool,
+    pub texture_compression_etc2: bool,
+    pub texture_compression_astc_ldr: bool,
+    pub texture_compression_bc: bool,
+    pub occlusion_query_precise: bool,
+    pub pipeline_statistics_query: bool,
+    pub vertex_pipeline_stores_and_atomics: bool,
+    pub fragment_stores_and_atomics: bool,
+    pub shader_tessellation_and_geometry_point_size: bool,
+    pub shader_image_gather_extended: bool,
+    pub shader_storage_image_extended_formats: bool,
+    pub shader_storage_image_multisample: bool,
+    pub shader_storage_image_read_without_format: bool,
+    pub shader_storage_image_write_without_format: bool,
+    pub shader_uniform_buffer_array_dynamic_indexing: bool,
+    pub shader_sampled_image_array_dynamic_indexing: bool,
+    pub shader_storage_buffer_array_dynamic_indexing: bool,
+    pub shader_storage_image_array_dynamic_indexing: bool,
+    pub shader_clip_distance: bool,
+    pub shader_cull_distance: bool,
+    pub shader_float64: bool,
+    pub shader_int64: bool,
+    pub shader_int16: bool,
+    pub shader_resource_residency: bool,
+    pub shader_resource_min_lod: bool,
+    pub sparse_binding: bool,
+    pub sparse_residency_buffer: bool,
+    pub sparse_residency_image2_d: bool,
+    pub sparse_residency_image3_d: bool,
+    pub sparse_residency2_samples: bool,
+    pub sparse_residency4_samples: bool,
+    pub sparse_residency8_samples: bool,
+    pub sparse_residency16_samples: bool,
+    pub sparse_residency_aliased: bool,
+    pub variable_multisample_rate: bool,
+    pub inherited_queries: bool,
+}
+
+impl From<Features> for vk::PhysicalDeviceFeatures {
+    fn from(features: Features) -> Self {
+        Self {
+            robust_buffer_access: features.robust_buffer_access as _,
+            full_draw_index_uint32: features.full_draw_index_uint32 as _,
+            image_cube_array: features.image_cube_array as _,
+            independent_blend: features.independent_blend as _,
+            geometry_shader: features.geometry_shader as _,
+            tessellation_shader: features.tessellation_shader as _,
+            sample_rate_shading: features.sample_rate_shading as _,
+            dual_src_blend: features.dual_src_blend as _,
+            logic_op: features.logic_op as _,
+            multi_draw_indirect: features.multi_draw_indirect as _,
+            draw_indirect_first_instance: features.draw_indirect_first_instance as _,
+            depth_clamp: features.depth_clamp as _,
+            depth_bias_clamp: features.depth_bias_clamp as _,
+            fill_mode_non_solid: features.fill_mode_non_solid as _,
+            depth_bounds: features.depth_bounds as _,
+            wide_lines: features.wide_lines as _,
+            large_points: features.large_points as _,
+            alpha_to_one: features.alpha_to_one as _,
+            multi_viewport: features.multi_viewport as _,
+            sampler_anisotropy: features.sampler_anisotropy as _,
+            texture_compression_etc2: features.texture_compression_etc2 as _,
+            texture_compression_astc_ldr: features.texture_compression_astc_ldr as _,
+            texture_compression_bc: features.texture_compression_bc as _,
+            occlusion_query_precise: features.occlusion_query_precise as _,
+            pipeline_statistics_query: features.pipeline_statistics_query as _,
+            vertex_pipeline_stores_and_atomics: features.vertex_pipeline_stores_and_atomics as _,
+            fragment_stores_and_atomics: features.fragment_stores_and_atomics as _,
+            shader_tessellation_and_geometry_point_size: features
+                .shader_tessellation_and_geometry_point_size
+                as _,
+            shader_image_gather_extended: features.shader_image_gather_extended as _,
+            shader_storage_image_extended_formats: features.shader_storage_image_extended_formats
+                as _,
+            shader_storage_image_multisample: features.shader_storage_image_multisample as _,
+            shader_storage_image_read_without_format: features
+                .shader_storage_image_read_without_format
+                as _,
+            shader_storage_image_write_without_format: features
+                .shader_storage_image_write_without_format
+                as _,
+            shader_uniform_buffer_array_dynamic_indexing: features
+                .shader_uniform_buffer_array_dynamic_indexing
+                as _,
+            shader_sampled_image_array_dynamic_indexing: features
+                .shader_sampled_image_array_dynamic_indexing
+                as _,
+            shader_storage_buffer_array_dynamic_indexing: features
+                .shader_storage_buffer_array_dynamic_indexing
+                as _,
+            shader_storage_image_array_dynamic_indexing: features
+                .shader_storage_image_array_dynamic_indexing
+                as _,
+            shader_clip_distance: features.shader_clip_distance as _,
+            shader_cull_distance: features.shader_cull_distance as _,
+            shader_float64: features.shader_float64 as _,
+            shader_int64: features.shader_int64 as _,
+            shader_int16: features.shader_int16 as _,
+            shader_resource_residency: features.shader_resource_residency as _,
+            shader_resource_min_lod: features.shader_resource_min_lod as _,
+            sparse_binding: features.sparse_binding as _,
+            sparse_residency_buffer: features.sparse_residency_buffer as _,
+            sparse_residency_image2_d: features.sparse_residency_image2_d as _,
+            sparse_residency_image3_d: features.sparse_residency_image3_d as _,
+            sparse_residency2_samples: features.sparse_residency2_samples as _,
+            sparse_residency4_samples: features.sparse_residency4_samples as _,
+            sparse_residency8_samples: features.sparse_residency8_samples as _,
+            sparse_residency16_samples: features.sparse_residency16_samples as _,
+            sparse_residency_aliased: features.sparse_residency_aliased as _,
+            variable_multisample_rate: features.variable_multisample_rate as _,
+            inherited_queries: features.inherited_queries as _,
+        }
+    }
+}
+
+impl From<vk::PhysicalDeviceFeatures> for Features {
+    fn from(features: vk::PhysicalDeviceFeatures) -> Self {
+        Self {
+            robust_buffer_access: features.robust_buffer_access != 0,
+            full_draw_index_uint32: features.full_draw_index_uint32 != 0,
+            image_cube_array: features.image_cube_array != 0,
+            independent_blend: features.independent_blend != 0,
+            geometry_shader: features.geometry_shader != 0,
+            tessellation_shader: features.tessellation_shader != 0,
+            sample_rate_shading: features.sample_rate_shading != 0,
+            dual_src_blend: features.dual_src_blend != 0,
+            logic_op: features.logic_op != 0,
+            multi_draw_indirect: features.multi_draw_indirect != 0,
+            draw_indirect_first_instance: features.draw_indirect_first_instance != 0,
+            depth_clamp: features.depth_clamp != 0,
+            depth_bias_clamp: features.depth_bias_clamp != 0,
+            fill_mode_non_solid: features.fill_mode_non_solid != 0,
+            depth_bounds: features.depth_bounds != 0,
+            wide_lines: features.wide_lines != 0,
+            large_points: features.large_points != 0,
+            alpha_to_one: features.alpha_to_one != 0,
+            multi_viewport: features.multi_viewport != 0,
+            sampler_anisotropy: features.sampler_anisotropy != 0,
+            texture_compression_etc2: features.texture_compression_etc2 != 0,
+            texture_compression_astc_ldr: features.texture_compression_astc_ldr != 0,
+            texture_compression_bc: features.texture_compression_bc != 0,
+            occlusion_query_precise: features.occlusion_query_precise != 0,
+            pipeline_statistics_query: features.pipeline_statistics_query != 0,
+            vertex_pipeline_stores_and_atomics: features.vertex_pipeline_stores_and_atomics != 0,
+            fragment_stores_and_atomics: features.fragment_stores_and_atomics != 0,
+            shader_tessellation_and_geometry_point_size: features
+                .shader_tessellation_and_geometry_point_size
+                != 0,
+            shader_image_gather_extended: features.shader_image_gather_extended != 0,
+            shader_storage_image_extended_formats: features.shader_storage_image_extended_formats
+                != 0,
+            shader_storage_image_multisample: features.shader_storage_image_multisample != 0,
+            shader_storage_image_read_without_format: features
+                .shader_storage_image_read_without_format
+                != 0,
+            shader_storage_image_write_without_format: features
+                .shader_storage_image_write_without_format
+                != 0,
+            shader_uniform_buffer_array_dynamic_indexing: features
+                .shader_uniform_buffer_array_dynamic_indexing
+                != 0,
+            shader_sampled_image_array_dynamic_indexing: features
+                .shader_sampled_image_array_dynamic_indexing
+                != 0,
+            shader_storage_buffer_array_dynamic_indexing: features
+                .shader_storage_buffer_array_dynamic_indexing
+                != 0,
+            shader_storage_image_array_dynamic_indexing: features
+                .shader_storage_image_array_dynamic_indexing
+                != 0,
+            shader_clip_distance: features.shader_clip_distance != 0,
+            shader_cull_distance: features.shader_cull_distance != 0,
+            shader_float64: features.shader_float64 != 0,
+            shader_int64: features.shader_int64 != 0,
+            shader_int16: features.shader_int16 != 0,
+            shader_resource_residency: features.shader_resource_residency != 0,
+            shader_resource_min_lod: features.shader_resource_min_lod != 0,
+            sparse_binding: features.sparse_binding != 0,
+            sparse_residency_buffer: features.sparse_residency_buffer != 0,
+            sparse_residency_image2_d: features.sparse_residency_image2_d != 0,
+            sparse_residency_image3_d: features.sparse_residency_image3_d != 0,
             sparse_residency2_samples: features.sparse_residency2_samples != 0,
             sparse_residency4_samples: features.sparse_residency4_samples != 0,
             sparse_residency8_samples: features.sparse_residency8_samples != 0,
@@ -758,25 +2154,39 @@ impl Device {
     ) -> Result<RenderGraphBuilder<'a, T>> {
         let RenderGraphInfo {
             debug_name,
-            swapchain,
+            swapchains,
+            recording_threads,
+            validate,
         } = info;
 
         let debug_name = debug_name.to_owned();
 
         let nodes = vec![];
 
-        self.inner
-            .resources
-            .lock()
-            .unwrap()
-            .swapchains
-            .get(swapchain)
-            .ok_or(Error::ResourceNotFound)?;
+        {
+            let resources = self.inner.resources.lock().unwrap();
+
+            if swapchains.is_empty() {
+                Err(Error::ResourceNotFound)?
+            }
+
+            for &swapchain in &swapchains {
+                resources
+                    .swapchains
+                    .get(swapchain)
+                    .ok_or(Error::ResourceNotFound)?;
+            }
+        }
 
         Ok(RenderGraphBuilder {
             device: self.inner.clone(),
-            swapchain,
+            swapchains,
             nodes,
+            recording_threads,
+            record_batch: None,
+            scope: vec![],
+            on_resize: vec![],
+            validate,
             debug_name,
         })
     }
@@ -867,134 +2277,129 @@ impl Device {
 
     ///Creates an image of the user's specification.
     pub fn create_image(&self, info: ImageInfo<'_>) -> Result<Image> {
+        let mut resources = self.inner.resources.lock().unwrap();
+
+        self.inner.create_image(&mut resources, info)
+    }
+
+    ///Creates another view of an image, with its own handle that can be used like any other image,
+    ///for instance as an attachment or a bindless image.
+    ///A view of a view views the same image. The render graph synchronizes views together with their image.
+    ///Views of the images of a virtual swapchain are destroyed when it is recreated or destroyed.
+    pub fn create_image_view(&self, image: Image, info: ImageViewInfo<'_>) -> Result<Image> {
         let DeviceInner {
-            context,
-            physical_device,
             logical_device,
             resources,
+            features,
             ..
         } = &*self.inner;
 
         let mut resources = resources.lock().unwrap();
 
-        let ContextInner { instance, .. } = &**context;
-
-        let ImageInfo {
-            extent,
-            usage,
+        let ImageViewInfo {
+            view_type,
             format,
+            range,
+            swizzle,
             debug_name,
         } = info;
 
-        let (image_type, view_type, extent) = match extent {
-            ImageExtent::OneDim(x) => (
-                vk::ImageType::TYPE_1D,
-                vk::ImageViewType::TYPE_1D,
-                vk::Extent3D {
-                    width: x as _,
-                    height: 1,
-                    depth: 1,
-                },
-            ),
-            ImageExtent::TwoDim(x, y) => (
-                vk::ImageType::TYPE_2D,
-                vk::ImageViewType::TYPE_2D,
-                vk::Extent3D {
-                    width: x as _,
-                    height: y as _,
-                    depth: 1,
-                },
-            ),
-            ImageExtent::ThreeDim(x, y, z) => (
-                vk::ImageType::TYPE_3D,
-                vk::ImageViewType::TYPE_3D,
-                vk::Extent3D {
-                    width: x as _,
-                    height: y as _,
-                    depth: z as _,
-                },
-            ),
-        };
+        let internal_image = resources.images.get(image).ok_or(Error::ResourceNotFound)?;
 
-        let mut usage = vk::ImageUsageFlags::from(usage);
+        if let InternalImage::Swapchain { .. } = internal_image {
+            Err(Error::InvalidResource)?
+        }
+
+        let range = internal_image.to_parent(range.resolve(
+            internal_image.get_mip_levels(),
+            internal_image.get_array_layers(),
+        ));
 
-        if (usage & vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT) == vk::ImageUsageFlags::empty() {
-            usage |= vk::ImageUsageFlags::STORAGE;
+        if range.mip_level_count == 0 || range.array_layer_count == 0 {
+            Err(Error::InvalidResource)?
         }
 
-        let image_create_info = vk::ImageCreateInfo {
-            image_type,
-            extent,
-            format: format.into(),
-            usage,
-            array_layers: 1,
-            mip_levels: 1,
-            tiling: vk::ImageTiling::OPTIMAL,
-            sharing_mode: vk::SharingMode::EXCLUSIVE,
-            samples: vk::SampleCountFlags::TYPE_1,
-            ..Default::default()
+        let view_type = match view_type {
+            Some(view_type) => view_type.into(),
+            None => {
+                use vk::ImageViewType as T;
+
+                match (internal_image.get_view_type(), range.array_layer_count) {
+                    (T::TYPE_1D | T::TYPE_1D_ARRAY, 1) => T::TYPE_1D,
+                    (T::TYPE_1D | T::TYPE_1D_ARRAY, _) => T::TYPE_1D_ARRAY,
+                    (T::CUBE | T::CUBE_ARRAY, 6) => T::CUBE,
+                    (T::CUBE | T::CUBE_ARRAY, layers) if layers % 6 == 0 => T::CUBE_ARRAY,
+                    (T::TYPE_3D, _) => T::TYPE_3D,
+                    (_, 1) => T::TYPE_2D,
+                    _ => T::TYPE_2D_ARRAY,
+                }
+            }
         };
 
-        let image = unsafe { logical_device.create_image(&image_create_info, None) }
-            .map_err(|_| Error::Creation)?;
+        if view_type == vk::ImageViewType::CUBE_ARRAY && features.image_cube_array == 0 {
+            Err(Error::Creation)?
+        }
 
-        let memory_requirements = unsafe { logical_device.get_image_memory_requirements(image) };
+        let format = format.unwrap_or(internal_image.get_format());
 
-        let memory_properties =
-            unsafe { instance.get_physical_device_memory_properties(*physical_device) };
+        let parent = internal_image.get_parent().unwrap_or(image);
 
-        let memory_type_index = memory::type_index(
-            &memory_requirements,
-            &memory_properties,
-            Memory::empty().into(),
-        )?;
+        let root = resources
+            .images
+            .get(parent)
+            .ok_or(Error::ResourceNotFound)?;
 
-        let allocation_size = memory_requirements.size;
+        if format != root.get_format() && !root.get_view_formats().contains(&format) {
+            Err(Error::IncompatibleViewFormat)?
+        }
 
-        let memory_allocate_info = {
-            vk::MemoryAllocateInfo {
-                allocation_size,
-                memory_type_index,
-                ..Default::default()
-            }
-        };
+        //Storage image descriptors have to read every component from where it is.
+        let mut identity = swizzle.iter().zip([
+            ComponentSwizzle::R,
+            ComponentSwizzle::G,
+            ComponentSwizzle::B,
+            ComponentSwizzle::A,
+        ]);
 
-        let memory = unsafe { logical_device.allocate_memory(&memory_allocate_info, None) }
-            .map_err(|_| Error::Creation)?;
+        let mut usage = root.get_usage();
 
-        unsafe { logical_device.bind_image_memory(image, memory, 0) }
-            .map_err(|_| Error::Creation)?;
+        if !identity.all(|(&swizzle, component)| {
+            swizzle == ComponentSwizzle::Identity || swizzle == component
+        }) {
+            usage &= !vk::ImageUsageFlags::STORAGE;
+        }
+
+        let [r, g, b, a] = swizzle.map(vk::ComponentSwizzle::from);
 
         let image_view_create_info = vk::ImageViewCreateInfo {
-            image,
+            image: root.get_image(),
             view_type,
             format: format.into(),
-            components: vk::ComponentMapping {
-                r: vk::ComponentSwizzle::IDENTITY,
-                g: vk::ComponentSwizzle::IDENTITY,
-                b: vk::ComponentSwizzle::IDENTITY,
-                a: vk::ComponentSwizzle::IDENTITY,
-            },
+            components: vk::ComponentMapping { r, g, b, a },
             subresource_range: vk::ImageSubresourceRange {
                 aspect_mask: format.entire_aspect(),
-                base_mip_level: 0,
-                level_count: 1,
-                base_array_layer: 0,
-                layer_count: 1,
+                ..range.into()
             },
-
             ..Default::default()
         };
 
-        let view =
-            unsafe { logical_device.create_image_view(&image_view_create_info, None) }.unwrap();
+        let view = unsafe { logical_device.create_image_view(&image_view_create_info, None) }
+            .map_err(|_| Error::Creation)?;
 
-        Ok(resources.images.add(InternalImage::Managed {
-            image,
-            memory,
+        let internal_image = InternalImage::View {
+            parent,
+            image: root.get_image(),
             view,
+            view_type,
             format,
-        }))
+            extent: mipmap::mip_extent(root.get_extent(), range.base_mip_level),
+            range,
+            samples: root.get_samples(),
+            usage,
+            debug_name: debug_name.to_owned(),
+        };
+
+        Ok(resources.images.add(internal_image))
     }
 
     ///Destroys a buffer immedietely.
@@ -1020,89 +2425,92 @@ impl Device {
         return Ok(());
     }
 
-    ///Creates a buffer of the user's specification.
-    pub fn create_buffer(&self, info: BufferInfo<'_>) -> Result<Buffer> {
-        let DeviceInner {
-            context,
-            physical_device,
-            logical_device,
-            resources,
-            ..
-        } = &*self.inner;
-
-        let mut resources = resources.lock().unwrap();
-
-        let ContextInner { instance, .. } = &**context;
-
-        let BufferInfo {
-            size,
-            usage,
-            memory: properties,
-            debug_name,
-        } = info;
-
-        let size = size as _;
-
-        let allocation_size = size as _;
-
-        let mut usage = usage.into();
+    ///Creates a sampler, for sampled image and combined image sampler bindings.
+    pub fn create_sampler(&self, info: SamplerInfo<'_>) -> Result<Sampler> {
+        let DeviceInner {
+            context,
+            physical_device,
+            logical_device,
+            resources,
+            features,
+            ..
+        } = &*self.inner;
 
-        usage |= vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS;
+        let ContextInner { instance, .. } = &**context;
 
-        let debug_name = debug_name.to_owned();
+        let SamplerInfo {
+            mag_filter,
+            min_filter,
+            mipmap_filter,
+            address_mode: [address_mode_u, address_mode_v, address_mode_w],
+            mip_lod_bias,
+            min_lod,
+            max_lod,
+            anisotropy,
+            compare,
+            border_color,
+            ..
+        } = info;
 
-        let sharing_mode = vk::SharingMode::EXCLUSIVE;
+        if anisotropy.is_some() && features.sampler_anisotropy == vk::FALSE {
+            Err(Error::Creation)?
+        }
 
-        let buffer_create_info = vk::BufferCreateInfo {
-            size,
-            usage,
-            sharing_mode,
+        let limits = unsafe { instance.get_physical_device_properties(*physical_device) }.limits;
+
+        let sampler_create_info = vk::SamplerCreateInfo {
+            mag_filter: mag_filter.into(),
+            min_filter: min_filter.into(),
+            mipmap_mode: mipmap_filter.into(),
+            address_mode_u: address_mode_u.into(),
+            address_mode_v: address_mode_v.into(),
+            address_mode_w: address_mode_w.into(),
+            mip_lod_bias,
+            anisotropy_enable: anisotropy.is_some() as _,
+            max_anisotropy: anisotropy
+                .unwrap_or(1.0)
+                .clamp(1.0, limits.max_sampler_anisotropy),
+            compare_enable: compare.is_some() as _,
+            compare_op: compare.unwrap_or(CompareOp::Always).into(),
+            min_lod,
+            max_lod,
+            border_color: border_color.into(),
             ..Default::default()
         };
 
-        let buffer = unsafe { logical_device.create_buffer(&buffer_create_info, None) }
+        let sampler = unsafe { logical_device.create_sampler(&sampler_create_info, None) }
             .map_err(|_| Error::Creation)?;
 
-        let memory_requirements = unsafe { logical_device.get_buffer_memory_requirements(buffer) };
-
-        let memory_properties =
-            unsafe { instance.get_physical_device_memory_properties(*physical_device) };
+        let mut resources = resources.lock().unwrap();
 
-        let memory_type_index =
-            memory::type_index(&memory_requirements, &memory_properties, properties.into())?;
+        Ok(resources.samplers.add(InternalSampler { sampler }))
+    }
 
-        let mut memory_allocate_flags_info = vk::MemoryAllocateFlagsInfo {
-            flags: vk::MemoryAllocateFlags::DEVICE_ADDRESS,
-            ..Default::default()
-        };
+    ///Destroys a sampler immedietely.
+    pub fn destroy_sampler(&self, sampler: Sampler) -> Result<()> {
+        let DeviceInner {
+            logical_device,
+            resources,
+            ..
+        } = &*self.inner;
 
-        let memory_allocate_info = {
-            let p_next = &mut memory_allocate_flags_info as *mut _ as *mut _;
-            vk::MemoryAllocateInfo {
-                p_next,
-                allocation_size,
-                memory_type_index,
-                ..Default::default()
-            }
-        };
+        let mut resources = resources.lock().unwrap();
 
-        let memory = unsafe { logical_device.allocate_memory(&memory_allocate_info, None) }
-            .map_err(|_| Error::AllocateMemory)?;
+        let internal_sampler = resources
+            .samplers
+            .remove(sampler)
+            .ok_or(Error::ResourceNotFound)?;
 
-        unsafe { logical_device.bind_buffer_memory(buffer, memory, 0) }
-            .map_err(|_| Error::Creation)?;
+        unsafe { logical_device.destroy_sampler(internal_sampler.sampler, None) };
 
-        let memory = InternalMemory { memory, properties };
+        Ok(())
+    }
 
-        let BufferInfo { size, usage, .. } = info;
+    ///Creates a buffer of the user's specification.
+    pub fn create_buffer(&self, info: BufferInfo<'_>) -> Result<Buffer> {
+        let mut resources = self.inner.resources.lock().unwrap();
 
-        Ok(resources.buffers.add(InternalBuffer {
-            buffer,
-            memory,
-            size,
-            usage,
-            debug_name,
-        }))
+        self.inner.create_buffer(&mut resources, info)
     }
 
     ///Creates a binary semaphore, useful for GPU synchronization.
@@ -1241,307 +2649,690 @@ impl Device {
         Ok(images.clone())
     }
 
-    pub fn get_current_frame_index(&self, swapchain: Swapchain) -> Result<usize> {
-        let DeviceInner { resources, .. } = &*self.inner;
+    pub fn get_current_frame_index(&self, swapchain: Swapchain) -> Result<usize> {
+        let DeviceInner { resources, .. } = &*self.inner;
+
+        let mut resources = resources.lock().unwrap();
+        let InternalSwapchain {
+            loader,
+            handle,
+            images,
+            last_acquisition_index,
+            current_frame,
+            allow_acquisition,
+            ..
+        } = resources
+            .swapchains
+            .get_mut(swapchain)
+            .ok_or(Error::ResourceNotFound)?;
+
+        Ok(*current_frame)
+    }
+
+    ///Gets the next presentable image from the swapchain.
+    pub fn acquire_next_image(&self, acquire: Acquire) -> Result<Image> {
+        let DeviceInner { resources, .. } = &*self.inner;
+
+        let mut resources = resources.lock().unwrap();
+
+        let semaphores = if let Some(handle) = acquire.semaphore {
+            resources
+                .binary_semaphores
+                .get(handle)
+                .ok_or(Error::InvalidResource)?
+                .semaphores
+                .clone()
+        } else {
+            vec![]
+        };
+
+        let InternalSwapchain {
+            images,
+            last_acquisition_index,
+            allow_acquisition,
+            recreate,
+            ..
+        } = resources
+            .swapchains
+            .get(acquire.swapchain)
+            .ok_or(Error::ResourceNotFound)?;
+
+        if !*allow_acquisition {
+            return last_acquisition_index
+                .map(|i| images[i as usize])
+                .ok_or(Error::FailedToAcquire);
+        }
+
+        if *recreate {
+            self.inner
+                .recreate_swapchain(&mut resources, acquire.swapchain)?;
+        }
+
+        if resources
+            .swapchains
+            .get(acquire.swapchain)
+            .unwrap()
+            .capture
+            .is_some()
+        {
+            return self.inner.acquire_virtual_image(
+                &mut resources,
+                acquire.swapchain,
+                &semaphores,
+            );
+        }
+
+        //An out of date swapchain is recreated once, then acquired from again.
+        for _ in 0..2 {
+            let InternalSwapchain {
+                loader,
+                handle,
+                images,
+                last_acquisition_index,
+                current_frame,
+                allow_acquisition,
+                recreate,
+                ..
+            } = resources.swapchains.get_mut(acquire.swapchain).unwrap();
+
+            let semaphore = if !semaphores.is_empty() {
+                semaphores[*current_frame]
+            } else {
+                vk::Semaphore::null()
+            };
+
+            match unsafe {
+                loader.acquire_next_image(*handle, u64::MAX, semaphore, vk::Fence::null())
+            } {
+                Ok((next_image_index, suboptimal)) => {
+                    *allow_acquisition = false;
+                    *last_acquisition_index = Some(next_image_index);
+                    //The image is still presentable, so the swapchain is recreated next frame.
+                    *recreate |= suboptimal;
+
+                    return Ok(images[next_image_index as usize]);
+                }
+                Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => self
+                    .inner
+                    .recreate_swapchain(&mut resources, acquire.swapchain)?,
+                Err(_) => Err(Error::FailedToAcquire)?,
+            }
+        }
+
+        Err(Error::FailedToAcquire)
+    }
+
+    ///Gets the swapchain image's format.
+    pub fn presentation_format(&self, swapchain: Swapchain) -> Result<Format> {
+        let DeviceInner { resources, .. } = &*self.inner;
+
+        let resources = resources.lock().unwrap();
+
+        let InternalSwapchain { format, .. } = resources
+            .swapchains
+            .get(swapchain)
+            .ok_or(Error::ResourceNotFound)?;
+
+        Ok(*format)
+    }
+
+    ///Gets the image's format.
+    pub fn image_format(&self, image: Image) -> Result<Format> {
+        let DeviceInner { resources, .. } = &*self.inner;
+
+        let resources = resources.lock().unwrap();
+
+        Ok(resources
+            .images
+            .get(image)
+            .ok_or(Error::ResourceNotFound)?
+            .get_format())
+    }
+
+    ///Gets what the device supports for a format, to choose fallbacks before creating images.
+    pub fn format_properties(&self, format: Format) -> FormatProperties {
+        let DeviceInner {
+            context,
+            physical_device,
+            features,
+            ..
+        } = &*self.inner;
+
+        let ContextInner { instance, .. } = &**context;
+
+        let vk::FormatProperties {
+            linear_tiling_features,
+            optimal_tiling_features,
+            buffer_features,
+        } = unsafe {
+            instance.get_physical_device_format_properties(*physical_device, format.into())
+        };
+
+        FormatProperties {
+            optimal_tiling: optimal_tiling_features.into(),
+            linear_tiling: linear_tiling_features.into(),
+            vertex_buffer: buffer_features.contains(vk::FormatFeatureFlags::VERTEX_BUFFER),
+            enabled: format.is_enabled(features),
+        }
+    }
+
+    ///Gets the limits of images created like `info`, or `None` when such an image cannot be created.
+    ///The extent, mip levels, array layers and samples of `info` are checked against those limits.
+    pub fn image_format_supported(
+        &self,
+        info: ImageInfo<'_>,
+    ) -> Result<Option<ImageFormatProperties>> {
+        let DeviceInner {
+            context,
+            physical_device,
+            ..
+        } = &*self.inner;
+
+        let ContextInner { instance, .. } = &**context;
+
+        //Whatever `create_image` rejects is not supported either.
+        let Ok(ImageDesc {
+            image_type,
+            extent,
+            mip_levels,
+            array_layers,
+            flags,
+            usage,
+            samples,
+            ..
+        }) = self.inner.describe_image(&info)
+        else {
+            return Ok(None);
+        };
+
+        let image_format_properties = match unsafe {
+            instance.get_physical_device_image_format_properties(
+                *physical_device,
+                info.format.into(),
+                image_type,
+                vk::ImageTiling::OPTIMAL,
+                usage,
+                flags,
+            )
+        } {
+            Ok(image_format_properties) => image_format_properties,
+            Err(vk::Result::ERROR_FORMAT_NOT_SUPPORTED) => return Ok(None),
+            Err(_) => Err(Error::Creation)?,
+        };
+
+        let fits = extent.width <= image_format_properties.max_extent.width
+            && extent.height <= image_format_properties.max_extent.height
+            && extent.depth <= image_format_properties.max_extent.depth
+            && mip_levels <= image_format_properties.max_mip_levels
+            && array_layers <= image_format_properties.max_array_layers
+            && image_format_properties.sample_counts.contains(samples);
+
+        Ok(fits.then(|| image_format_properties.into()))
+    }
+
+    ///Stops all execution until the GPU is done processing its current workload.
+    ///Usually, you would not want to use this, as it slows execution to a crawl if used in a hot loop.
+    ///It is more useful for cleaning up and the like.
+    pub fn wait_idle(&self) {
+        let DeviceInner { logical_device, .. } = &*self.inner;
+
+        unsafe { logical_device.device_wait_idle() };
+    }
+
+    ///A swapchain holds the images which will be used for drawing to the screen.
+    ///It usually holds more than one image, which prevents a number of issues such as tearing.
+    ///Call `acquire_next_image` to get the current swapchain image.
+    ///Each swapchain has its own surface, created from the display and window in `info`,
+    ///so one device can present to many windows.
+    ///In a headless context the surface has no window, and the swapchain keeps the size it is given.
+    ///When the surface changes size, the swapchain is recreated in place and keeps its handle.
+    pub fn create_swapchain(&self, info: SwapchainInfo<'_>) -> Result<Swapchain> {
+        let DeviceInner {
+            context,
+            surface_loader,
+            physical_device,
+            logical_device,
+            resources,
+            ..
+        } = &*self.inner;
+
+        let ContextInner { instance, .. } = &**context;
+
+        let surface = self.inner.create_surface(info.display, info.window)?;
+
+        //Every failure from here on has to give the surface back.
+        let discard_surface = |error| {
+            unsafe { surface_loader.destroy_surface(surface, None) };
+
+            error
+        };
+
+        //Swapchains are presented on the graphics queue.
+        let present_support = unsafe {
+            surface_loader.get_physical_device_surface_support(
+                *physical_device,
+                self.inner.queue_family_index(Queue::Graphics),
+                surface,
+            )
+        }
+        .unwrap_or(false);
+
+        if !present_support {
+            Err(discard_surface(Error::PresentNotSupported))?
+        }
+
+        let mut resources = resources.lock().unwrap();
+
+        let surface_formats = unsafe {
+            surface_loader.get_physical_device_surface_formats(*physical_device, surface)
+        }
+        .map_err(|_| discard_surface(Error::Creation))?;
+
+        let selector = info.surface_format_selector;
+
+        let Some(surface_format) = surface_formats
+            .into_iter()
+            .filter_map(|surface_format| {
+                let score = selector(surface_format.try_into().ok()?);
+
+                Some((score, surface_format))
+            })
+            .max_by_key(|(score, _)| *score)
+            .map(|(_, surface_format)| surface_format)
+        else {
+            Err(discard_surface(Error::UnsupportedFormat))?
+        };
+
+        let loader = khr::Swapchain::new(&instance, &logical_device);
+
+        let present_modes = unsafe {
+            surface_loader.get_physical_device_surface_present_modes(*physical_device, surface)
+        }
+        .map_err(|_| discard_surface(Error::Creation))?;
+
+        //FIFO is always supported, so the fallbacks always end in a supported mode.
+        let present_mode = info
+            .present_mode
+            .fallbacks()
+            .iter()
+            .map(|&present_mode| vk::PresentModeKHR::from(present_mode))
+            .find(|present_mode| present_modes.contains(present_mode))
+            .unwrap_or(vk::PresentModeKHR::FIFO);
+
+        let requested_extent = vk::Extent2D {
+            width: info.width,
+            height: info.height,
+        };
+
+        let format = surface_format
+            .format
+            .try_into()
+            .map_err(|_| discard_surface(Error::Creation))?;
+
+        if info.allow_capture && !capture::is_capturable(format) {
+            Err(discard_surface(Error::UnsupportedCaptureFormat))?
+        }
+
+        let image_usage = if info.allow_capture {
+            info.image_usage | ImageUsage::TRANSFER_SRC
+        } else {
+            info.image_usage
+        };
+
+        let (handle, extent, images) = self
+            .inner
+            .build_swapchain(
+                &mut resources,
+                &loader,
+                SwapchainDesc {
+                    surface,
+                    surface_format,
+                    present_mode,
+                    requested_extent,
+                    image_usage,
+                    old_swapchain: vk::SwapchainKHR::null(),
+                    debug_name: info.debug_name,
+                },
+            )
+            .map_err(discard_surface)?;
+
+        let readback = if info.allow_capture {
+            match self
+                .inner
+                .create_readback(&mut resources, extent, info.debug_name)
+            {
+                Ok(readback) => Some(readback),
+                Err(error) => {
+                    self.inner.remove_swapchain_images(&mut resources, &images);
+
+                    unsafe { loader.destroy_swapchain(handle, None) };
+
+                    Err(discard_surface(error))?
+                }
+            }
+        } else {
+            None
+        };
+
+        let last_acquisition_index = None;
+
+        let current_frame = 0;
+
+        let allow_acquisition = true;
 
-        let mut resources = resources.lock().unwrap();
-        let InternalSwapchain {
+        Ok(resources.swapchains.add(InternalSwapchain {
             loader,
             handle,
+            format,
             images,
             last_acquisition_index,
             current_frame,
             allow_acquisition,
+            surface,
+            surface_format,
+            present_mode,
+            requested_extent,
+            extent,
+            recreate: false,
+            generation: 0,
+            debug_name: info.debug_name.to_owned(),
+            image_usage,
+            readback,
+            capture: None,
+        }))
+    }
+
+    ///Creates a virtual swapchain, which needs no window and writes every frame presented to it to disk.
+    ///Presenting to it stalls until the frame is written, so it is meant for captures rather than real time.
+    pub fn create_virtual_swapchain(&self, info: VirtualSwapchainInfo<'_>) -> Result<Swapchain> {
+        let DeviceInner {
+            context,
+            logical_device,
+            resources,
             ..
-        } = resources
-            .swapchains
-            .get_mut(swapchain)
-            .ok_or(Error::ResourceNotFound)?;
+        } = &*self.inner;
 
-        Ok(*current_frame)
-    }
+        let ContextInner { instance, .. } = &**context;
 
-    ///Gets the next presentable image from the swapchain.
-    pub fn acquire_next_image(&self, acquire: Acquire) -> Result<Image> {
-        let DeviceInner { resources, .. } = &*self.inner;
+        if !capture::is_capturable(info.format) {
+            Err(Error::UnsupportedCaptureFormat)?
+        }
+
+        if info.image_count == 0 {
+            Err(Error::InvalidImageCount)?
+        }
 
         let mut resources = resources.lock().unwrap();
 
-        let semaphores = if let Some(handle) = acquire.semaphore {
-            resources
-                .binary_semaphores
-                .get(handle)
-                .ok_or(Error::InvalidResource)?
-                .semaphores
-                .clone()
-        } else {
-            vec![]
+        let extent = vk::Extent2D {
+            width: info.width,
+            height: info.height,
         };
 
-        let InternalSwapchain {
-            loader,
-            handle,
-            images,
-            last_acquisition_index,
-            current_frame,
-            allow_acquisition,
-            ..
-        } = resources
-            .swapchains
-            .get_mut(acquire.swapchain)
-            .ok_or(Error::ResourceNotFound)?;
+        let images = self.inner.build_virtual_swapchain(
+            &mut resources,
+            info.format,
+            info.image_usage,
+            info.image_count,
+            extent,
+            info.debug_name,
+        )?;
 
-        let semaphore = if semaphores.len() > 0 {
-            semaphores[*current_frame]
-        } else {
-            vk::Semaphore::null()
+        let readback = match self
+            .inner
+            .create_readback(&mut resources, extent, info.debug_name)
+        {
+            Ok(readback) => readback,
+            Err(error) => {
+                self.inner
+                    .destroy_virtual_swapchain(&mut resources, &images);
+
+                return Err(error);
+            }
         };
 
-        if !*allow_acquisition {
-            return last_acquisition_index
-                .map(|i| images[i as usize])
-                .ok_or(Error::FailedToAcquire);
-        }
+        let CaptureInfo {
+            directory,
+            file_prefix,
+            file_format,
+            frame_count,
+        } = info.capture;
+
+        let capture = Capture {
+            directory: directory.to_owned(),
+            file_prefix: file_prefix.to_owned(),
+            file_format,
+            frame_count,
+            frames_written: 0,
+        };
 
-        let (next_image_index, suboptimal) =
-            unsafe { loader.acquire_next_image(*handle, u64::MAX, semaphore, vk::Fence::null()) }
-                .map_err(|_| Error::FailedToAcquire)?;
+        Ok(resources.swapchains.add(InternalSwapchain {
+            format: info.format,
+            loader: khr::Swapchain::new(&instance, &logical_device),
+            handle: vk::SwapchainKHR::null(),
+            images,
+            last_acquisition_index: None,
+            current_frame: 0,
+            allow_acquisition: true,
+            surface: vk::SurfaceKHR::null(),
+            surface_format: vk::SurfaceFormatKHR {
+                format: info.format.into(),
+                color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
+            },
+            present_mode: vk::PresentModeKHR::FIFO,
+            requested_extent: extent,
+            extent,
+            recreate: false,
+            generation: 0,
+            debug_name: info.debug_name.to_owned(),
+            image_usage: info.image_usage,
+            readback: Some(readback),
+            capture: Some(capture),
+        }))
+    }
 
-        *allow_acquisition = false;
-        *last_acquisition_index = Some(next_image_index);
+    ///Gets the image last presented to a swapchain created with `allow_capture`, such as for a screenshot.
+    ///Waits for its copy to finish, which it does shortly after the frame is presented.
+    ///Fails with `NothingCaptured` until a frame has been presented since it was created or resized.
+    pub fn capture_swapchain(&self, swapchain: Swapchain) -> Result<Screenshot> {
+        let DeviceInner {
+            logical_device,
+            resources,
+            ..
+        } = &*self.inner;
 
-        Ok(images[next_image_index as usize])
-    }
+        let fence = {
+            let resources = resources.lock().unwrap();
 
-    ///Gets the swapchain image's format.
-    pub fn presentation_format(&self, swapchain: Swapchain) -> Result<Format> {
-        let DeviceInner { resources, .. } = &*self.inner;
+            let InternalSwapchain { readback, .. } = resources
+                .swapchains
+                .get(swapchain)
+                .ok_or(Error::ResourceNotFound)?;
 
-        let resources = resources.lock().unwrap();
+            readback.as_ref().ok_or(Error::InvalidResource)?.fence
+        };
 
-        let InternalSwapchain { format, .. } = resources
-            .swapchains
-            .get(swapchain)
-            .ok_or(Error::ResourceNotFound)?;
+        //Waits without the resources locked, so that other threads can keep rendering meanwhile.
+        unsafe { logical_device.wait_for_fences(&[fence], true, u64::MAX) }
+            .map_err(|_| Error::CaptureFailed)?;
 
-        Ok(*format)
+        let resources = resources.lock().unwrap();
+
+        self.inner.screenshot(&resources, swapchain)
     }
 
-    ///Gets the image's format.
-    pub fn image_format(&self, image: Image) -> Result<Format> {
+    ///Whether a virtual swapchain has written all the frames it was asked for.
+    pub fn capture_complete(&self, swapchain: Swapchain) -> Result<bool> {
         let DeviceInner { resources, .. } = &*self.inner;
 
         let resources = resources.lock().unwrap();
 
-        Ok(resources
-            .images
-            .get(image)
-            .ok_or(Error::ResourceNotFound)?
-            .get_format())
-    }
-
-    ///Stops all execution until the GPU is done processing its current workload.
-    ///Usually, you would not want to use this, as it slows execution to a crawl if used in a hot loop.
-    ///It is more useful for cleaning up and the like.
-    pub fn wait_idle(&self) {
-        let DeviceInner { logical_device, .. } = &*self.inner;
+        let InternalSwapchain { capture, .. } = resources
+            .swapchains
+            .get(swapchain)
+            .ok_or(Error::ResourceNotFound)?;
 
-        unsafe { logical_device.device_wait_idle() };
+        Ok(capture
+            .as_ref()
+            .ok_or(Error::InvalidResource)?
+            .is_complete())
     }
 
-    ///A swapchain holds the images which will be used for drawing to the screen.
-    ///It usually holds more than one image, which prevents a number of issues such as tearing.
-    ///Call `acquire_next_image` to get the current swapchain image.
-    pub fn create_swapchain(&self, info: SwapchainInfo<'_>) -> Result<Swapchain> {
+    ///Destroys a swapchain and its surface, for example when its window is closed.
+    ///Waits for the GPU to finish using its images. Render graphs presenting to it must not be rendered afterwards.
+    pub fn destroy_swapchain(&self, swapchain: Swapchain) -> Result<()> {
         let DeviceInner {
-            context,
-            surface: (surface_loader, surface_handle),
-            physical_device,
             logical_device,
-            queue_family_indices,
+            surface_loader,
             resources,
             ..
         } = &*self.inner;
 
         let mut resources = resources.lock().unwrap();
 
-        let mut surface_formats = unsafe {
-            surface_loader.get_physical_device_surface_formats(*physical_device, *surface_handle)
-        }
-        .map_err(|_| Error::Creation)?
-        .into_iter()
-        .filter_map(|surface_format| {
-            let selector = info.surface_format_selector;
+        let InternalSwapchain {
+            loader,
+            handle,
+            images,
+            surface,
+            readback,
+            capture,
+            ..
+        } = resources
+            .swapchains
+            .remove(swapchain)
+            .ok_or(Error::ResourceNotFound)?;
 
-            let score = selector(surface_format.format.try_into().ok()?);
+        unsafe { logical_device.device_wait_idle() }.ok();
 
-            Some((score, surface_format))
-        })
-        .collect::<Vec<_>>();
+        if let Some(readback) = readback {
+            self.inner.destroy_readback(&mut resources, readback);
+        }
 
-        surface_formats.sort_by(|(a, _), (b, _)| b.cmp(a));
+        if capture.is_some() {
+            self.inner
+                .destroy_virtual_swapchain(&mut resources, &images);
 
-        let surface_capabilities = unsafe {
-            surface_loader
-                .get_physical_device_surface_capabilities(*physical_device, *surface_handle)
+            return Ok(());
         }
-        .map_err(|_| Error::Creation)?;
 
-        let ContextInner { instance, .. } = &**context;
+        self.inner.remove_swapchain_images(&mut resources, &images);
 
-        let swapchain_loader = khr::Swapchain::new(&instance, &logical_device);
+        unsafe {
+            loader.destroy_swapchain(handle, None);
+            surface_loader.destroy_surface(surface, None);
+        }
 
-        let Some((_, vk::SurfaceFormatKHR { format, color_space })) = surface_formats.pop() else {
-                panic!("failed to find suitable surface format");
-        };
+        Ok(())
+    }
 
-        let swapchain_create_info = {
-            let surface = surface_handle;
+    ///Queries what a window supports before a swapchain is created for it, such as the formats and color spaces it can present,
+    ///the present modes and the number of images.
+    ///Formats and color spaces unknown to boson are left out.
+    pub fn surface_capabilities(
+        &self,
+        display: RawDisplayHandle,
+        window: RawWindowHandle,
+    ) -> Result<SurfaceCapabilities> {
+        let DeviceInner { surface_loader, .. } = &*self.inner;
 
-            let min_image_count = 3;
+        let surface = self.inner.create_surface(display, window)?;
 
-            let image_format = format;
-            let image_color_space = color_space;
+        let surface_capabilities = self.inner.surface_support(surface);
 
-            let image_extent = match surface_capabilities.current_extent.width {
-                std::u32::MAX => vk::Extent2D {
-                    width: info.width,
-                    height: info.height,
-                },
-                _ => surface_capabilities.current_extent,
-            };
+        unsafe { surface_loader.destroy_surface(surface, None) };
 
-            let image_array_layers = 1;
-            let image_usage = vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::STORAGE;
-            let image_sharing_mode = vk::SharingMode::EXCLUSIVE;
+        surface_capabilities
+    }
 
-            let queue_family_index_count = queue_family_indices.len() as _;
+    ///Queries what the surface of a swapchain supports, like `surface_capabilities`.
+    pub fn swapchain_surface_capabilities(
+        &self,
+        swapchain: Swapchain,
+    ) -> Result<SurfaceCapabilities> {
+        let DeviceInner { resources, .. } = &*self.inner;
 
-            let p_queue_family_indices = queue_family_indices.as_ptr();
+        let surface = {
+            let resources = resources.lock().unwrap();
 
-            let pre_transform = if surface_capabilities
-                .supported_transforms
-                .contains(vk::SurfaceTransformFlagsKHR::IDENTITY)
-            {
-                vk::SurfaceTransformFlagsKHR::IDENTITY
-            } else {
-                surface_capabilities.current_transform
-            };
+            let internal_swapchain = resources
+                .swapchains
+                .get(swapchain)
+                .ok_or(Error::ResourceNotFound)?;
 
-            let composite_alpha = vk::CompositeAlphaFlagsKHR::OPAQUE;
+            //Virtual swapchains have no surface.
+            if internal_swapchain.capture.is_some() {
+                Err(Error::InvalidResource)?
+            }
 
-            let preferred_present_mode = vk::PresentModeKHR::from(info.present_mode);
+            internal_swapchain.surface
+        };
 
-            let present_modes = unsafe {
-                surface_loader
-                    .get_physical_device_surface_present_modes(*physical_device, *surface_handle)
-            }
-            .map_err(|_| Error::Creation)?;
+        self.inner.surface_support(surface)
+    }
 
-            let present_mode = present_modes
-                .into_iter()
-                .find(|&present_mode| present_mode == preferred_present_mode)
-                .unwrap_or(vk::PresentModeKHR::IMMEDIATE);
+    ///Gets the format and color space the swapchain was created with.
+    pub fn swapchain_surface_format(&self, swapchain: Swapchain) -> Result<SurfaceFormat> {
+        let DeviceInner { resources, .. } = &*self.inner;
 
-            let clipped = true as _;
+        let resources = resources.lock().unwrap();
 
-            let surface = *surface;
+        let InternalSwapchain { surface_format, .. } = resources
+            .swapchains
+            .get(swapchain)
+            .ok_or(Error::ResourceNotFound)?;
 
-            let old_swapchain = if let Some(swapchain) = info.old_swapchain {
-                resources
-                    .swapchains
-                    .get(swapchain)
-                    .ok_or(Error::ResourceNotFound)?
-                    .handle
-            } else {
-                vk::SwapchainKHR::null()
-            };
+        (*surface_format)
+            .try_into()
+            .map_err(|_| Error::InvalidResource)
+    }
 
-            vk::SwapchainCreateInfoKHR {
-                surface,
-                min_image_count,
-                image_format,
-                image_color_space,
-                image_extent,
-                image_array_layers,
-                image_usage,
-                image_sharing_mode,
-                queue_family_index_count,
-                p_queue_family_indices,
-                pre_transform,
-                composite_alpha,
-                present_mode,
-                clipped,
-                old_swapchain,
-                ..Default::default()
-            }
-        };
+    ///Gets the present mode the swapchain ended up with, after falling back from the one requested.
+    pub fn swapchain_present_mode(&self, swapchain: Swapchain) -> Result<PresentMode> {
+        let DeviceInner { resources, .. } = &*self.inner;
 
-        let swapchain_handle =
-            unsafe { swapchain_loader.create_swapchain(&swapchain_create_info, None) }
-                .map_err(|_| Error::Creation)?;
+        let resources = resources.lock().unwrap();
 
-        let swapchain_images = unsafe { swapchain_loader.get_swapchain_images(swapchain_handle) }
-            .map_err(|_| Error::Creation)?;
+        let InternalSwapchain { present_mode, .. } = resources
+            .swapchains
+            .get(swapchain)
+            .ok_or(Error::ResourceNotFound)?;
 
-        let images = swapchain_images
-            .into_iter()
-            .map(|image| {
-                let image_view_create_info = vk::ImageViewCreateInfo {
-                    image,
-                    view_type: vk::ImageViewType::TYPE_2D,
-                    format,
-                    components: vk::ComponentMapping {
-                        r: vk::ComponentSwizzle::IDENTITY,
-                        g: vk::ComponentSwizzle::IDENTITY,
-                        b: vk::ComponentSwizzle::IDENTITY,
-                        a: vk::ComponentSwizzle::IDENTITY,
-                    },
-                    subresource_range: vk::ImageSubresourceRange {
-                        aspect_mask: vk::ImageAspectFlags::COLOR,
-                        base_mip_level: 0,
-                        level_count: 1,
-                        base_array_layer: 0,
-                        layer_count: 1,
-                    },
-                    ..Default::default()
-                };
+        (*present_mode)
+            .try_into()
+            .map_err(|_| Error::InvalidResource)
+    }
 
-                let image_view =
-                    unsafe { logical_device.create_image_view(&image_view_create_info, None) }
-                        .unwrap();
+    ///Asks for the swapchain to be resized, for example when the window is resized.
+    ///The swapchain is recreated in place before its next image is acquired, so render graphs using it keep working.
+    pub fn resize_swapchain(&self, swapchain: Swapchain, width: u32, height: u32) -> Result<()> {
+        let DeviceInner { resources, .. } = &*self.inner;
 
-                (image, image_view)
-            })
-            .map(|(image, view)| InternalImage::Swapchain {
-                image,
-                view,
-                format: format.try_into().unwrap(),
-            })
-            .map(|internal_image| resources.images.add(internal_image))
-            .collect::<Vec<_>>();
+        let mut resources = resources.lock().unwrap();
 
-        let loader = swapchain_loader;
+        let internal_swapchain = resources
+            .swapchains
+            .get_mut(swapchain)
+            .ok_or(Error::ResourceNotFound)?;
 
-        let handle = swapchain_handle;
+        internal_swapchain.requested_extent = vk::Extent2D { width, height };
+        internal_swapchain.recreate = true;
 
-        let format = format.try_into().map_err(|_| Error::Creation)?;
+        Ok(())
+    }
 
-        let last_acquisition_index = None;
+    ///Gets the size of the swapchain images, which changes when the swapchain is recreated.
+    pub fn swapchain_extent(&self, swapchain: Swapchain) -> Result<(u32, u32)> {
+        let DeviceInner { resources, .. } = &*self.inner;
 
-        let current_frame = 0;
+        let resources = resources.lock().unwrap();
 
-        let allow_acquisition = true;
+        let InternalSwapchain { extent, .. } = resources
+            .swapchains
+            .get(swapchain)
+            .ok_or(Error::ResourceNotFound)?;
 
-        Ok(resources.swapchains.add(InternalSwapchain {
-            loader,
-            handle,
-            format,
-            images,
-            last_acquisition_index,
-            current_frame,
-            allow_acquisition,
-        }))
+        Ok((extent.width, extent.height))
     }
 
     ///A pipeline compiler allows you to create pipelines from shader source code.
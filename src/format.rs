@@ -1,41 +1,557 @@
-use std::mem;
+use crate::device::SampleCount;
+use crate::image::ImageUsage;
 
 use ash::vk;
 
+///How the channels of a format are stored and read in shaders.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum NumericFormat {
+    Unorm,
+    Snorm,
+    Uscaled,
+    Sscaled,
+    Uint,
+    Sint,
+    Ufloat,
+    Sfloat,
+    ///Unsigned normalized, converted from sRGB to linear on read.
+    Srgb,
+}
+
+///The family of a block-compressed format, each needs its device feature.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Compression {
+    ///BC1 to BC7, `Features::texture_compression_bc`.
+    Bc,
+    ///ETC2 and EAC, `Features::texture_compression_etc2`.
+    Etc2,
+    ///ASTC LDR, `Features::texture_compression_astc_ldr`.
+    Astc,
+}
+
 #[derive(Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Format {
     #[default]
     Undefined,
-    R8Uint,
+    R4g4Unorm,
+    Rgba4Unorm,
+    Bgra4Unorm,
+    R5g6b5Unorm,
+    B5g6r5Unorm,
+    R5g5b5a1Unorm,
+    B5g5r5a1Unorm,
+    A1r5g5b5Unorm,
     R8Unorm,
+    R8Snorm,
+    R8Uscaled,
+    R8Sscaled,
+    R8Uint,
+    R8Sint,
+    R8Srgb,
+    Rg8Unorm,
+    Rg8Snorm,
+    Rg8Uscaled,
+    Rg8Sscaled,
+    Rg8Uint,
+    Rg8Sint,
+    Rg8Srgb,
+    Rgb8Unorm,
+    Rgb8Snorm,
+    Rgb8Uscaled,
+    Rgb8Sscaled,
+    Rgb8Uint,
+    Rgb8Sint,
+    Rgb8Srgb,
+    Bgr8Unorm,
+    Bgr8Snorm,
+    Bgr8Uscaled,
+    Bgr8Sscaled,
+    Bgr8Uint,
+    Bgr8Sint,
+    Bgr8Srgb,
+    Rgba8Unorm,
+    Rgba8Snorm,
+    Rgba8Uscaled,
+    Rgba8Sscaled,
+    Rgba8Uint,
+    Rgba8Sint,
+    Rgba8Srgb,
+    Bgra8Unorm,
+    Bgra8Snorm,
+    Bgra8Uscaled,
+    Bgra8Sscaled,
+    Bgra8Uint,
+    Bgra8Sint,
+    Bgra8Srgb,
+    Abgr8Unorm,
+    Abgr8Snorm,
+    Abgr8Uscaled,
+    Abgr8Sscaled,
+    Abgr8Uint,
+    Abgr8Sint,
+    Abgr8Srgb,
+    A2r10g10b10Unorm,
+    A2r10g10b10Snorm,
+    A2r10g10b10Uscaled,
+    A2r10g10b10Sscaled,
+    A2r10g10b10Uint,
+    A2r10g10b10Sint,
+    A2b10g10r10Unorm,
+    A2b10g10r10Snorm,
+    A2b10g10r10Uscaled,
+    A2b10g10r10Sscaled,
+    A2b10g10r10Uint,
+    A2b10g10r10Sint,
+    R16Unorm,
+    R16Snorm,
+    R16Uscaled,
+    R16Sscaled,
+    R16Uint,
+    R16Sint,
+    R16Sfloat,
+    Rg16Unorm,
+    Rg16Snorm,
+    Rg16Uscaled,
+    Rg16Sscaled,
+    Rg16Uint,
+    Rg16Sint,
+    Rg16Sfloat,
+    Rgb16Unorm,
+    Rgb16Snorm,
+    Rgb16Uscaled,
+    Rgb16Sscaled,
+    Rgb16Uint,
+    Rgb16Sint,
+    Rgb16Sfloat,
+    Rgba16Unorm,
+    Rgba16Snorm,
+    Rgba16Uscaled,
+    Rgba16Sscaled,
+    Rgba16Uint,
+    Rgba16Sint,
+    Rgba16Sfloat,
     R32Uint,
+    R32Sint,
     R32Sfloat,
-    Rgba32Sfloat,
     Rg32Uint,
-    R16Uint,
-    Rgba8Uint,
+    Rg32Sint,
+    Rg32Sfloat,
     Rgb32Uint,
-    Rgba8Unorm,
-    Rgba8Srgb,
+    Rgb32Sint,
+    Rgb32Sfloat,
     Rgba32Uint,
-    Bgra8Unorm,
-    Bgra8Srgb,
+    Rgba32Sint,
+    Rgba32Sfloat,
+    R64Uint,
+    R64Sint,
+    R64Sfloat,
+    Rg64Uint,
+    Rg64Sint,
+    Rg64Sfloat,
+    Rgb64Uint,
+    Rgb64Sint,
+    Rgb64Sfloat,
+    Rgba64Uint,
+    Rgba64Sint,
+    Rgba64Sfloat,
+    B10g11r11Ufloat,
+    E5b9g9r9Ufloat,
+    D16Unorm,
+    X8D24Unorm,
     D32Sfloat,
+    S8Uint,
+    D16UnormS8Uint,
+    D24UnormS8Uint,
     D32SfloatS8Uint,
+    Bc1RgbUnorm,
+    Bc1RgbSrgb,
+    Bc1RgbaUnorm,
+    Bc1RgbaSrgb,
+    Bc2Unorm,
+    Bc2Srgb,
+    Bc3Unorm,
+    Bc3Srgb,
+    Bc4Unorm,
+    Bc4Snorm,
+    Bc5Unorm,
+    Bc5Snorm,
+    Bc6hUfloat,
+    Bc6hSfloat,
+    Bc7Unorm,
+    Bc7Srgb,
+    Etc2Rgb8Unorm,
+    Etc2Rgb8Srgb,
+    Etc2Rgb8a1Unorm,
+    Etc2Rgb8a1Srgb,
+    Etc2Rgba8Unorm,
+    Etc2Rgba8Srgb,
+    EacR11Unorm,
+    EacR11Snorm,
+    EacRg11Unorm,
+    EacRg11Snorm,
+    Astc4x4Unorm,
+    Astc4x4Srgb,
+    Astc5x4Unorm,
+    Astc5x4Srgb,
+    Astc5x5Unorm,
+    Astc5x5Srgb,
+    Astc6x5Unorm,
+    Astc6x5Srgb,
+    Astc6x6Unorm,
+    Astc6x6Srgb,
+    Astc8x5Unorm,
+    Astc8x5Srgb,
+    Astc8x6Unorm,
+    Astc8x6Srgb,
+    Astc8x8Unorm,
+    Astc8x8Srgb,
+    Astc10x5Unorm,
+    Astc10x5Srgb,
+    Astc10x6Unorm,
+    Astc10x6Srgb,
+    Astc10x8Unorm,
+    Astc10x8Srgb,
+    Astc10x10Unorm,
+    Astc10x10Srgb,
+    Astc12x10Unorm,
+    Astc12x10Srgb,
+    Astc12x12Unorm,
+    Astc12x12Srgb,
+}
+
+struct FormatInfo {
+    bytes_per_block: usize,
+    block_extent: (u32, u32),
+    compression: Option<Compression>,
+    channel_count: usize,
+    numeric_format: NumericFormat,
+    depth: bool,
+    stencil: bool,
 }
 
 impl Format {
+    fn info(&self) -> FormatInfo {
+        use Format::*;
+        use NumericFormat as N;
+
+        let (bytes_per_block, channel_count, numeric_format, depth, stencil) = match self {
+            Undefined => (0, 0, N::Unorm, false, false),
+            R4g4Unorm => (1, 2, N::Unorm, false, false),
+            Rgba4Unorm => (2, 4, N::Unorm, false, false),
+            Bgra4Unorm => (2, 4, N::Unorm, false, false),
+            R5g6b5Unorm => (2, 3, N::Unorm, false, false),
+            B5g6r5Unorm => (2, 3, N::Unorm, false, false),
+            R5g5b5a1Unorm => (2, 4, N::Unorm, false, false),
+            B5g5r5a1Unorm => (2, 4, N::Unorm, false, false),
+            A1r5g5b5Unorm => (2, 4, N::Unorm, false, false),
+            R8Unorm => (1, 1, N::Unorm, false, false),
+            R8Snorm => (1, 1, N::Snorm, false, false),
+            R8Uscaled => (1, 1, N::Uscaled, false, false),
+            R8Sscaled => (1, 1, N::Sscaled, false, false),
+            R8Uint => (1, 1, N::Uint, false, false),
+            R8Sint => (1, 1, N::Sint, false, false),
+            R8Srgb => (1, 1, N::Srgb, false, false),
+            Rg8Unorm => (2, 2, N::Unorm, false, false),
+            Rg8Snorm => (2, 2, N::Snorm, false, false),
+            Rg8Uscaled => (2, 2, N::Uscaled, false, false),
+            Rg8Sscaled => (2, 2, N::Sscaled, false, false),
+            Rg8Uint => (2, 2, N::Uint, false, false),
+            Rg8Sint => (2, 2, N::Sint, false, false),
+            Rg8Srgb => (2, 2, N::Srgb, false, false),
+            Rgb8Unorm => (3, 3, N::Unorm, false, false),
+            Rgb8Snorm => (3, 3, N::Snorm, false, false),
+            Rgb8Uscaled => (3, 3, N::Uscaled, false, false),
+            Rgb8Sscaled => (3, 3, N::Sscaled, false, false),
+            Rgb8Uint => (3, 3, N::Uint, false, false),
+            Rgb8Sint => (3, 3, N::Sint, false, false),
+            Rgb8Srgb => (3, 3, N::Srgb, false, false),
+            Bgr8Unorm => (3, 3, N::Unorm, false, false),
+            Bgr8Snorm => (3, 3, N::Snorm, false, false),
+            Bgr8Uscaled => (3, 3, N::Uscaled, false, false),
+            Bgr8Sscaled => (3, 3, N::Sscaled, false, false),
+            Bgr8Uint => (3, 3, N::Uint, false, false),
+            Bgr8Sint => (3, 3, N::Sint, false, false),
+            Bgr8Srgb => (3, 3, N::Srgb, false, false),
+            Rgba8Unorm => (4, 4, N::Unorm, false, false),
+            Rgba8Snorm => (4, 4, N::Snorm, false, false),
+            Rgba8Uscaled => (4, 4, N::Uscaled, false, false),
+            Rgba8Sscaled => (4, 4, N::Sscaled, false, false),
+            Rgba8Uint => (4, 4, N::Uint, false, false),
+            Rgba8Sint => (4, 4, N::Sint, false, false),
+            Rgba8Srgb => (4, 4, N::Srgb, false, false),
+            Bgra8Unorm => (4, 4, N::Unorm, false, false),
+            Bgra8Snorm => (4, 4, N::Snorm, false, false),
+            Bgra8Uscaled => (4, 4, N::Uscaled, false, false),
+            Bgra8Sscaled => (4, 4, N::Sscaled, false, false),
+            Bgra8Uint => (4, 4, N::Uint, false, false),
+            Bgra8Sint => (4, 4, N::Sint, false, false),
+            Bgra8Srgb => (4, 4, N::Srgb, false, false),
+            Abgr8Unorm => (4, 4, N::Unorm, false, false),
+            Abgr8Snorm => (4, 4, N::Snorm, false, false),
+            Abgr8Uscaled => (4, 4, N::Uscaled, false, false),
+            Abgr8Sscaled => (4, 4, N::Sscaled, false, false),
+            Abgr8Uint => (4, 4, N::Uint, false, false),
+            Abgr8Sint => (4, 4, N::Sint, false, false),
+            Abgr8Srgb => (4, 4, N::Srgb, false, false),
+            A2r10g10b10Unorm => (4, 4, N::Unorm, false, false),
+            A2r10g10b10Snorm => (4, 4, N::Snorm, false, false),
+            A2r10g10b10Uscaled => (4, 4, N::Uscaled, false, false),
+            A2r10g10b10Sscaled => (4, 4, N::Sscaled, false, false),
+            A2r10g10b10Uint => (4, 4, N::Uint, false, false),
+            A2r10g10b10Sint => (4, 4, N::Sint, false, false),
+            A2b10g10r10Unorm => (4, 4, N::Unorm, false, false),
+            A2b10g10r10Snorm => (4, 4, N::Snorm, false, false),
+            A2b10g10r10Uscaled => (4, 4, N::Uscaled, false, false),
+            A2b10g10r10Sscaled => (4, 4, N::Sscaled, false, false),
+            A2b10g10r10Uint => (4, 4, N::Uint, false, false),
+            A2b10g10r10Sint => (4, 4, N::Sint, false, false),
+            R16Unorm => (2, 1, N::Unorm, false, false),
+            R16Snorm => (2, 1, N::Snorm, false, false),
+            R16Uscaled => (2, 1, N::Uscaled, false, false),
+            R16Sscaled => (2, 1, N::Sscaled, false, false),
+            R16Uint => (2, 1, N::Uint, false, false),
+            R16Sint => (2, 1, N::Sint, false, false),
+            R16Sfloat => (2, 1, N::Sfloat, false, false),
+            Rg16Unorm => (4, 2, N::Unorm, false, false),
+            Rg16Snorm => (4, 2, N::Snorm, false, false),
+            Rg16Uscaled => (4, 2, N::Uscaled, false, false),
+            Rg16Sscaled => (4, 2, N::Sscaled, false, false),
+            Rg16Uint => (4, 2, N::Uint, false, false),
+            Rg16Sint => (4, 2, N::Sint, false, false),
+            Rg16Sfloat => (4, 2, N::Sfloat, false, false),
+            Rgb16Unorm => (6, 3, N::Unorm, false, false),
+            Rgb16Snorm => (6, 3, N::Snorm, false, false),
+            Rgb16Uscaled => (6, 3, N::Uscaled, false, false),
+            Rgb16Sscaled => (6, 3, N::Sscaled, false, false),
+            Rgb16Uint => (6, 3, N::Uint, false, false),
+            Rgb16Sint => (6, 3, N::Sint, false, false),
+            Rgb16Sfloat => (6, 3, N::Sfloat, false, false),
+            Rgba16Unorm => (8, 4, N::Unorm, false, false),
+            Rgba16Snorm => (8, 4, N::Snorm, false, false),
+            Rgba16Uscaled => (8, 4, N::Uscaled, false, false),
+            Rgba16Sscaled => (8, 4, N::Sscaled, false, false),
+            Rgba16Uint => (8, 4, N::Uint, false, false),
+            Rgba16Sint => (8, 4, N::Sint, false, false),
+            Rgba16Sfloat => (8, 4, N::Sfloat, false, false),
+            R32Uint => (4, 1, N::Uint, false, false),
+            R32Sint => (4, 1, N::Sint, false, false),
+            R32Sfloat => (4, 1, N::Sfloat, false, false),
+            Rg32Uint => (8, 2, N::Uint, false, false),
+            Rg32Sint => (8, 2, N::Sint, false, false),
+            Rg32Sfloat => (8, 2, N::Sfloat, false, false),
+            Rgb32Uint => (12, 3, N::Uint, false, false),
+            Rgb32Sint => (12, 3, N::Sint, false, false),
+            Rgb32Sfloat => (12, 3, N::Sfloat, false, false),
+            Rgba32Uint => (16, 4, N::Uint, false, false),
+            Rgba32Sint => (16, 4, N::Sint, false, false),
+            Rgba32Sfloat => (16, 4, N::Sfloat, false, false),
+            R64Uint => (8, 1, N::Uint, false, false),
+            R64Sint => (8, 1, N::Sint, false, false),
+            R64Sfloat => (8, 1, N::Sfloat, false, false),
+            Rg64Uint => (16, 2, N::Uint, false, false),
+            Rg64Sint => (16, 2, N::Sint, false, false),
+            Rg64Sfloat => (16, 2, N::Sfloat, false, false),
+            Rgb64Uint => (24, 3, N::Uint, false, false),
+            Rgb64Sint => (24, 3, N::Sint, false, false),
+            Rgb64Sfloat => (24, 3, N::Sfloat, false, false),
+            Rgba64Uint => (32, 4, N::Uint, false, false),
+            Rgba64Sint => (32, 4, N::Sint, false, false),
+            Rgba64Sfloat => (32, 4, N::Sfloat, false, false),
+            B10g11r11Ufloat => (4, 3, N::Ufloat, false, false),
+            E5b9g9r9Ufloat => (4, 3, N::Ufloat, false, false),
+            D16Unorm => (2, 1, N::Unorm, true, false),
+            X8D24Unorm => (4, 1, N::Unorm, true, false),
+            D32Sfloat => (4, 1, N::Sfloat, true, false),
+            S8Uint => (1, 1, N::Uint, false, true),
+            D16UnormS8Uint => (3, 2, N::Unorm, true, true),
+            D24UnormS8Uint => (4, 2, N::Unorm, true, true),
+            D32SfloatS8Uint => (5, 2, N::Sfloat, true, true),
+            Bc1RgbUnorm => (8, 3, N::Unorm, false, false),
+            Bc1RgbSrgb => (8, 3, N::Srgb, false, false),
+            Bc1RgbaUnorm => (8, 4, N::Unorm, false, false),
+            Bc1RgbaSrgb => (8, 4, N::Srgb, false, false),
+            Bc2Unorm => (16, 4, N::Unorm, false, false),
+            Bc2Srgb => (16, 4, N::Srgb, false, false),
+            Bc3Unorm => (16, 4, N::Unorm, false, false),
+            Bc3Srgb => (16, 4, N::Srgb, false, false),
+            Bc4Unorm => (8, 1, N::Unorm, false, false),
+            Bc4Snorm => (8, 1, N::Snorm, false, false),
+            Bc5Unorm => (16, 2, N::Unorm, false, false),
+            Bc5Snorm => (16, 2, N::Snorm, false, false),
+            Bc6hUfloat => (16, 3, N::Ufloat, false, false),
+            Bc6hSfloat => (16, 3, N::Sfloat, false, false),
+            Bc7Unorm => (16, 4, N::Unorm, false, false),
+            Bc7Srgb => (16, 4, N::Srgb, false, false),
+            Etc2Rgb8Unorm => (8, 3, N::Unorm, false, false),
+            Etc2Rgb8Srgb => (8, 3, N::Srgb, false, false),
+            Etc2Rgb8a1Unorm => (8, 4, N::Unorm, false, false),
+            Etc2Rgb8a1Srgb => (8, 4, N::Srgb, false, false),
+            Etc2Rgba8Unorm => (16, 4, N::Unorm, false, false),
+            Etc2Rgba8Srgb => (16, 4, N::Srgb, false, false),
+            EacR11Unorm => (8, 1, N::Unorm, false, false),
+            EacR11Snorm => (8, 1, N::Snorm, false, false),
+            EacRg11Unorm => (16, 2, N::Unorm, false, false),
+            EacRg11Snorm => (16, 2, N::Snorm, false, false),
+            Astc4x4Unorm => (16, 4, N::Unorm, false, false),
+            Astc4x4Srgb => (16, 4, N::Srgb, false, false),
+            Astc5x4Unorm => (16, 4, N::Unorm, false, false),
+            Astc5x4Srgb => (16, 4, N::Srgb, false, false),
+            Astc5x5Unorm => (16, 4, N::Unorm, false, false),
+            Astc5x5Srgb => (16, 4, N::Srgb, false, false),
+            Astc6x5Unorm => (16, 4, N::Unorm, false, false),
+            Astc6x5Srgb => (16, 4, N::Srgb, false, false),
+            Astc6x6Unorm => (16, 4, N::Unorm, false, false),
+            Astc6x6Srgb => (16, 4, N::Srgb, false, false),
+            Astc8x5Unorm => (16, 4, N::Unorm, false, false),
+            Astc8x5Srgb => (16, 4, N::Srgb, false, false),
+            Astc8x6Unorm => (16, 4, N::Unorm, false, false),
+            Astc8x6Srgb => (16, 4, N::Srgb, false, false),
+            Astc8x8Unorm => (16, 4, N::Unorm, false, false),
+            Astc8x8Srgb => (16, 4, N::Srgb, false, false),
+            Astc10x5Unorm => (16, 4, N::Unorm, false, false),
+            Astc10x5Srgb => (16, 4, N::Srgb, false, false),
+            Astc10x6Unorm => (16, 4, N::Unorm, false, false),
+            Astc10x6Srgb => (16, 4, N::Srgb, false, false),
+            Astc10x8Unorm => (16, 4, N::Unorm, false, false),
+            Astc10x8Srgb => (16, 4, N::Srgb, false, false),
+            Astc10x10Unorm => (16, 4, N::Unorm, false, false),
+            Astc10x10Srgb => (16, 4, N::Srgb, false, false),
+            Astc12x10Unorm => (16, 4, N::Unorm, false, false),
+            Astc12x10Srgb => (16, 4, N::Srgb, false, false),
+            Astc12x12Unorm => (16, 4, N::Unorm, false, false),
+            Astc12x12Srgb => (16, 4, N::Srgb, false, false),
+        };
+
+        let (block_extent, compression) = match self {
+            Bc1RgbUnorm | Bc1RgbSrgb | Bc1RgbaUnorm | Bc1RgbaSrgb | Bc2Unorm | Bc2Srgb
+            | Bc3Unorm | Bc3Srgb | Bc4Unorm | Bc4Snorm | Bc5Unorm | Bc5Snorm | Bc6hUfloat
+            | Bc6hSfloat | Bc7Unorm | Bc7Srgb => ((4, 4), Some(Compression::Bc)),
+            Etc2Rgb8Unorm | Etc2Rgb8Srgb | Etc2Rgb8a1Unorm | Etc2Rgb8a1Srgb | Etc2Rgba8Unorm
+            | Etc2Rgba8Srgb | EacR11Unorm | EacR11Snorm | EacRg11Unorm | EacRg11Snorm => {
+                ((4, 4), Some(Compression::Etc2))
+            }
+            Astc4x4Unorm | Astc4x4Srgb => ((4, 4), Some(Compression::Astc)),
+            Astc5x4Unorm | Astc5x4Srgb => ((5, 4), Some(Compression::Astc)),
+            Astc5x5Unorm | Astc5x5Srgb => ((5, 5), Some(Compression::Astc)),
+            Astc6x5Unorm | Astc6x5Srgb => ((6, 5), Some(Compression::Astc)),
+            Astc6x6Unorm | Astc6x6Srgb => ((6, 6), Some(Compression::Astc)),
+            Astc8x5Unorm | Astc8x5Srgb => ((8, 5), Some(Compression::Astc)),
+            Astc8x6Unorm | Astc8x6Srgb => ((8, 6), Some(Compression::Astc)),
+            Astc8x8Unorm | Astc8x8Srgb => ((8, 8), Some(Compression::Astc)),
+            Astc10x5Unorm | Astc10x5Srgb => ((10, 5), Some(Compression::Astc)),
+            Astc10x6Unorm | Astc10x6Srgb => ((10, 6), Some(Compression::Astc)),
+            Astc10x8Unorm | Astc10x8Srgb => ((10, 8), Some(Compression::Astc)),
+            Astc10x10Unorm | Astc10x10Srgb => ((10, 10), Some(Compression::Astc)),
+            Astc12x10Unorm | Astc12x10Srgb => ((12, 10), Some(Compression::Astc)),
+            Astc12x12Unorm | Astc12x12Srgb => ((12, 12), Some(Compression::Astc)),
+            _ => ((1, 1), None),
+        };
+
+        FormatInfo {
+            bytes_per_block,
+            block_extent,
+            compression,
+            channel_count,
+            numeric_format,
+            depth,
+            stencil,
+        }
+    }
+
+    ///The size of one texel, with all aspects packed together.
+    ///Compressed formats have no size per texel, this is the size of a block.
+    pub fn bytes_per_texel(&self) -> usize {
+        self.info().bytes_per_block
+    }
+
+    ///The size of one block, a single texel for uncompressed formats.
+    pub fn bytes_per_block(&self) -> usize {
+        self.info().bytes_per_block
+    }
+
+    ///The width and height in texels of one block, (1, 1) for uncompressed formats.
+    pub fn block_extent(&self) -> (u32, u32) {
+        self.info().block_extent
+    }
+
+    pub fn compression(&self) -> Option<Compression> {
+        self.info().compression
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.compression().is_some()
+    }
+
+    pub fn channel_count(&self) -> usize {
+        self.info().channel_count
+    }
+
+    ///Depth/stencil formats report the type of their depth aspect.
+    pub fn numeric_format(&self) -> NumericFormat {
+        self.info().numeric_format
+    }
+
+    pub fn is_srgb(&self) -> bool {
+        self.numeric_format() == NumericFormat::Srgb
+    }
+
+    pub fn has_depth(&self) -> bool {
+        self.info().depth
+    }
+
+    pub fn has_stencil(&self) -> bool {
+        self.info().stencil
+    }
+
     pub(crate) fn entire_aspect(&self) -> vk::ImageAspectFlags {
-        match self {
-            Format::D32Sfloat => vk::ImageAspectFlags::DEPTH,
-            Format::D32SfloatS8Uint => vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL,
-            _ => vk::ImageAspectFlags::COLOR,
+        let FormatInfo { depth, stencil, .. } = self.info();
+
+        let mut aspect = vk::ImageAspectFlags::empty();
+
+        if depth {
+            aspect |= vk::ImageAspectFlags::DEPTH;
+        }
+
+        if stencil {
+            aspect |= vk::ImageAspectFlags::STENCIL;
         }
+
+        if aspect.is_empty() {
+            aspect = vk::ImageAspectFlags::COLOR;
+        }
+
+        aspect
     }
+
     pub(crate) fn is_depth_or_stencil(&self) -> bool {
-        match self {
-            Format::D32Sfloat | Format::D32SfloatS8Uint => true,
-            _ => false,
+        let FormatInfo { depth, stencil, .. } = self.info();
+
+        depth || stencil
+    }
+
+    ///The size of one block of a single aspect in a buffer, as copies tightly pack it.
+    pub(crate) fn aspect_bytes_per_block(&self, aspect: vk::ImageAspectFlags) -> usize {
+        use Format::*;
+
+        if aspect == vk::ImageAspectFlags::STENCIL {
+            1
+        } else if aspect == vk::ImageAspectFlags::DEPTH {
+            match self {
+                D16Unorm | D16UnormS8Uint => 2,
+                _ => 4,
+            }
+        } else {
+            self.bytes_per_block()
+        }
+    }
+
+    ///Whether the device feature this format needs is enabled.
+    pub(crate) fn is_enabled(&self, features: &vk::PhysicalDeviceFeatures) -> bool {
+        match self.compression() {
+            None => true,
+            Some(Compression::Bc) => features.texture_compression_bc != 0,
+            Some(Compression::Etc2) => features.texture_compression_etc2 != 0,
+            Some(Compression::Astc) => features.texture_compression_astc_ldr != 0,
         }
     }
 }
@@ -48,22 +564,190 @@ impl TryFrom<vk::Format> for Format {
 
         Ok(match format {
             vk::Format::UNDEFINED => Undefined,
-            vk::Format::R8_UINT => R8Uint,
+            vk::Format::R4G4_UNORM_PACK8 => R4g4Unorm,
+            vk::Format::R4G4B4A4_UNORM_PACK16 => Rgba4Unorm,
+            vk::Format::B4G4R4A4_UNORM_PACK16 => Bgra4Unorm,
+            vk::Format::R5G6B5_UNORM_PACK16 => R5g6b5Unorm,
+            vk::Format::B5G6R5_UNORM_PACK16 => B5g6r5Unorm,
+            vk::Format::R5G5B5A1_UNORM_PACK16 => R5g5b5a1Unorm,
+            vk::Format::B5G5R5A1_UNORM_PACK16 => B5g5r5a1Unorm,
+            vk::Format::A1R5G5B5_UNORM_PACK16 => A1r5g5b5Unorm,
             vk::Format::R8_UNORM => R8Unorm,
-            vk::Format::R32_UINT => R32Uint,
-            vk::Format::R32G32_UINT => Rg32Uint,
-            vk::Format::R16_UINT => R32Uint,
-            vk::Format::R32_SFLOAT => R32Sfloat,
-            vk::Format::R8G8B8A8_UINT => Rgba8Uint,
-            vk::Format::R32G32B32A32_UINT => Rgba32Uint,
-            vk::Format::R32G32B32_UINT => Rgb32Uint,
-            vk::Format::R32G32B32A32_SFLOAT => Rgba32Sfloat,
+            vk::Format::R8_SNORM => R8Snorm,
+            vk::Format::R8_USCALED => R8Uscaled,
+            vk::Format::R8_SSCALED => R8Sscaled,
+            vk::Format::R8_UINT => R8Uint,
+            vk::Format::R8_SINT => R8Sint,
+            vk::Format::R8_SRGB => R8Srgb,
+            vk::Format::R8G8_UNORM => Rg8Unorm,
+            vk::Format::R8G8_SNORM => Rg8Snorm,
+            vk::Format::R8G8_USCALED => Rg8Uscaled,
+            vk::Format::R8G8_SSCALED => Rg8Sscaled,
+            vk::Format::R8G8_UINT => Rg8Uint,
+            vk::Format::R8G8_SINT => Rg8Sint,
+            vk::Format::R8G8_SRGB => Rg8Srgb,
+            vk::Format::R8G8B8_UNORM => Rgb8Unorm,
+            vk::Format::R8G8B8_SNORM => Rgb8Snorm,
+            vk::Format::R8G8B8_USCALED => Rgb8Uscaled,
+            vk::Format::R8G8B8_SSCALED => Rgb8Sscaled,
+            vk::Format::R8G8B8_UINT => Rgb8Uint,
+            vk::Format::R8G8B8_SINT => Rgb8Sint,
+            vk::Format::R8G8B8_SRGB => Rgb8Srgb,
+            vk::Format::B8G8R8_UNORM => Bgr8Unorm,
+            vk::Format::B8G8R8_SNORM => Bgr8Snorm,
+            vk::Format::B8G8R8_USCALED => Bgr8Uscaled,
+            vk::Format::B8G8R8_SSCALED => Bgr8Sscaled,
+            vk::Format::B8G8R8_UINT => Bgr8Uint,
+            vk::Format::B8G8R8_SINT => Bgr8Sint,
+            vk::Format::B8G8R8_SRGB => Bgr8Srgb,
             vk::Format::R8G8B8A8_UNORM => Rgba8Unorm,
+            vk::Format::R8G8B8A8_SNORM => Rgba8Snorm,
+            vk::Format::R8G8B8A8_USCALED => Rgba8Uscaled,
+            vk::Format::R8G8B8A8_SSCALED => Rgba8Sscaled,
+            vk::Format::R8G8B8A8_UINT => Rgba8Uint,
+            vk::Format::R8G8B8A8_SINT => Rgba8Sint,
             vk::Format::R8G8B8A8_SRGB => Rgba8Srgb,
             vk::Format::B8G8R8A8_UNORM => Bgra8Unorm,
+            vk::Format::B8G8R8A8_SNORM => Bgra8Snorm,
+            vk::Format::B8G8R8A8_USCALED => Bgra8Uscaled,
+            vk::Format::B8G8R8A8_SSCALED => Bgra8Sscaled,
+            vk::Format::B8G8R8A8_UINT => Bgra8Uint,
+            vk::Format::B8G8R8A8_SINT => Bgra8Sint,
             vk::Format::B8G8R8A8_SRGB => Bgra8Srgb,
+            vk::Format::A8B8G8R8_UNORM_PACK32 => Abgr8Unorm,
+            vk::Format::A8B8G8R8_SNORM_PACK32 => Abgr8Snorm,
+            vk::Format::A8B8G8R8_USCALED_PACK32 => Abgr8Uscaled,
+            vk::Format::A8B8G8R8_SSCALED_PACK32 => Abgr8Sscaled,
+            vk::Format::A8B8G8R8_UINT_PACK32 => Abgr8Uint,
+            vk::Format::A8B8G8R8_SINT_PACK32 => Abgr8Sint,
+            vk::Format::A8B8G8R8_SRGB_PACK32 => Abgr8Srgb,
+            vk::Format::A2R10G10B10_UNORM_PACK32 => A2r10g10b10Unorm,
+            vk::Format::A2R10G10B10_SNORM_PACK32 => A2r10g10b10Snorm,
+            vk::Format::A2R10G10B10_USCALED_PACK32 => A2r10g10b10Uscaled,
+            vk::Format::A2R10G10B10_SSCALED_PACK32 => A2r10g10b10Sscaled,
+            vk::Format::A2R10G10B10_UINT_PACK32 => A2r10g10b10Uint,
+            vk::Format::A2R10G10B10_SINT_PACK32 => A2r10g10b10Sint,
+            vk::Format::A2B10G10R10_UNORM_PACK32 => A2b10g10r10Unorm,
+            vk::Format::A2B10G10R10_SNORM_PACK32 => A2b10g10r10Snorm,
+            vk::Format::A2B10G10R10_USCALED_PACK32 => A2b10g10r10Uscaled,
+            vk::Format::A2B10G10R10_SSCALED_PACK32 => A2b10g10r10Sscaled,
+            vk::Format::A2B10G10R10_UINT_PACK32 => A2b10g10r10Uint,
+            vk::Format::A2B10G10R10_SINT_PACK32 => A2b10g10r10Sint,
+            vk::Format::R16_UNORM => R16Unorm,
+            vk::Format::R16_SNORM => R16Snorm,
+            vk::Format::R16_USCALED => R16Uscaled,
+            vk::Format::R16_SSCALED => R16Sscaled,
+            vk::Format::R16_UINT => R16Uint,
+            vk::Format::R16_SINT => R16Sint,
+            vk::Format::R16_SFLOAT => R16Sfloat,
+            vk::Format::R16G16_UNORM => Rg16Unorm,
+            vk::Format::R16G16_SNORM => Rg16Snorm,
+            vk::Format::R16G16_USCALED => Rg16Uscaled,
+            vk::Format::R16G16_SSCALED => Rg16Sscaled,
+            vk::Format::R16G16_UINT => Rg16Uint,
+            vk::Format::R16G16_SINT => Rg16Sint,
+            vk::Format::R16G16_SFLOAT => Rg16Sfloat,
+            vk::Format::R16G16B16_UNORM => Rgb16Unorm,
+            vk::Format::R16G16B16_SNORM => Rgb16Snorm,
+            vk::Format::R16G16B16_USCALED => Rgb16Uscaled,
+            vk::Format::R16G16B16_SSCALED => Rgb16Sscaled,
+            vk::Format::R16G16B16_UINT => Rgb16Uint,
+            vk::Format::R16G16B16_SINT => Rgb16Sint,
+            vk::Format::R16G16B16_SFLOAT => Rgb16Sfloat,
+            vk::Format::R16G16B16A16_UNORM => Rgba16Unorm,
+            vk::Format::R16G16B16A16_SNORM => Rgba16Snorm,
+            vk::Format::R16G16B16A16_USCALED => Rgba16Uscaled,
+            vk::Format::R16G16B16A16_SSCALED => Rgba16Sscaled,
+            vk::Format::R16G16B16A16_UINT => Rgba16Uint,
+            vk::Format::R16G16B16A16_SINT => Rgba16Sint,
+            vk::Format::R16G16B16A16_SFLOAT => Rgba16Sfloat,
+            vk::Format::R32_UINT => R32Uint,
+            vk::Format::R32_SINT => R32Sint,
+            vk::Format::R32_SFLOAT => R32Sfloat,
+            vk::Format::R32G32_UINT => Rg32Uint,
+            vk::Format::R32G32_SINT => Rg32Sint,
+            vk::Format::R32G32_SFLOAT => Rg32Sfloat,
+            vk::Format::R32G32B32_UINT => Rgb32Uint,
+            vk::Format::R32G32B32_SINT => Rgb32Sint,
+            vk::Format::R32G32B32_SFLOAT => Rgb32Sfloat,
+            vk::Format::R32G32B32A32_UINT => Rgba32Uint,
+            vk::Format::R32G32B32A32_SINT => Rgba32Sint,
+            vk::Format::R32G32B32A32_SFLOAT => Rgba32Sfloat,
+            vk::Format::R64_UINT => R64Uint,
+            vk::Format::R64_SINT => R64Sint,
+            vk::Format::R64_SFLOAT => R64Sfloat,
+            vk::Format::R64G64_UINT => Rg64Uint,
+            vk::Format::R64G64_SINT => Rg64Sint,
+            vk::Format::R64G64_SFLOAT => Rg64Sfloat,
+            vk::Format::R64G64B64_UINT => Rgb64Uint,
+            vk::Format::R64G64B64_SINT => Rgb64Sint,
+            vk::Format::R64G64B64_SFLOAT => Rgb64Sfloat,
+            vk::Format::R64G64B64A64_UINT => Rgba64Uint,
+            vk::Format::R64G64B64A64_SINT => Rgba64Sint,
+            vk::Format::R64G64B64A64_SFLOAT => Rgba64Sfloat,
+            vk::Format::B10G11R11_UFLOAT_PACK32 => B10g11r11Ufloat,
+            vk::Format::E5B9G9R9_UFLOAT_PACK32 => E5b9g9r9Ufloat,
+            vk::Format::D16_UNORM => D16Unorm,
+            vk::Format::X8_D24_UNORM_PACK32 => X8D24Unorm,
             vk::Format::D32_SFLOAT => D32Sfloat,
+            vk::Format::S8_UINT => S8Uint,
+            vk::Format::D16_UNORM_S8_UINT => D16UnormS8Uint,
+            vk::Format::D24_UNORM_S8_UINT => D24UnormS8Uint,
             vk::Format::D32_SFLOAT_S8_UINT => D32SfloatS8Uint,
+            vk::Format::BC1_RGB_UNORM_BLOCK => Bc1RgbUnorm,
+            vk::Format::BC1_RGB_SRGB_BLOCK => Bc1RgbSrgb,
+            vk::Format::BC1_RGBA_UNORM_BLOCK => Bc1RgbaUnorm,
+            vk::Format::BC1_RGBA_SRGB_BLOCK => Bc1RgbaSrgb,
+            vk::Format::BC2_UNORM_BLOCK => Bc2Unorm,
+            vk::Format::BC2_SRGB_BLOCK => Bc2Srgb,
+            vk::Format::BC3_UNORM_BLOCK => Bc3Unorm,
+            vk::Format::BC3_SRGB_BLOCK => Bc3Srgb,
+            vk::Format::BC4_UNORM_BLOCK => Bc4Unorm,
+            vk::Format::BC4_SNORM_BLOCK => Bc4Snorm,
+            vk::Format::BC5_UNORM_BLOCK => Bc5Unorm,
+            vk::Format::BC5_SNORM_BLOCK => Bc5Snorm,
+            vk::Format::BC6H_UFLOAT_BLOCK => Bc6hUfloat,
+            vk::Format::BC6H_SFLOAT_BLOCK => Bc6hSfloat,
+            vk::Format::BC7_UNORM_BLOCK => Bc7Unorm,
+            vk::Format::BC7_SRGB_BLOCK => Bc7Srgb,
+            vk::Format::ETC2_R8G8B8_UNORM_BLOCK => Etc2Rgb8Unorm,
+            vk::Format::ETC2_R8G8B8_SRGB_BLOCK => Etc2Rgb8Srgb,
+            vk::Format::ETC2_R8G8B8A1_UNORM_BLOCK => Etc2Rgb8a1Unorm,
+            vk::Format::ETC2_R8G8B8A1_SRGB_BLOCK => Etc2Rgb8a1Srgb,
+            vk::Format::ETC2_R8G8B8A8_UNORM_BLOCK => Etc2Rgba8Unorm,
+            vk::Format::ETC2_R8G8B8A8_SRGB_BLOCK => Etc2Rgba8Srgb,
+            vk::Format::EAC_R11_UNORM_BLOCK => EacR11Unorm,
+            vk::Format::EAC_R11_SNORM_BLOCK => EacR11Snorm,
+            vk::Format::EAC_R11G11_UNORM_BLOCK => EacRg11Unorm,
+            vk::Format::EAC_R11G11_SNORM_BLOCK => EacRg11Snorm,
+            vk::Format::ASTC_4X4_UNORM_BLOCK => Astc4x4Unorm,
+            vk::Format::ASTC_4X4_SRGB_BLOCK => Astc4x4Srgb,
+            vk::Format::ASTC_5X4_UNORM_BLOCK => Astc5x4Unorm,
+            vk::Format::ASTC_5X4_SRGB_BLOCK => Astc5x4Srgb,
+            vk::Format::ASTC_5X5_UNORM_BLOCK => Astc5x5Unorm,
+            vk::Format::ASTC_5X5_SRGB_BLOCK => Astc5x5Srgb,
+            vk::Format::ASTC_6X5_UNORM_BLOCK => Astc6x5Unorm,
+            vk::Format::ASTC_6X5_SRGB_BLOCK => Astc6x5Srgb,
+            vk::Format::ASTC_6X6_UNORM_BLOCK => Astc6x6Unorm,
+            vk::Format::ASTC_6X6_SRGB_BLOCK => Astc6x6Srgb,
+            vk::Format::ASTC_8X5_UNORM_BLOCK => Astc8x5Unorm,
+            vk::Format::ASTC_8X5_SRGB_BLOCK => Astc8x5Srgb,
+            vk::Format::ASTC_8X6_UNORM_BLOCK => Astc8x6Unorm,
+            vk::Format::ASTC_8X6_SRGB_BLOCK => Astc8x6Srgb,
+            vk::Format::ASTC_8X8_UNORM_BLOCK => Astc8x8Unorm,
+            vk::Format::ASTC_8X8_SRGB_BLOCK => Astc8x8Srgb,
+            vk::Format::ASTC_10X5_UNORM_BLOCK => Astc10x5Unorm,
+            vk::Format::ASTC_10X5_SRGB_BLOCK => Astc10x5Srgb,
+            vk::Format::ASTC_10X6_UNORM_BLOCK => Astc10x6Unorm,
+            vk::Format::ASTC_10X6_SRGB_BLOCK => Astc10x6Srgb,
+            vk::Format::ASTC_10X8_UNORM_BLOCK => Astc10x8Unorm,
+            vk::Format::ASTC_10X8_SRGB_BLOCK => Astc10x8Srgb,
+            vk::Format::ASTC_10X10_UNORM_BLOCK => Astc10x10Unorm,
+            vk::Format::ASTC_10X10_SRGB_BLOCK => Astc10x10Srgb,
+            vk::Format::ASTC_12X10_UNORM_BLOCK => Astc12x10Unorm,
+            vk::Format::ASTC_12X10_SRGB_BLOCK => Astc12x10Srgb,
+            vk::Format::ASTC_12X12_UNORM_BLOCK => Astc12x12Unorm,
+            vk::Format::ASTC_12X12_SRGB_BLOCK => Astc12x12Srgb,
             _ => Err(())?,
         })
     }
@@ -75,22 +759,359 @@ impl From<Format> for vk::Format {
 
         match format {
             Undefined => Self::UNDEFINED,
-            R8Uint => Self::R8_UINT,
+            R4g4Unorm => Self::R4G4_UNORM_PACK8,
+            Rgba4Unorm => Self::R4G4B4A4_UNORM_PACK16,
+            Bgra4Unorm => Self::B4G4R4A4_UNORM_PACK16,
+            R5g6b5Unorm => Self::R5G6B5_UNORM_PACK16,
+            B5g6r5Unorm => Self::B5G6R5_UNORM_PACK16,
+            R5g5b5a1Unorm => Self::R5G5B5A1_UNORM_PACK16,
+            B5g5r5a1Unorm => Self::B5G5R5A1_UNORM_PACK16,
+            A1r5g5b5Unorm => Self::A1R5G5B5_UNORM_PACK16,
             R8Unorm => Self::R8_UNORM,
-            R32Uint => Self::R32_UINT,
-            Rg32Uint => Self::R32G32_UINT,
-            R32Sfloat => Self::R32_SFLOAT,
-            R16Uint => Self::R16_UINT,
-            Rgb32Uint => Self::R32G32B32_UINT,
-            Rgba32Sfloat => Self::R32G32B32A32_SFLOAT,
-            Rgba32Uint => Self::R32G32B32A32_UINT,
-            Rgba8Uint => Self::R8G8B8A8_UINT,
+            R8Snorm => Self::R8_SNORM,
+            R8Uscaled => Self::R8_USCALED,
+            R8Sscaled => Self::R8_SSCALED,
+            R8Uint => Self::R8_UINT,
+            R8Sint => Self::R8_SINT,
+            R8Srgb => Self::R8_SRGB,
+            Rg8Unorm => Self::R8G8_UNORM,
+            Rg8Snorm => Self::R8G8_SNORM,
+            Rg8Uscaled => Self::R8G8_USCALED,
+            Rg8Sscaled => Self::R8G8_SSCALED,
+            Rg8Uint => Self::R8G8_UINT,
+            Rg8Sint => Self::R8G8_SINT,
+            Rg8Srgb => Self::R8G8_SRGB,
+            Rgb8Unorm => Self::R8G8B8_UNORM,
+            Rgb8Snorm => Self::R8G8B8_SNORM,
+            Rgb8Uscaled => Self::R8G8B8_USCALED,
+            Rgb8Sscaled => Self::R8G8B8_SSCALED,
+            Rgb8Uint => Self::R8G8B8_UINT,
+            Rgb8Sint => Self::R8G8B8_SINT,
+            Rgb8Srgb => Self::R8G8B8_SRGB,
+            Bgr8Unorm => Self::B8G8R8_UNORM,
+            Bgr8Snorm => Self::B8G8R8_SNORM,
+            Bgr8Uscaled => Self::B8G8R8_USCALED,
+            Bgr8Sscaled => Self::B8G8R8_SSCALED,
+            Bgr8Uint => Self::B8G8R8_UINT,
+            Bgr8Sint => Self::B8G8R8_SINT,
+            Bgr8Srgb => Self::B8G8R8_SRGB,
             Rgba8Unorm => Self::R8G8B8A8_UNORM,
+            Rgba8Snorm => Self::R8G8B8A8_SNORM,
+            Rgba8Uscaled => Self::R8G8B8A8_USCALED,
+            Rgba8Sscaled => Self::R8G8B8A8_SSCALED,
+            Rgba8Uint => Self::R8G8B8A8_UINT,
+            Rgba8Sint => Self::R8G8B8A8_SINT,
             Rgba8Srgb => Self::R8G8B8A8_SRGB,
             Bgra8Unorm => Self::B8G8R8A8_UNORM,
+            Bgra8Snorm => Self::B8G8R8A8_SNORM,
+            Bgra8Uscaled => Self::B8G8R8A8_USCALED,
+            Bgra8Sscaled => Self::B8G8R8A8_SSCALED,
+            Bgra8Uint => Self::B8G8R8A8_UINT,
+            Bgra8Sint => Self::B8G8R8A8_SINT,
             Bgra8Srgb => Self::B8G8R8A8_SRGB,
+            Abgr8Unorm => Self::A8B8G8R8_UNORM_PACK32,
+            Abgr8Snorm => Self::A8B8G8R8_SNORM_PACK32,
+            Abgr8Uscaled => Self::A8B8G8R8_USCALED_PACK32,
+            Abgr8Sscaled => Self::A8B8G8R8_SSCALED_PACK32,
+            Abgr8Uint => Self::A8B8G8R8_UINT_PACK32,
+            Abgr8Sint => Self::A8B8G8R8_SINT_PACK32,
+            Abgr8Srgb => Self::A8B8G8R8_SRGB_PACK32,
+            A2r10g10b10Unorm => Self::A2R10G10B10_UNORM_PACK32,
+            A2r10g10b10Snorm => Self::A2R10G10B10_SNORM_PACK32,
+            A2r10g10b10Uscaled => Self::A2R10G10B10_USCALED_PACK32,
+            A2r10g10b10Sscaled => Self::A2R10G10B10_SSCALED_PACK32,
+            A2r10g10b10Uint => Self::A2R10G10B10_UINT_PACK32,
+            A2r10g10b10Sint => Self::A2R10G10B10_SINT_PACK32,
+            A2b10g10r10Unorm => Self::A2B10G10R10_UNORM_PACK32,
+            A2b10g10r10Snorm => Self::A2B10G10R10_SNORM_PACK32,
+            A2b10g10r10Uscaled => Self::A2B10G10R10_USCALED_PACK32,
+            A2b10g10r10Sscaled => Self::A2B10G10R10_SSCALED_PACK32,
+            A2b10g10r10Uint => Self::A2B10G10R10_UINT_PACK32,
+            A2b10g10r10Sint => Self::A2B10G10R10_SINT_PACK32,
+            R16Unorm => Self::R16_UNORM,
+            R16Snorm => Self::R16_SNORM,
+            R16Uscaled => Self::R16_USCALED,
+            R16Sscaled => Self::R16_SSCALED,
+            R16Uint => Self::R16_UINT,
+            R16Sint => Self::R16_SINT,
+            R16Sfloat => Self::R16_SFLOAT,
+            Rg16Unorm => Self::R16G16_UNORM,
+            Rg16Snorm => Self::R16G16_SNORM,
+            Rg16Uscaled => Self::R16G16_USCALED,
+            Rg16Sscaled => Self::R16G16_SSCALED,
+            Rg16Uint => Self::R16G16_UINT,
+            Rg16Sint => Self::R16G16_SINT,
+            Rg16Sfloat => Self::R16G16_SFLOAT,
+            Rgb16Unorm => Self::R16G16B16_UNORM,
+            Rgb16Snorm => Self::R16G16B16_SNORM,
+            Rgb16Uscaled => Self::R16G16B16_USCALED,
+            Rgb16Sscaled => Self::R16G16B16_SSCALED,
+            Rgb16Uint => Self::R16G16B16_UINT,
+            Rgb16Sint => Self::R16G16B16_SINT,
+            Rgb16Sfloat => Self::R16G16B16_SFLOAT,
+            Rgba16Unorm => Self::R16G16B16A16_UNORM,
+            Rgba16Snorm => Self::R16G16B16A16_SNORM,
+            Rgba16Uscaled => Self::R16G16B16A16_USCALED,
+            Rgba16Sscaled => Self::R16G16B16A16_SSCALED,
+            Rgba16Uint => Self::R16G16B16A16_UINT,
+            Rgba16Sint => Self::R16G16B16A16_SINT,
+            Rgba16Sfloat => Self::R16G16B16A16_SFLOAT,
+            R32Uint => Self::R32_UINT,
+            R32Sint => Self::R32_SINT,
+            R32Sfloat => Self::R32_SFLOAT,
+            Rg32Uint => Self::R32G32_UINT,
+            Rg32Sint => Self::R32G32_SINT,
+            Rg32Sfloat => Self::R32G32_SFLOAT,
+            Rgb32Uint => Self::R32G32B32_UINT,
+            Rgb32Sint => Self::R32G32B32_SINT,
+            Rgb32Sfloat => Self::R32G32B32_SFLOAT,
+            Rgba32Uint => Self::R32G32B32A32_UINT,
+            Rgba32Sint => Self::R32G32B32A32_SINT,
+            Rgba32Sfloat => Self::R32G32B32A32_SFLOAT,
+            R64Uint => Self::R64_UINT,
+            R64Sint => Self::R64_SINT,
+            R64Sfloat => Self::R64_SFLOAT,
+            Rg64Uint => Self::R64G64_UINT,
+            Rg64Sint => Self::R64G64_SINT,
+            Rg64Sfloat => Self::R64G64_SFLOAT,
+            Rgb64Uint => Self::R64G64B64_UINT,
+            Rgb64Sint => Self::R64G64B64_SINT,
+            Rgb64Sfloat => Self::R64G64B64_SFLOAT,
+            Rgba64Uint => Self::R64G64B64A64_UINT,
+            Rgba64Sint => Self::R64G64B64A64_SINT,
+            Rgba64Sfloat => Self::R64G64B64A64_SFLOAT,
+            B10g11r11Ufloat => Self::B10G11R11_UFLOAT_PACK32,
+            E5b9g9r9Ufloat => Self::E5B9G9R9_UFLOAT_PACK32,
+            D16Unorm => Self::D16_UNORM,
+            X8D24Unorm => Self::X8_D24_UNORM_PACK32,
             D32Sfloat => Self::D32_SFLOAT,
+            S8Uint => Self::S8_UINT,
+            D16UnormS8Uint => Self::D16_UNORM_S8_UINT,
+            D24UnormS8Uint => Self::D24_UNORM_S8_UINT,
             D32SfloatS8Uint => Self::D32_SFLOAT_S8_UINT,
+            Bc1RgbUnorm => Self::BC1_RGB_UNORM_BLOCK,
+            Bc1RgbSrgb => Self::BC1_RGB_SRGB_BLOCK,
+            Bc1RgbaUnorm => Self::BC1_RGBA_UNORM_BLOCK,
+            Bc1RgbaSrgb => Self::BC1_RGBA_SRGB_BLOCK,
+            Bc2Unorm => Self::BC2_UNORM_BLOCK,
+            Bc2Srgb => Self::BC2_SRGB_BLOCK,
+            Bc3Unorm => Self::BC3_UNORM_BLOCK,
+            Bc3Srgb => Self::BC3_SRGB_BLOCK,
+            Bc4Unorm => Self::BC4_UNORM_BLOCK,
+            Bc4Snorm => Self::BC4_SNORM_BLOCK,
+            Bc5Unorm => Self::BC5_UNORM_BLOCK,
+            Bc5Snorm => Self::BC5_SNORM_BLOCK,
+            Bc6hUfloat => Self::BC6H_UFLOAT_BLOCK,
+            Bc6hSfloat => Self::BC6H_SFLOAT_BLOCK,
+            Bc7Unorm => Self::BC7_UNORM_BLOCK,
+            Bc7Srgb => Self::BC7_SRGB_BLOCK,
+            Etc2Rgb8Unorm => Self::ETC2_R8G8B8_UNORM_BLOCK,
+            Etc2Rgb8Srgb => Self::ETC2_R8G8B8_SRGB_BLOCK,
+            Etc2Rgb8a1Unorm => Self::ETC2_R8G8B8A1_UNORM_BLOCK,
+            Etc2Rgb8a1Srgb => Self::ETC2_R8G8B8A1_SRGB_BLOCK,
+            Etc2Rgba8Unorm => Self::ETC2_R8G8B8A8_UNORM_BLOCK,
+            Etc2Rgba8Srgb => Self::ETC2_R8G8B8A8_SRGB_BLOCK,
+            EacR11Unorm => Self::EAC_R11_UNORM_BLOCK,
+            EacR11Snorm => Self::EAC_R11_SNORM_BLOCK,
+            EacRg11Unorm => Self::EAC_R11G11_UNORM_BLOCK,
+            EacRg11Snorm => Self::EAC_R11G11_SNORM_BLOCK,
+            Astc4x4Unorm => Self::ASTC_4X4_UNORM_BLOCK,
+            Astc4x4Srgb => Self::ASTC_4X4_SRGB_BLOCK,
+            Astc5x4Unorm => Self::ASTC_5X4_UNORM_BLOCK,
+            Astc5x4Srgb => Self::ASTC_5X4_SRGB_BLOCK,
+            Astc5x5Unorm => Self::ASTC_5X5_UNORM_BLOCK,
+            Astc5x5Srgb => Self::ASTC_5X5_SRGB_BLOCK,
+            Astc6x5Unorm => Self::ASTC_6X5_UNORM_BLOCK,
+            Astc6x5Srgb => Self::ASTC_6X5_SRGB_BLOCK,
+            Astc6x6Unorm => Self::ASTC_6X6_UNORM_BLOCK,
+            Astc6x6Srgb => Self::ASTC_6X6_SRGB_BLOCK,
+            Astc8x5Unorm => Self::ASTC_8X5_UNORM_BLOCK,
+            Astc8x5Srgb => Self::ASTC_8X5_SRGB_BLOCK,
+            Astc8x6Unorm => Self::ASTC_8X6_UNORM_BLOCK,
+            Astc8x6Srgb => Self::ASTC_8X6_SRGB_BLOCK,
+            Astc8x8Unorm => Self::ASTC_8X8_UNORM_BLOCK,
+            Astc8x8Srgb => Self::ASTC_8X8_SRGB_BLOCK,
+            Astc10x5Unorm => Self::ASTC_10X5_UNORM_BLOCK,
+            Astc10x5Srgb => Self::ASTC_10X5_SRGB_BLOCK,
+            Astc10x6Unorm => Self::ASTC_10X6_UNORM_BLOCK,
+            Astc10x6Srgb => Self::ASTC_10X6_SRGB_BLOCK,
+            Astc10x8Unorm => Self::ASTC_10X8_UNORM_BLOCK,
+            Astc10x8Srgb => Self::ASTC_10X8_SRGB_BLOCK,
+            Astc10x10Unorm => Self::ASTC_10X10_UNORM_BLOCK,
+            Astc10x10Srgb => Self::ASTC_10X10_SRGB_BLOCK,
+            Astc12x10Unorm => Self::ASTC_12X10_UNORM_BLOCK,
+            Astc12x10Srgb => Self::ASTC_12X10_SRGB_BLOCK,
+            Astc12x12Unorm => Self::ASTC_12X12_UNORM_BLOCK,
+            Astc12x12Srgb => Self::ASTC_12X12_SRGB_BLOCK,
+        }
+    }
+}
+
+///What images of one tiling can do with a format.
+#[derive(Clone, Copy, Debug)]
+pub struct FormatFeatures {
+    ///The usages images of this format can be created with.
+    pub usage: ImageUsage,
+    pub blit_src: bool,
+    pub blit_dst: bool,
+    ///Whether sampling can filter linearly, which linear blits also need.
+    pub linear_filter: bool,
+}
+
+impl From<vk::FormatFeatureFlags> for FormatFeatures {
+    fn from(features: vk::FormatFeatureFlags) -> Self {
+        let mut usage = ImageUsage::empty();
+
+        if features.contains(vk::FormatFeatureFlags::TRANSFER_SRC) {
+            usage |= ImageUsage::TRANSFER_SRC;
+        }
+
+        if features.contains(vk::FormatFeatureFlags::TRANSFER_DST) {
+            usage |= ImageUsage::TRANSFER_DST;
+        }
+
+        if features.contains(vk::FormatFeatureFlags::SAMPLED_IMAGE) {
+            usage |= ImageUsage::SAMPLED;
+        }
+
+        if features.contains(vk::FormatFeatureFlags::STORAGE_IMAGE) {
+            usage |= ImageUsage::STORAGE;
+        }
+
+        if features.contains(vk::FormatFeatureFlags::COLOR_ATTACHMENT) {
+            usage |= ImageUsage::COLOR;
+        }
+
+        if features.contains(vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT) {
+            usage |= ImageUsage::DEPTH_STENCIL;
+        }
+
+        //Any attachment can also be transient or read as an input attachment.
+        if usage.intersects(ImageUsage::COLOR | ImageUsage::DEPTH_STENCIL) {
+            usage |= ImageUsage::TRANSIENT | ImageUsage::INPUT_ATTACHMENT;
+        }
+
+        Self {
+            usage,
+            blit_src: features.contains(vk::FormatFeatureFlags::BLIT_SRC),
+            blit_dst: features.contains(vk::FormatFeatureFlags::BLIT_DST),
+            linear_filter: features.contains(vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR),
+        }
+    }
+}
+
+///What the device supports for a format, from `Device::format_properties`.
+#[derive(Clone, Copy, Debug)]
+pub struct FormatProperties {
+    ///Images created by the device are optimally tiled.
+    pub optimal_tiling: FormatFeatures,
+    ///Linearly tiled images are laid out row by row, and usually support far less.
+    pub linear_tiling: FormatFeatures,
+    ///Whether buffers of this format can be read as vertex attributes.
+    pub vertex_buffer: bool,
+    ///False when the format needs a device feature that is not enabled.
+    pub enabled: bool,
+}
+
+///The limits of an image of a format, type and usage, from `Device::image_format_supported`.
+#[derive(Clone, Copy, Debug)]
+pub struct ImageFormatProperties {
+    pub max_extent: (u32, u32, u32),
+    pub max_mip_levels: u32,
+    pub max_array_layers: u32,
+    pub sample_counts: SampleCount,
+}
+
+impl From<vk::ImageFormatProperties> for ImageFormatProperties {
+    fn from(properties: vk::ImageFormatProperties) -> Self {
+        let vk::ImageFormatProperties {
+            max_extent,
+            max_mip_levels,
+            max_array_layers,
+            sample_counts,
+            ..
+        } = properties;
+
+        Self {
+            max_extent: (max_extent.width, max_extent.height, max_extent.depth),
+            max_mip_levels,
+            max_array_layers,
+            sample_counts: SampleCount::from_bits_truncate(sample_counts.as_raw()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_round_trip() {
+        //Variants follow the core Vulkan formats, so their discriminants are the Vulkan values.
+        for raw in 0..=Format::Astc12x12Srgb as i32 {
+            let format = Format::try_from(vk::Format::from_raw(raw)).unwrap();
+
+            assert_eq!(format as i32, raw);
+            assert_eq!(vk::Format::from(format), vk::Format::from_raw(raw));
+        }
+
+        assert_eq!(Format::try_from(vk::Format::G8B8G8R8_422_UNORM), Err(()));
+        assert_eq!(Format::try_from(vk::Format::A4R4G4B4_UNORM_PACK16), Err(()));
+    }
+
+    #[test]
+    fn block_sizes_match_the_specification() {
+        use Format::*;
+
+        for (format, bytes_per_block, block_extent) in [
+            (R4g4Unorm, 1, (1, 1)),
+            (Rgba4Unorm, 2, (1, 1)),
+            (R5g6b5Unorm, 2, (1, 1)),
+            (R8Unorm, 1, (1, 1)),
+            (Rg8Snorm, 2, (1, 1)),
+            (Rgb8Unorm, 3, (1, 1)),
+            (Rgba8Srgb, 4, (1, 1)),
+            (Bgra8Unorm, 4, (1, 1)),
+            (A2b10g10r10Unorm, 4, (1, 1)),
+            (R16Sfloat, 2, (1, 1)),
+            (Rgb16Unorm, 6, (1, 1)),
+            (Rgba16Sfloat, 8, (1, 1)),
+            (R32Uint, 4, (1, 1)),
+            (Rgb32Sfloat, 12, (1, 1)),
+            (Rgba32Sfloat, 16, (1, 1)),
+            (R64Sfloat, 8, (1, 1)),
+            (Rgba64Uint, 32, (1, 1)),
+            (B10g11r11Ufloat, 4, (1, 1)),
+            (E5b9g9r9Ufloat, 4, (1, 1)),
+            (D16Unorm, 2, (1, 1)),
+            (X8D24Unorm, 4, (1, 1)),
+            (D32Sfloat, 4, (1, 1)),
+            (S8Uint, 1, (1, 1)),
+            (D16UnormS8Uint, 3, (1, 1)),
+            (D24UnormS8Uint, 4, (1, 1)),
+            (D32SfloatS8Uint, 5, (1, 1)),
+            (Bc1RgbUnorm, 8, (4, 4)),
+            (Bc1RgbaSrgb, 8, (4, 4)),
+            (Bc2Unorm, 16, (4, 4)),
+            (Bc3Srgb, 16, (4, 4)),
+            (Bc4Snorm, 8, (4, 4)),
+            (Bc5Unorm, 16, (4, 4)),
+            (Bc6hUfloat, 16, (4, 4)),
+            (Bc7Srgb, 16, (4, 4)),
+            (Etc2Rgb8Unorm, 8, (4, 4)),
+            (Etc2Rgb8a1Srgb, 8, (4, 4)),
+            (Etc2Rgba8Unorm, 16, (4, 4)),
+            (EacR11Unorm, 8, (4, 4)),
+            (EacRg11Snorm, 16, (4, 4)),
+            (Astc4x4Unorm, 16, (4, 4)),
+            (Astc5x4Srgb, 16, (5, 4)),
+            (Astc6x5Unorm, 16, (6, 5)),
+            (Astc8x8Srgb, 16, (8, 8)),
+            (Astc10x5Unorm, 16, (10, 5)),
+            (Astc12x12Srgb, 16, (12, 12)),
+        ] {
+            assert_eq!(format.bytes_per_block(), bytes_per_block, "{format:?}");
+            assert_eq!(format.block_extent(), block_extent, "{format:?}");
         }
     }
 }
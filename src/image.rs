@@ -9,12 +9,40 @@ pub(crate) enum InternalImage {
         image: vk::Image,
         memory: vk::DeviceMemory,
         view: vk::ImageView,
+        view_type: vk::ImageViewType,
         format: Format,
+        extent: vk::Extent3D,
+        mip_levels: u32,
+        array_layers: u32,
+        samples: vk::SampleCountFlags,
+        usage: vk::ImageUsageFlags,
+        ///The formats other than `format` the image can be viewed as.
+        view_formats: Vec<Format>,
+        debug_name: String,
     },
     Swapchain {
         image: vk::Image,
         view: vk::ImageView,
         format: Format,
+        extent: vk::Extent3D,
+        usage: vk::ImageUsageFlags,
+        debug_name: String,
+    },
+    ///An additional view of a managed image, from `Device::create_image_view`.
+    View {
+        ///Always a managed image, never another view.
+        parent: Image,
+        image: vk::Image,
+        view: vk::ImageView,
+        view_type: vk::ImageViewType,
+        format: Format,
+        ///The extent of the first mip level in `range`.
+        extent: vk::Extent3D,
+        ///Resolved against the parent.
+        range: ImageRange,
+        samples: vk::SampleCountFlags,
+        usage: vk::ImageUsageFlags,
+        debug_name: String,
     },
 }
 impl InternalImage {
@@ -22,18 +50,120 @@ impl InternalImage {
         match self {
             Self::Managed { image, .. } => *image,
             Self::Swapchain { image, .. } => *image,
+            Self::View { image, .. } => *image,
         }
     }
     pub(crate) fn get_image_view(&self) -> vk::ImageView {
         match self {
             Self::Managed { view, .. } => *view,
             Self::Swapchain { view, .. } => *view,
+            Self::View { view, .. } => *view,
+        }
+    }
+    pub(crate) fn get_view_type(&self) -> vk::ImageViewType {
+        match self {
+            Self::Managed { view_type, .. } => *view_type,
+            Self::Swapchain { .. } => vk::ImageViewType::TYPE_2D,
+            Self::View { view_type, .. } => *view_type,
         }
     }
     pub(crate) fn get_format(&self) -> Format {
         match self {
             Self::Managed { format, .. } => *format,
             Self::Swapchain { format, .. } => *format,
+            Self::View { format, .. } => *format,
+        }
+    }
+    pub(crate) fn get_extent(&self) -> vk::Extent3D {
+        match self {
+            Self::Managed { extent, .. } => *extent,
+            Self::Swapchain { extent, .. } => *extent,
+            Self::View { extent, .. } => *extent,
+        }
+    }
+    pub(crate) fn get_mip_levels(&self) -> u32 {
+        match self {
+            Self::Managed { mip_levels, .. } => *mip_levels,
+            Self::Swapchain { .. } => 1,
+            Self::View { range, .. } => range.mip_level_count,
+        }
+    }
+    pub(crate) fn get_array_layers(&self) -> u32 {
+        match self {
+            Self::Managed { array_layers, .. } => *array_layers,
+            Self::Swapchain { .. } => 1,
+            Self::View { range, .. } => range.array_layer_count,
+        }
+    }
+    pub(crate) fn get_samples(&self) -> vk::SampleCountFlags {
+        match self {
+            Self::Managed { samples, .. } => *samples,
+            Self::Swapchain { .. } => vk::SampleCountFlags::TYPE_1,
+            Self::View { samples, .. } => *samples,
+        }
+    }
+    pub(crate) fn get_usage(&self) -> vk::ImageUsageFlags {
+        match self {
+            Self::Managed { usage, .. } => *usage,
+            Self::Swapchain { usage, .. } => *usage,
+            Self::View { usage, .. } => *usage,
+        }
+    }
+    pub(crate) fn get_view_formats(&self) -> &[Format] {
+        match self {
+            Self::Managed { view_formats, .. } => view_formats,
+            _ => &[],
+        }
+    }
+    ///The image a view was created from, which the render graph synchronizes it as.
+    pub(crate) fn get_parent(&self) -> Option<Image> {
+        match self {
+            Self::View { parent, .. } => Some(*parent),
+            _ => None,
+        }
+    }
+    ///Moves a resolved range of this image onto its parent, so it can be used with the parent's `vk::Image`.
+    pub(crate) fn to_parent(&self, range: ImageRange) -> ImageRange {
+        match self {
+            Self::View {
+                range: view_range, ..
+            } => ImageRange {
+                base_mip_level: view_range.base_mip_level + range.base_mip_level,
+                base_array_layer: view_range.base_array_layer + range.base_array_layer,
+                ..range
+            },
+            _ => range,
+        }
+    }
+    ///Moves a subresource of this image onto its parent, like `to_parent`.
+    pub(crate) fn subresource_to_parent(&self, subresource: ImageSubresource) -> ImageSubresource {
+        match self {
+            Self::View { range, .. } => ImageSubresource {
+                mip_level: range.base_mip_level + subresource.mip_level,
+                base_array_layer: range.base_array_layer + subresource.base_array_layer,
+                ..subresource
+            },
+            _ => subresource,
+        }
+    }
+    ///The reverse of `to_parent`, for ranges that lie within this image.
+    pub(crate) fn from_parent(&self, range: ImageRange) -> ImageRange {
+        match self {
+            Self::View {
+                range: view_range, ..
+            } => ImageRange {
+                base_mip_level: range.base_mip_level - view_range.base_mip_level,
+                base_array_layer: range.base_array_layer - view_range.base_array_layer,
+                ..range
+            },
+            _ => range,
+        }
+    }
+    pub(crate) fn get_debug_name(&self) -> &str {
+        match self {
+            Self::Managed { debug_name, .. } => debug_name,
+            Self::Swapchain { debug_name, .. } => debug_name,
+            Self::View { debug_name, .. } => debug_name,
         }
     }
 }
@@ -55,20 +185,57 @@ pub struct ImageInfo<'a> {
     pub extent: ImageExtent,
     pub usage: ImageUsage,
     pub format: Format,
+    ///The amount of mip levels, each half the size of the one before.
+    ///`ImageInfo::FULL_MIP_CHAIN` goes all the way down to a single texel.
+    pub mip_levels: u32,
+    ///The amount of array layers. Layered images are viewed as arrays, except for 3D images which cannot have layers.
+    pub array_layers: u32,
+    ///Makes a 2D image cube compatible, and views it as a cube, or as a cube array past six layers.
+    ///Needs a square extent and a multiple of six array layers, ordered +X, -X, +Y, -Y, +Z, -Z.
+    ///Cube arrays need `Features::image_cube_array`.
+    pub cube: bool,
+    ///Other formats `Device::create_image_view` may view the image as.
+    ///They have to be compatible with `format`, usually by having the same texel size.
+    pub view_formats: &'a [Format],
+    ///Multisampled images have one mip level, and are resolved with `Attachment::resolve` or `Commands::resolve_image`.
+    ///Checked against what the device supports for the format and usage.
+    pub samples: SampleCount,
     pub debug_name: &'a str,
 }
 
+impl ImageInfo<'_> {
+    pub const FULL_MIP_CHAIN: u32 = u32::MAX;
+}
+
 impl Default for ImageInfo<'_> {
     fn default() -> Self {
         Self {
             extent: Default::default(),
             usage: ImageUsage::empty(),
             format: Format::Undefined,
+            mip_levels: 1,
+            array_layers: 1,
+            cube: false,
+            view_formats: &[],
+            samples: SampleCount::TYPE_1,
             debug_name: "",
         }
     }
 }
 
+impl ImageExtent {
+    ///The amount of mip levels in a full mip chain of an image this size.
+    pub fn full_mip_levels(self) -> u32 {
+        let largest = match self {
+            Self::OneDim(x) => x,
+            Self::TwoDim(x, y) => x.max(y),
+            Self::ThreeDim(x, y, z) => x.max(y).max(z),
+        };
+
+        usize::BITS - largest.max(1).leading_zeros()
+    }
+}
+
 #[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
 #[repr(transparent)]
 ///An image handle, used for bindless images.
@@ -86,6 +253,202 @@ impl From<u32> for Image {
     }
 }
 
+///A range of mip levels and array layers of an image.
+///A count of `u32::MAX` means every remaining mip level or layer.
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
+pub struct ImageRange {
+    pub base_mip_level: u32,
+    pub mip_level_count: u32,
+    pub base_array_layer: u32,
+    pub array_layer_count: u32,
+}
+
+impl ImageRange {
+    ///Every mip level and array layer of the image.
+    pub const ALL: Self = Self {
+        base_mip_level: 0,
+        mip_level_count: u32::MAX,
+        base_array_layer: 0,
+        array_layer_count: u32::MAX,
+    };
+
+    ///A single mip level of every array layer.
+    pub fn mip(level: u32) -> Self {
+        Self {
+            base_mip_level: level,
+            mip_level_count: 1,
+            ..Self::ALL
+        }
+    }
+
+    ///A single array layer of every mip level.
+    pub fn layer(layer: u32) -> Self {
+        Self {
+            base_array_layer: layer,
+            array_layer_count: 1,
+            ..Self::ALL
+        }
+    }
+
+    ///Clamps the range to an image with the given amount of mip levels and array layers.
+    pub(crate) fn resolve(self, mip_levels: u32, array_layers: u32) -> Self {
+        let base_mip_level = self.base_mip_level.min(mip_levels);
+        let base_array_layer = self.base_array_layer.min(array_layers);
+
+        Self {
+            base_mip_level,
+            mip_level_count: self.mip_level_count.min(mip_levels - base_mip_level),
+            base_array_layer,
+            array_layer_count: self.array_layer_count.min(array_layers - base_array_layer),
+        }
+    }
+}
+
+///The mip level and array layers of an image a copy reads or writes.
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
+pub struct ImageSubresource {
+    pub mip_level: u32,
+    pub base_array_layer: u32,
+    pub array_layer_count: u32,
+}
+
+///How a view interprets the layers of an image.
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
+pub enum ImageViewType {
+    OneDim,
+    TwoDim,
+    ThreeDim,
+    ///Six layers of a cube compatible image.
+    Cube,
+    OneDimArray,
+    TwoDimArray,
+    ///A multiple of six layers of a cube compatible image, needs `Features::image_cube_array`.
+    CubeArray,
+}
+
+impl From<ImageViewType> for vk::ImageViewType {
+    fn from(view_type: ImageViewType) -> Self {
+        match view_type {
+            ImageViewType::OneDim => Self::TYPE_1D,
+            ImageViewType::TwoDim => Self::TYPE_2D,
+            ImageViewType::ThreeDim => Self::TYPE_3D,
+            ImageViewType::Cube => Self::CUBE,
+            ImageViewType::OneDimArray => Self::TYPE_1D_ARRAY,
+            ImageViewType::TwoDimArray => Self::TYPE_2D_ARRAY,
+            ImageViewType::CubeArray => Self::CUBE_ARRAY,
+        }
+    }
+}
+
+///Where a component of a view reads from.
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
+pub enum ComponentSwizzle {
+    Identity,
+    Zero,
+    One,
+    R,
+    G,
+    B,
+    A,
+}
+
+impl From<ComponentSwizzle> for vk::ComponentSwizzle {
+    fn from(swizzle: ComponentSwizzle) -> Self {
+        match swizzle {
+            ComponentSwizzle::Identity => Self::IDENTITY,
+            ComponentSwizzle::Zero => Self::ZERO,
+            ComponentSwizzle::One => Self::ONE,
+            ComponentSwizzle::R => Self::R,
+            ComponentSwizzle::G => Self::G,
+            ComponentSwizzle::B => Self::B,
+            ComponentSwizzle::A => Self::A,
+        }
+    }
+}
+
+///An additional view of an image, for `Device::create_image_view`.
+pub struct ImageViewInfo<'a> {
+    ///`None` picks the type the image itself is viewed as, without the array when `range` has one layer.
+    pub view_type: Option<ImageViewType>,
+    ///`None` keeps the format of the image. Other formats have to be listed in `ImageInfo::view_formats`.
+    pub format: Option<Format>,
+    ///The mip levels and layers that are viewed, which become the first of the view.
+    pub range: ImageRange,
+    ///Where the red, green, blue and alpha components are read from.
+    ///Views that swizzle cannot be used as storage images.
+    pub swizzle: [ComponentSwizzle; 4],
+    pub debug_name: &'a str,
+}
+
+impl Default for ImageViewInfo<'_> {
+    fn default() -> Self {
+        Self {
+            view_type: None,
+            format: None,
+            range: ImageRange::ALL,
+            swizzle: [ComponentSwizzle::Identity; 4],
+            debug_name: "",
+        }
+    }
+}
+
+impl ImageSubresource {
+    ///A single mip level of the first array layer.
+    pub fn mip(level: u32) -> Self {
+        Self {
+            mip_level: level,
+            ..Default::default()
+        }
+    }
+
+    ///A single array layer of the first mip level.
+    pub fn layer(layer: u32) -> Self {
+        Self {
+            base_array_layer: layer,
+            ..Default::default()
+        }
+    }
+
+    ///Whether this lies within a resolved range.
+    pub(crate) fn within(self, range: ImageRange) -> bool {
+        let mip_levels = range.base_mip_level..range.base_mip_level + range.mip_level_count;
+
+        let array_layers = range.base_array_layer..range.base_array_layer + range.array_layer_count;
+
+        mip_levels.contains(&self.mip_level)
+            && array_layers.contains(&self.base_array_layer)
+            && self.base_array_layer + self.array_layer_count <= array_layers.end
+    }
+}
+
+impl Default for ImageSubresource {
+    fn default() -> Self {
+        Self {
+            mip_level: 0,
+            base_array_layer: 0,
+            array_layer_count: 1,
+        }
+    }
+}
+
+impl Default for ImageRange {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl From<ImageRange> for vk::ImageSubresourceRange {
+    fn from(range: ImageRange) -> Self {
+        Self {
+            aspect_mask: vk::ImageAspectFlags::empty(),
+            base_mip_level: range.base_mip_level,
+            level_count: range.mip_level_count,
+            base_array_layer: range.base_array_layer,
+            layer_count: range.array_layer_count,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum ImageLayout {
     Undefined,
@@ -116,12 +479,19 @@ impl From<ImageLayout> for vk::ImageLayout {
 }
 
 bitflags! {
-    #[derive(Clone, Copy)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     pub struct ImageUsage: u32 {
         const TRANSFER_SRC = 0x00000001;
         const TRANSFER_DST = 0x00000002;
+        const SAMPLED = 0x00000004;
+        ///Read and written by shaders, and bindless storage images.
+        const STORAGE = 0x00000008;
         const COLOR = 0x00000010;
         const DEPTH_STENCIL = 0x00000020;
+        ///Attachments that never leave the tile memory of the GPU, such as multisampled
+        ///attachments that are resolved. Can only be used together with attachment usages.
+        const TRANSIENT = 0x00000040;
+        const INPUT_ATTACHMENT = 0x00000080;
     }
 }
 
@@ -172,6 +542,14 @@ impl From<ImageUsage> for vk::ImageUsageFlags {
             result |= vk::ImageUsageFlags::TRANSFER_DST;
         }
 
+        if usage.contains(ImageUsage::SAMPLED) {
+            result |= vk::ImageUsageFlags::SAMPLED;
+        }
+
+        if usage.contains(ImageUsage::STORAGE) {
+            result |= vk::ImageUsageFlags::STORAGE;
+        }
+
         if usage.contains(ImageUsage::COLOR) {
             result |= vk::ImageUsageFlags::COLOR_ATTACHMENT;
         }
@@ -180,6 +558,14 @@ impl From<ImageUsage> for vk::ImageUsageFlags {
             result |= vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT;
         }
 
+        if usage.contains(ImageUsage::TRANSIENT) {
+            result |= vk::ImageUsageFlags::TRANSIENT_ATTACHMENT;
+        }
+
+        if usage.contains(ImageUsage::INPUT_ATTACHMENT) {
+            result |= vk::ImageUsageFlags::INPUT_ATTACHMENT;
+        }
+
         result
     }
 }
@@ -1,15 +1,21 @@
 pub mod buffer;
+pub mod capture;
 pub mod commands;
 pub mod context;
 pub mod device;
 pub mod format;
 pub mod image;
 pub mod memory;
+pub mod mipmap;
 pub mod pipeline;
 pub mod renderpass;
+pub mod sampler;
 pub mod semaphore;
+pub mod subgraph;
 pub mod swapchain;
 pub mod task;
+pub mod token;
+pub mod visualize;
 
 use std::error;
 use std::fmt;
@@ -17,22 +23,31 @@ use std::result;
 
 pub mod prelude {
     pub(crate) use crate::buffer::InternalBuffer;
-    pub use crate::buffer::{Buffer, BufferAddress, BufferInfo, BufferUsage};
+    pub use crate::buffer::{Buffer, BufferAddress, BufferInfo, BufferRange, BufferUsage};
+    pub(crate) use crate::capture::Capture;
+    pub use crate::capture::{CaptureFormat, CaptureInfo, Screenshot};
     pub use crate::commands::{
         Access, Attachment, Barrier, BindIndexBuffer, BufferCopy, BufferImageCopy, BufferRead,
         BufferWrite, Clear, Commands, Draw, DrawIndexed, DrawIndexedIndirectCommand, DrawIndirect,
-        DrawIndirectCommand, ImageCopy, LoadOp, PipelineBarrier, PushConstant, Region, Render,
-        RenderArea, WriteBinding,
+        DrawIndirectCommand, ImageCopy, ImageResolve, LoadOp, PipelineBarrier, PushConstant, Region,
+        Render, RenderArea, WriteBinding,
     };
     pub(crate) use crate::context::DESCRIPTOR_COUNT;
     pub use crate::context::{Context, ContextInfo};
     pub(crate) use crate::device::DeviceResources;
-    pub use crate::device::{Device, DeviceInfo, Features};
-    pub use crate::format::Format;
+    pub use crate::device::{Device, DeviceInfo, Features, Queue, SampleCount};
+    pub use crate::format::{
+        Compression, Format, FormatFeatures, FormatProperties, ImageFormatProperties,
+        NumericFormat,
+    };
     pub(crate) use crate::image::InternalImage;
-    pub use crate::image::{Image, ImageAspect, ImageExtent, ImageInfo, ImageLayout, ImageUsage};
+    pub use crate::image::{
+        ComponentSwizzle, Image, ImageAspect, ImageExtent, ImageInfo, ImageLayout, ImageRange,
+        ImageSubresource, ImageUsage, ImageViewInfo, ImageViewType,
+    };
     pub(crate) use crate::memory::InternalMemory;
     pub use crate::memory::Memory;
+    pub(crate) use crate::mipmap::Mipmaps;
     pub use crate::pipeline::{
         Blend, BlendFactor, BlendOp, Color, ColorComponent, CompareOp, ComputePipelineInfo, Define,
         Depth, FaceCull, FrontFace, GraphicsPipelineInfo, Pipeline, PipelineCompiler,
@@ -40,15 +55,29 @@ pub mod prelude {
         ShaderType, Stencil, StencilOp, StencilState,
     };
     pub use crate::renderpass::*;
+    pub(crate) use crate::sampler::InternalSampler;
+    pub use crate::sampler::{AddressMode, BorderColor, Filter, Sampler, SamplerInfo};
     pub use crate::semaphore::{
         BinarySemaphore, BinarySemaphoreInfo, TimelineSemaphore, TimelineSemaphoreInfo,
     };
     pub(crate) use crate::swapchain::InternalSwapchain;
-    pub use crate::swapchain::{Acquire, PresentMode, Swapchain, SwapchainInfo};
+    pub use crate::subgraph::{Port, SubGraph};
+    pub use crate::swapchain::{
+        default_surface_format_selector, hdr_surface_format_selector, Acquire, ColorSpace,
+        PresentMode, SurfaceCapabilities, SurfaceFormat, Swapchain, SwapchainInfo,
+        VirtualSwapchainInfo,
+    };
     pub(crate) use crate::task::Qualifier;
     pub use crate::task::{
-        BufferAccess, ImageAccess, Present, RenderGraph, RenderGraphBuilder, RenderGraphInfo,
-        Resource, Submit, Task,
+        BufferAccess, ImageAccess, ParallelTask, Present, RenderGraph, RenderGraphBuilder,
+        RenderGraphInfo, Resource, Submit, Task,
+    };
+    pub(crate) use crate::token::Brand;
+    pub use crate::token::{
+        AttachmentAccess, BufferDeclaration, BufferToken, ImageDeclaration, ImageToken, Kind,
+        ReadBufferAccess, ReadImageAccess, ReadOnly, ReadWrite, ReadWriteBufferAccess,
+        ReadWriteImageAccess, RenderTarget, Resources, WriteBufferAccess, WriteImageAccess,
+        WriteOnly,
     };
     pub(crate) use crate::{Error, Result};
 }
@@ -69,10 +98,38 @@ pub enum Error {
     AllocateDescriptorSets,
     EnumeratePhysicalDevices,
     CreateSurface,
+    ///The GPU cannot present to the surface of the window.
+    PresentNotSupported,
+    ///The device does not support the sample count for this format, usage or attachment.
+    UnsupportedSampleCount,
+    ///Frames of this format cannot be written to disk by a virtual swapchain.
+    UnsupportedCaptureFormat,
+    ///A frame of a virtual swapchain could not be copied back or written to disk.
+    CaptureFailed,
+    ///No frame has been copied back from the swapchain since it was created or resized.
+    NothingCaptured,
+    ///A virtual swapchain needs at least one image.
+    InvalidImageCount,
+    ///A render graph could not record or submit its command buffers, usually because the device was lost.
+    SubmitFailed,
+    ///The format needs a device feature that is not enabled, such as texture compression.
+    UnsupportedFormat,
+    ///The format of a view is neither the format of its image nor one of its `ImageInfo::view_formats`.
+    IncompatibleViewFormat,
+    ///The format can neither be blitted nor downsampled by the built in compute shader.
+    UnsupportedMipmapFormat,
     CreateCommandPool,
     CreateLogicalDevice,
     CreateDescriptorPool,
     CreateDescriptorSetLayout,
+    ///A task returned an error, or its barriers could not be recorded.
+    TaskFailed { task: String, error: Box<Error> },
+    ///A task used a resource in a way it did not declare.
+    UndeclaredAccess {
+        task: String,
+        resource: String,
+        message: String,
+    },
 }
 
 impl fmt::Display for Error {
@@ -0,0 +1,609 @@
+use crate::prelude::*;
+
+use std::collections::HashMap;
+use std::ffi;
+use std::io;
+
+use ash::vk;
+
+///The workgroup size of `shaders/downsample.comp`.
+const LOCAL_SIZE: u32 = 8;
+
+///The downsample shader compiled for a format, for the formats it supports.
+///Each variant declares its storage images with the format, see `shaders/downsample.comp`.
+fn downsample_spirv(format: Format) -> Option<&'static [u8]> {
+    use Format::*;
+
+    match format {
+        Rgba32Sfloat => Some(include_bytes!("shaders/downsample_rgba32f.spv")),
+        Rgba16Sfloat => Some(include_bytes!("shaders/downsample_rgba16f.spv")),
+        R32Sfloat => Some(include_bytes!("shaders/downsample_r32f.spv")),
+        Rgba8Unorm => Some(include_bytes!("shaders/downsample_rgba8.spv")),
+        _ => None,
+    }
+}
+
+///The size of a mip level of an image with the given extent.
+pub(crate) fn mip_extent(extent: vk::Extent3D, level: u32) -> vk::Extent3D {
+    vk::Extent3D {
+        width: (extent.width >> level).max(1),
+        height: (extent.height >> level).max(1),
+        depth: (extent.depth >> level).max(1),
+    }
+}
+
+fn mip_offset(extent: vk::Extent3D, level: u32) -> vk::Offset3D {
+    let vk::Extent3D {
+        width,
+        height,
+        depth,
+    } = mip_extent(extent, level);
+
+    vk::Offset3D {
+        x: width as _,
+        y: height as _,
+        z: depth as _,
+    }
+}
+
+fn image_barrier(
+    image: vk::Image,
+    (src_access_mask, dst_access_mask): (vk::AccessFlags, vk::AccessFlags),
+    (old_layout, new_layout): (vk::ImageLayout, vk::ImageLayout),
+    (base_mip_level, level_count): (u32, u32),
+) -> vk::ImageMemoryBarrier {
+    vk::ImageMemoryBarrier {
+        src_access_mask,
+        dst_access_mask,
+        old_layout,
+        new_layout,
+        src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+        dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+        image,
+        subresource_range: vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level,
+            level_count,
+            base_array_layer: 0,
+            layer_count: vk::REMAINING_ARRAY_LAYERS,
+        },
+        ..Default::default()
+    }
+}
+
+///Blits every level from the one before it, with every level starting and ending in `TRANSFER_DST_OPTIMAL`.
+pub(crate) fn record_blits(
+    logical_device: &ash::Device,
+    command_buffer: vk::CommandBuffer,
+    image: vk::Image,
+    extent: vk::Extent3D,
+    mip_levels: u32,
+    array_layers: u32,
+) {
+    for level in 1..mip_levels {
+        let barrier = image_barrier(
+            image,
+            (
+                vk::AccessFlags::TRANSFER_WRITE,
+                vk::AccessFlags::TRANSFER_READ,
+            ),
+            (
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            ),
+            (level - 1, 1),
+        );
+
+        let layers = |mip_level| vk::ImageSubresourceLayers {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            mip_level,
+            base_array_layer: 0,
+            layer_count: array_layers,
+        };
+
+        let blit = vk::ImageBlit {
+            src_subresource: layers(level - 1),
+            src_offsets: [vk::Offset3D::default(), mip_offset(extent, level - 1)],
+            dst_subresource: layers(level),
+            dst_offsets: [vk::Offset3D::default(), mip_offset(extent, level)],
+        };
+
+        unsafe {
+            logical_device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[barrier],
+            );
+
+            logical_device.cmd_blit_image(
+                command_buffer,
+                image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &[blit],
+                vk::Filter::LINEAR,
+            );
+        }
+    }
+
+    //Every level but the last was read from, and goes back to the layout the image was declared with.
+    let barrier = image_barrier(
+        image,
+        (
+            vk::AccessFlags::TRANSFER_READ,
+            vk::AccessFlags::TRANSFER_WRITE,
+        ),
+        (
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+        ),
+        (0, mip_levels - 1),
+    );
+
+    unsafe {
+        logical_device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[barrier],
+        );
+    }
+}
+
+///The compute pipelines behind `Commands::generate_mipmaps`, created the first time a format needs them,
+///and the views and descriptor sets of the images they downsample.
+#[derive(Default)]
+pub(crate) struct Mipmaps {
+    layouts: Option<(vk::DescriptorSetLayout, vk::PipelineLayout)>,
+    pipelines: HashMap<Format, vk::Pipeline>,
+    ///Evicted whenever the image is removed from the device resources, before its handle can be reused.
+    images: HashMap<Image, ImageSets>,
+}
+
+///Views of every level and layer of an image, and the descriptor sets downsampling into each of them.
+struct ImageSets {
+    image: vk::Image,
+    ///Holds only the descriptor sets of this image, so that they are freed with it.
+    descriptor_pool: vk::DescriptorPool,
+    image_views: Vec<vk::ImageView>,
+    ///Indexed by destination level minus one, then by layer.
+    descriptor_sets: Vec<vk::DescriptorSet>,
+}
+
+impl ImageSets {
+    fn destroy(self, logical_device: &ash::Device) {
+        unsafe {
+            logical_device.destroy_descriptor_pool(self.descriptor_pool, None);
+
+            for image_view in self.image_views {
+                logical_device.destroy_image_view(image_view, None);
+            }
+        }
+    }
+}
+
+impl Mipmaps {
+    ///Averages every level from the one before it, with every level starting and ending in `TRANSFER_DST_OPTIMAL`.
+    pub(crate) fn record_downsample(
+        &mut self,
+        logical_device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        handle: Image,
+        internal_image: &InternalImage,
+    ) -> Result<()> {
+        let image = internal_image.get_image();
+        let format = internal_image.get_format();
+        let extent = internal_image.get_extent();
+        let mip_levels = internal_image.get_mip_levels();
+        let array_layers = internal_image.get_array_layers();
+
+        let (pipeline, pipeline_layout) = self.pipeline(logical_device, format)?;
+
+        let descriptor_sets = self
+            .image_sets(
+                logical_device,
+                handle,
+                image,
+                format,
+                mip_levels,
+                array_layers,
+            )?
+            .descriptor_sets
+            .clone();
+
+        let to_general = image_barrier(
+            image,
+            (
+                vk::AccessFlags::TRANSFER_WRITE,
+                vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE,
+            ),
+            (
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::ImageLayout::GENERAL,
+            ),
+            (0, mip_levels),
+        );
+
+        unsafe {
+            logical_device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[to_general],
+            );
+
+            logical_device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                pipeline,
+            );
+        }
+
+        for level in 1..mip_levels {
+            let vk::Extent3D { width, height, .. } = mip_extent(extent, level);
+
+            for layer in 0..array_layers {
+                let descriptor_set = descriptor_sets[((level - 1) * array_layers + layer) as usize];
+
+                unsafe {
+                    logical_device.cmd_bind_descriptor_sets(
+                        command_buffer,
+                        vk::PipelineBindPoint::COMPUTE,
+                        pipeline_layout,
+                        0,
+                        &[descriptor_set],
+                        &[],
+                    );
+
+                    logical_device.cmd_dispatch(
+                        command_buffer,
+                        width.div_ceil(LOCAL_SIZE),
+                        height.div_ceil(LOCAL_SIZE),
+                        1,
+                    );
+                }
+            }
+
+            //The next level reads this one.
+            let barrier = image_barrier(
+                image,
+                (vk::AccessFlags::SHADER_WRITE, vk::AccessFlags::SHADER_READ),
+                (vk::ImageLayout::GENERAL, vk::ImageLayout::GENERAL),
+                (level, 1),
+            );
+
+            unsafe {
+                logical_device.cmd_pipeline_barrier(
+                    command_buffer,
+                    vk::PipelineStageFlags::COMPUTE_SHADER,
+                    vk::PipelineStageFlags::COMPUTE_SHADER,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &[],
+                    &[barrier],
+                );
+            }
+        }
+
+        //Back to the layout the image was declared with.
+        let to_transfer = image_barrier(
+            image,
+            (
+                vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE,
+                vk::AccessFlags::TRANSFER_WRITE,
+            ),
+            (
+                vk::ImageLayout::GENERAL,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            ),
+            (0, mip_levels),
+        );
+
+        unsafe {
+            logical_device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                &[to_transfer],
+            );
+        }
+
+        Ok(())
+    }
+
+    fn pipeline(
+        &mut self,
+        logical_device: &ash::Device,
+        format: Format,
+    ) -> Result<(vk::Pipeline, vk::PipelineLayout)> {
+        let (_, pipeline_layout) = match self.layouts {
+            Some(layouts) => layouts,
+            None => {
+                let bindings = [0, 1].map(|binding| vk::DescriptorSetLayoutBinding {
+                    binding,
+                    descriptor_type: vk::DescriptorType::STORAGE_IMAGE,
+                    descriptor_count: 1,
+                    stage_flags: vk::ShaderStageFlags::COMPUTE,
+                    ..Default::default()
+                });
+
+                let descriptor_set_layout_create_info = vk::DescriptorSetLayoutCreateInfo {
+                    binding_count: bindings.len() as _,
+                    p_bindings: bindings.as_ptr(),
+                    ..Default::default()
+                };
+
+                let descriptor_set_layout = unsafe {
+                    logical_device
+                        .create_descriptor_set_layout(&descriptor_set_layout_create_info, None)
+                }
+                .map_err(|_| Error::CreateDescriptorSetLayout)?;
+
+                let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo {
+                    set_layout_count: 1,
+                    p_set_layouts: &descriptor_set_layout,
+                    ..Default::default()
+                };
+
+                let pipeline_layout = unsafe {
+                    logical_device.create_pipeline_layout(&pipeline_layout_create_info, None)
+                }
+                .map_err(|_| Error::Creation)?;
+
+                self.layouts = Some((descriptor_set_layout, pipeline_layout));
+
+                (descriptor_set_layout, pipeline_layout)
+            }
+        };
+
+        if let Some(&pipeline) = self.pipelines.get(&format) {
+            return Ok((pipeline, pipeline_layout));
+        }
+
+        let spirv = downsample_spirv(format).ok_or(Error::UnsupportedMipmapFormat)?;
+
+        let code = ash::util::read_spv(&mut io::Cursor::new(spirv)).map_err(|_| {
+            Error::ShaderCompilationError {
+                message: String::from("Invalid mipmap shader"),
+            }
+        })?;
+
+        let shader_module_create_info = vk::ShaderModuleCreateInfo {
+            code_size: 4 * code.len(),
+            p_code: code.as_ptr(),
+            ..Default::default()
+        };
+
+        let module =
+            unsafe { logical_device.create_shader_module(&shader_module_create_info, None) }
+                .map_err(|_| Error::ShaderCompilationError {
+                    message: String::from("Failed to create mipmap shader module"),
+                })?;
+
+        let name = ffi::CString::new("main").unwrap();
+
+        let compute_pipeline_create_info = vk::ComputePipelineCreateInfo {
+            stage: vk::PipelineShaderStageCreateInfo {
+                stage: vk::ShaderStageFlags::COMPUTE,
+                module,
+                p_name: name.as_ptr(),
+                ..Default::default()
+            },
+            layout: pipeline_layout,
+            ..Default::default()
+        };
+
+        let pipeline = unsafe {
+            logical_device.create_compute_pipelines(
+                vk::PipelineCache::null(),
+                &[compute_pipeline_create_info],
+                None,
+            )
+        };
+
+        unsafe { logical_device.destroy_shader_module(module, None) };
+
+        let pipeline = pipeline.map_err(|_| Error::Creation)?[0];
+
+        self.pipelines.insert(format, pipeline);
+
+        Ok((pipeline, pipeline_layout))
+    }
+
+    ///Creates the views and descriptor sets of an image the first time it is downsampled.
+    fn image_sets(
+        &mut self,
+        logical_device: &ash::Device,
+        handle: Image,
+        image: vk::Image,
+        format: Format,
+        mip_levels: u32,
+        array_layers: u32,
+    ) -> Result<&ImageSets> {
+        if self.images.contains_key(&handle) {
+            return Ok(&self.images[&handle]);
+        }
+
+        let (descriptor_set_layout, _) = self.layouts.ok_or(Error::Creation)?;
+
+        let set_count = (mip_levels - 1) * array_layers;
+
+        let pool_sizes = [vk::DescriptorPoolSize {
+            ty: vk::DescriptorType::STORAGE_IMAGE,
+            descriptor_count: 2 * set_count,
+        }];
+
+        let descriptor_pool_create_info = vk::DescriptorPoolCreateInfo {
+            max_sets: set_count,
+            pool_size_count: pool_sizes.len() as _,
+            p_pool_sizes: pool_sizes.as_ptr(),
+            ..Default::default()
+        };
+
+        let descriptor_pool =
+            unsafe { logical_device.create_descriptor_pool(&descriptor_pool_create_info, None) }
+                .map_err(|_| Error::CreateDescriptorPool)?;
+
+        let mut image_sets = ImageSets {
+            image,
+            descriptor_pool,
+            image_views: vec![],
+            descriptor_sets: vec![],
+        };
+
+        if let Err(error) = Self::fill_image_sets(
+            logical_device,
+            &mut image_sets,
+            descriptor_set_layout,
+            format,
+            mip_levels,
+            array_layers,
+        ) {
+            image_sets.destroy(logical_device);
+
+            return Err(error);
+        }
+
+        Ok(self.images.entry(handle).or_insert(image_sets))
+    }
+
+    fn fill_image_sets(
+        logical_device: &ash::Device,
+        image_sets: &mut ImageSets,
+        descriptor_set_layout: vk::DescriptorSetLayout,
+        format: Format,
+        mip_levels: u32,
+        array_layers: u32,
+    ) -> Result<()> {
+        for level in 0..mip_levels {
+            for layer in 0..array_layers {
+                let image_view_create_info = vk::ImageViewCreateInfo {
+                    image: image_sets.image,
+                    view_type: vk::ImageViewType::TYPE_2D,
+                    format: format.into(),
+                    subresource_range: vk::ImageSubresourceRange {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        base_mip_level: level,
+                        level_count: 1,
+                        base_array_layer: layer,
+                        layer_count: 1,
+                    },
+                    ..Default::default()
+                };
+
+                let image_view =
+                    unsafe { logical_device.create_image_view(&image_view_create_info, None) }
+                        .map_err(|_| Error::Creation)?;
+
+                image_sets.image_views.push(image_view);
+            }
+        }
+
+        let set_layouts = vec![descriptor_set_layout; ((mip_levels - 1) * array_layers) as usize];
+
+        let descriptor_set_allocate_info = vk::DescriptorSetAllocateInfo {
+            descriptor_pool: image_sets.descriptor_pool,
+            descriptor_set_count: set_layouts.len() as _,
+            p_set_layouts: set_layouts.as_ptr(),
+            ..Default::default()
+        };
+
+        image_sets.descriptor_sets =
+            unsafe { logical_device.allocate_descriptor_sets(&descriptor_set_allocate_info) }
+                .map_err(|_| Error::AllocateDescriptorSets)?;
+
+        let view = |level: u32, layer: u32| vk::DescriptorImageInfo {
+            sampler: vk::Sampler::null(),
+            image_view: image_sets.image_views[(level * array_layers + layer) as usize],
+            image_layout: vk::ImageLayout::GENERAL,
+        };
+
+        //Each set reads the level before its destination.
+        let image_infos = (1..mip_levels)
+            .flat_map(|level| (0..array_layers).map(move |layer| (level, layer)))
+            .map(|(level, layer)| [view(level - 1, layer), view(level, layer)])
+            .collect::<Vec<_>>();
+
+        let writes = image_sets
+            .descriptor_sets
+            .iter()
+            .zip(&image_infos)
+            .flat_map(|(&descriptor_set, image_infos)| {
+                [0, 1].map(|binding| vk::WriteDescriptorSet {
+                    dst_set: descriptor_set,
+                    dst_binding: binding,
+                    descriptor_count: 1,
+                    descriptor_type: vk::DescriptorType::STORAGE_IMAGE,
+                    p_image_info: &image_infos[binding as usize],
+                    ..Default::default()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        unsafe { logical_device.update_descriptor_sets(&writes, &[]) };
+
+        Ok(())
+    }
+
+    ///Destroys the views and descriptor sets of an image, once it is destroyed or its handle is reused.
+    pub(crate) fn evict(&mut self, logical_device: &ash::Device, image: Image) {
+        if let Some(image_sets) = self.images.remove(&image) {
+            image_sets.destroy(logical_device);
+        }
+    }
+
+    ///Destroys everything, when the device is dropped.
+    pub(crate) fn destroy(&mut self, logical_device: &ash::Device) {
+        for (_, image_sets) in self.images.drain() {
+            image_sets.destroy(logical_device);
+        }
+
+        unsafe {
+            for (_, pipeline) in self.pipelines.drain() {
+                logical_device.destroy_pipeline(pipeline, None);
+            }
+
+            if let Some((descriptor_set_layout, pipeline_layout)) = self.layouts.take() {
+                logical_device.destroy_pipeline_layout(pipeline_layout, None);
+                logical_device.destroy_descriptor_set_layout(descriptor_set_layout, None);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downsample_variants_are_spirv() {
+        for format in [
+            Format::Rgba32Sfloat,
+            Format::Rgba16Sfloat,
+            Format::R32Sfloat,
+            Format::Rgba8Unorm,
+        ] {
+            let spirv = downsample_spirv(format).unwrap();
+
+            let code = ash::util::read_spv(&mut io::Cursor::new(spirv)).unwrap();
+
+            assert_eq!(code[0], 0x07230203);
+        }
+
+        assert!(downsample_spirv(Format::Bgra8Unorm).is_none());
+    }
+}
@@ -224,10 +224,39 @@ impl PipelineCompiler {
     pub fn create_graphics_pipeline<'a>(&'a self, info: GraphicsPipelineInfo) -> Result<Pipeline> {
         let PipelineCompilerInner { device, .. } = &*self.inner;
 
-        let DeviceInner { logical_device, .. } = &**device;
+        let DeviceInner {
+            context,
+            physical_device,
+            logical_device,
+            ..
+        } = &**device;
         #[cfg(all(feature = "bindless"))]
         let DeviceInner { bindless, .. } = &**device;
 
+        let limits = unsafe {
+            context
+                .instance
+                .get_physical_device_properties(*physical_device)
+                .limits
+        };
+
+        let rasterization_samples = vk::SampleCountFlags::from(info.samples);
+
+        let supported_samples = match (info.color.is_empty(), info.depth.is_some()) {
+            (false, false) => limits.framebuffer_color_sample_counts,
+            (true, true) => limits.framebuffer_depth_sample_counts,
+            (false, true) => {
+                limits.framebuffer_color_sample_counts & limits.framebuffer_depth_sample_counts
+            }
+            (true, false) => limits.framebuffer_no_attachments_sample_counts,
+        };
+
+        if rasterization_samples.as_raw().count_ones() != 1
+            || !supported_samples.contains(rasterization_samples)
+        {
+            Err(Error::UnsupportedSampleCount)?
+        }
+
         let shader_data = info
             .shaders
             .iter()
@@ -312,6 +341,11 @@ impl PipelineCompiler {
 
         let rasterization_state = info.raster.into();
 
+        let multisample_state = vk::PipelineMultisampleStateCreateInfo {
+            rasterization_samples,
+            ..Default::default()
+        };
+
         let depth_and_stencil = OptionalDepthStencil(info.depth, info.stencil);
 
         let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo {
@@ -398,6 +432,18 @@ impl PipelineCompiler {
                             ty: vk::DescriptorType::STORAGE_IMAGE,
                             descriptor_count: MAX_FRAMES_IN_FLIGHT as _,
                         },
+                        Binding::SampledImage => vk::DescriptorPoolSize {
+                            ty: vk::DescriptorType::SAMPLED_IMAGE,
+                            descriptor_count: MAX_FRAMES_IN_FLIGHT as _,
+                        },
+                        Binding::Sampler => vk::DescriptorPoolSize {
+                            ty: vk::DescriptorType::SAMPLER,
+                            descriptor_count: MAX_FRAMES_IN_FLIGHT as _,
+                        },
+                        Binding::CombinedImageSampler => vk::DescriptorPoolSize {
+                            ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                            descriptor_count: MAX_FRAMES_IN_FLIGHT as _,
+                        },
                     });
                 }
                 s
@@ -440,6 +486,11 @@ impl PipelineCompiler {
                         descriptor_type: match binding {
                             Binding::Buffer => vk::DescriptorType::STORAGE_BUFFER,
                             Binding::Image => vk::DescriptorType::STORAGE_IMAGE,
+                            Binding::SampledImage => vk::DescriptorType::SAMPLED_IMAGE,
+                            Binding::Sampler => vk::DescriptorType::SAMPLER,
+                            Binding::CombinedImageSampler => {
+                                vk::DescriptorType::COMBINED_IMAGE_SAMPLER
+                            }
                         },
                         descriptor_count: 1,
                         stage_flags: ShaderStageFlags::ALL,
@@ -526,7 +577,7 @@ impl PipelineCompiler {
                 BindingState::Binding(_) => ptr::null_mut(),
                 #[cfg(all(feature = "bindless"))]
                 BindingState::Bindless => {
-                    &mut pipeline_rendering_create_info as *mut _ as *mut _;
+                    &mut pipeline_rendering_create_info as *mut _ as *mut _
                 }
             };
 
@@ -540,6 +591,8 @@ impl PipelineCompiler {
 
             let p_rasterization_state = &rasterization_state;
 
+            let p_multisample_state = &multisample_state;
+
             let p_depth_stencil_state = &depth_stencil_state;
 
             let p_color_blend_state = &color_blend_state;
@@ -561,6 +614,7 @@ impl PipelineCompiler {
                 p_vertex_input_state,
                 p_input_assembly_state,
                 p_rasterization_state,
+                p_multisample_state,
                 p_depth_stencil_state,
                 p_color_blend_state,
                 p_viewport_state,
@@ -670,6 +724,11 @@ impl PipelineCompiler {
                         descriptor_type: match binding {
                             Binding::Buffer => vk::DescriptorType::STORAGE_BUFFER_DYNAMIC,
                             Binding::Image => vk::DescriptorType::STORAGE_IMAGE,
+                            Binding::SampledImage => vk::DescriptorType::SAMPLED_IMAGE,
+                            Binding::Sampler => vk::DescriptorType::SAMPLER,
+                            Binding::CombinedImageSampler => {
+                                vk::DescriptorType::COMBINED_IMAGE_SAMPLER
+                            }
                         },
                         descriptor_count: MAX_FRAMES_IN_FLIGHT as _,
                         stage_flags: ShaderStageFlags::ALL,
@@ -702,6 +761,18 @@ impl PipelineCompiler {
                 ty: vk::DescriptorType::STORAGE_IMAGE,
                 descriptor_count: 400,
             },
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::SAMPLED_IMAGE,
+                descriptor_count: 400,
+            },
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::SAMPLER,
+                descriptor_count: 200,
+            },
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                descriptor_count: 400,
+            },
         ];
 
         let descriptor_pool_create_info = {
@@ -1235,7 +1306,11 @@ impl From<OptionalDepthStencil> for vk::PipelineDepthStencilStateCreateInfo {
 #[derive(Clone)]
 pub enum Binding {
     Buffer,
+    ///A storage image.
     Image,
+    SampledImage,
+    Sampler,
+    CombinedImageSampler,
 }
 
 #[derive(Clone)]
@@ -1255,6 +1330,8 @@ pub struct GraphicsPipelineInfo {
     pub push_constant_size: usize,
     pub render_pass: Option<RenderPass>,
     pub binding: BindingState,
+    ///Has to match the sample count of the attachments rendered to.
+    pub samples: SampleCount,
     pub debug_name: String,
 }
 
@@ -1269,6 +1346,7 @@ impl Default for GraphicsPipelineInfo {
             push_constant_size: 128,
             render_pass: None,
             binding: BindingState::Binding(vec![]),
+            samples: SampleCount::TYPE_1,
             debug_name: String::from("Pipeline"),
         }
     }
@@ -1311,6 +1389,7 @@ bitflags! {
         const HOST = 0x00000200;
         const ALL_GRAPHICS = 0x00000400;
         const ALL_COMMANDS = 0x00000800;
+        const DRAW_INDIRECT = 0x00001000;
     }
 }
 
@@ -1370,6 +1449,10 @@ impl From<PipelineStage> for vk::PipelineStageFlags {
             result |= vk::PipelineStageFlags::ALL_COMMANDS;
         }
 
+        if stage.contains(PipelineStage::DRAW_INDIRECT) {
+            result |= vk::PipelineStageFlags::DRAW_INDIRECT;
+        }
+
         result
     }
 }
@@ -0,0 +1,130 @@
+use crate::prelude::*;
+
+use ash::vk;
+
+#[derive(Clone, Copy, Default, Hash, PartialEq, Eq, Debug)]
+pub enum Filter {
+    Nearest,
+    #[default]
+    Linear,
+}
+
+impl From<Filter> for vk::Filter {
+    fn from(filter: Filter) -> Self {
+        match filter {
+            Filter::Nearest => Self::NEAREST,
+            Filter::Linear => Self::LINEAR,
+        }
+    }
+}
+
+impl From<Filter> for vk::SamplerMipmapMode {
+    fn from(filter: Filter) -> Self {
+        match filter {
+            Filter::Nearest => Self::NEAREST,
+            Filter::Linear => Self::LINEAR,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Hash, PartialEq, Eq, Debug)]
+pub enum AddressMode {
+    #[default]
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+    ///Reads outside the image return `SamplerInfo::border_color`.
+    ClampToBorder,
+}
+
+impl From<AddressMode> for vk::SamplerAddressMode {
+    fn from(mode: AddressMode) -> Self {
+        match mode {
+            AddressMode::Repeat => Self::REPEAT,
+            AddressMode::MirroredRepeat => Self::MIRRORED_REPEAT,
+            AddressMode::ClampToEdge => Self::CLAMP_TO_EDGE,
+            AddressMode::ClampToBorder => Self::CLAMP_TO_BORDER,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Hash, PartialEq, Eq, Debug)]
+pub enum BorderColor {
+    #[default]
+    TransparentBlack,
+    OpaqueBlack,
+    OpaqueWhite,
+}
+
+impl From<BorderColor> for vk::BorderColor {
+    fn from(color: BorderColor) -> Self {
+        match color {
+            BorderColor::TransparentBlack => Self::FLOAT_TRANSPARENT_BLACK,
+            BorderColor::OpaqueBlack => Self::FLOAT_OPAQUE_BLACK,
+            BorderColor::OpaqueWhite => Self::FLOAT_OPAQUE_WHITE,
+        }
+    }
+}
+
+pub struct SamplerInfo<'a> {
+    pub mag_filter: Filter,
+    pub min_filter: Filter,
+    ///How samples between mip levels are blended.
+    pub mipmap_filter: Filter,
+    ///Addressing of the u, v and w coordinates.
+    pub address_mode: [AddressMode; 3],
+    pub mip_lod_bias: f32,
+    pub min_lod: f32,
+    ///`SamplerInfo::LOD_CLAMP_NONE` uses every mip level.
+    pub max_lod: f32,
+    ///The maximum anisotropy, clamped to the device limit.
+    ///Needs `Features::sampler_anisotropy` when set.
+    pub anisotropy: Option<f32>,
+    ///Compares samples against a reference instead, for shadow maps.
+    pub compare: Option<CompareOp>,
+    pub border_color: BorderColor,
+    pub debug_name: &'a str,
+}
+
+impl SamplerInfo<'_> {
+    pub const LOD_CLAMP_NONE: f32 = vk::LOD_CLAMP_NONE;
+}
+
+impl Default for SamplerInfo<'_> {
+    fn default() -> Self {
+        Self {
+            mag_filter: Default::default(),
+            min_filter: Default::default(),
+            mipmap_filter: Default::default(),
+            address_mode: Default::default(),
+            mip_lod_bias: 0.0,
+            min_lod: 0.0,
+            max_lod: Self::LOD_CLAMP_NONE,
+            anisotropy: None,
+            compare: None,
+            border_color: Default::default(),
+            debug_name: "",
+        }
+    }
+}
+
+pub(crate) struct InternalSampler {
+    pub(crate) sampler: vk::Sampler,
+}
+
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
+#[repr(transparent)]
+///A sampler handle, from `Device::create_sampler`.
+pub struct Sampler(pub(crate) u32);
+
+impl From<Sampler> for u32 {
+    fn from(handle: Sampler) -> Self {
+        handle.0
+    }
+}
+
+impl From<u32> for Sampler {
+    fn from(handle: u32) -> Self {
+        Self(handle)
+    }
+}
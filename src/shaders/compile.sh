@@ -0,0 +1,10 @@
+#!/bin/sh
+#Compiles downsample.comp once per storage format, run after changing it.
+#Needs glslangValidator from the Vulkan SDK or glslang.
+set -e
+
+cd "$(dirname "$0")"
+
+for format in rgba32f rgba16f r32f rgba8; do
+    glslangValidator -V --target-env vulkan1.0 -DFORMAT=$format downsample.comp -o downsample_$format.spv
+done
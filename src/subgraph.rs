@@ -0,0 +1,137 @@
+use crate::prelude::*;
+
+use std::ops;
+use std::sync::Arc;
+
+///A reusable part of a render graph, such as bloom, SSAO or a shadow pass.
+///It receives the resources it works on as typed inputs and hands the ones it produces back as outputs,
+///so it can be inserted into any render graph with `RenderGraphBuilder::insert`.
+pub trait SubGraph<T> {
+    ///The ports the sub-graph reads from, for example the image to apply bloom to.
+    type Input;
+    ///The ports the sub-graph produces, which can be wired into later tasks or sub-graphs.
+    type Output;
+
+    ///Adds the tasks of this sub-graph to the builder.
+    fn build(self, builder: &mut RenderGraphBuilder<'_, T>, input: Self::Input) -> Self::Output;
+}
+
+///A handle to a resource that is resolved every frame, passed between sub-graphs.
+///Unlike a closure over `T`, a port does not need to know where in `T` the resource lives.
+pub struct Port<T, H> {
+    resolve: Arc<dyn ops::Fn(&mut T) -> H + Send + Sync>,
+}
+
+impl<T, H> Clone for Port<T, H> {
+    fn clone(&self) -> Self {
+        Self {
+            resolve: Arc::clone(&self.resolve),
+        }
+    }
+}
+
+impl<T, H: Copy + Send + Sync + 'static> Port<T, H> {
+    ///A port that resolves the handle from `T` every frame, such as the current swapchain image.
+    pub fn new(resolve: impl ops::Fn(&mut T) -> H + Send + Sync + 'static) -> Self {
+        Self {
+            resolve: Arc::new(resolve),
+        }
+    }
+
+    ///A port to a resource that never changes.
+    pub fn constant(handle: H) -> Self {
+        Self::new(move |_| handle)
+    }
+
+    pub fn resolve(&self, home: &mut T) -> H {
+        (self.resolve)(home)
+    }
+}
+
+impl<T: 'static> Port<T, Buffer> {
+    ///Declares an access to the whole buffer behind this port.
+    pub fn buffer<'id, A: BufferDeclaration>(
+        &self,
+        resources: &mut Resources<'_, 'id, T>,
+        access: A,
+    ) -> BufferToken<'id, A::Kind> {
+        let port = self.clone();
+
+        resources.buffer(move |home| port.resolve(home), access)
+    }
+
+    ///Declares an access to a range of the buffer behind this port.
+    pub fn buffer_range<'id, A: BufferDeclaration>(
+        &self,
+        resources: &mut Resources<'_, 'id, T>,
+        access: A,
+        range: BufferRange,
+    ) -> BufferToken<'id, A::Kind> {
+        let port = self.clone();
+
+        resources.buffer_range(move |home| port.resolve(home), access, range)
+    }
+}
+
+impl<T: 'static> Port<T, Image> {
+    ///Declares an access to the whole image behind this port.
+    pub fn image<'id, A: ImageDeclaration>(
+        &self,
+        resources: &mut Resources<'_, 'id, T>,
+        access: A,
+        aspect: ImageAspect,
+    ) -> ImageToken<'id, A::Kind> {
+        let port = self.clone();
+
+        resources.image(move |home| port.resolve(home), access, aspect)
+    }
+
+    ///Declares an access to some mip levels and array layers of the image behind this port.
+    pub fn image_range<'id, A: ImageDeclaration>(
+        &self,
+        resources: &mut Resources<'_, 'id, T>,
+        access: A,
+        aspect: ImageAspect,
+        range: ImageRange,
+    ) -> ImageToken<'id, A::Kind> {
+        let port = self.clone();
+
+        resources.image_range(move |home| port.resolve(home), access, aspect, range)
+    }
+}
+
+impl<'a, T> RenderGraphBuilder<'a, T> {
+    ///Adds the tasks of a sub-graph, wiring `input` into it and returning what it produces.
+    ///The names of its tasks are prefixed with `name`, as in `bloom/downsample`.
+    pub fn insert<S: SubGraph<T>>(
+        &mut self,
+        name: &str,
+        sub_graph: S,
+        input: S::Input,
+    ) -> S::Output {
+        self.scope.push(name.to_owned());
+
+        let output = sub_graph.build(self, input);
+
+        self.scope.pop();
+
+        output
+    }
+
+    ///The device the graph is built on, for sub-graphs that create their own resources.
+    pub fn device(&self) -> Device {
+        Device {
+            inner: Arc::clone(&self.device),
+        }
+    }
+
+    ///Prefixes a task name with the names of the sub-graphs it is inserted through.
+    pub(crate) fn scoped_name(&self, debug_name: &str) -> String {
+        self.scope
+            .iter()
+            .map(String::as_str)
+            .chain([debug_name])
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
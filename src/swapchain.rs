@@ -1,3 +1,4 @@
+use crate::capture::Readback;
 use crate::prelude::*;
 
 use std::ops;
@@ -5,8 +6,28 @@ use std::ops;
 use ash::extensions::{ext, khr};
 use ash::{vk, Entry, Instance};
 
-pub fn default_surface_format_selector(format: Format) -> usize {
+use raw_window_handle::{RawDisplayHandle, RawWindowHandle};
+
+#[cfg(target_os = "android")]
+use raw_window_handle::{AndroidDisplayHandle, AndroidNdkWindowHandle};
+#[cfg(target_os = "windows")]
+use raw_window_handle::{Win32WindowHandle, WindowsDisplayHandle};
+#[cfg(target_os = "linux")]
+use raw_window_handle::{XlibDisplayHandle, XlibWindowHandle};
+
+///Ranks 8 bit formats in the standard sRGB color space, and nothing else.
+pub fn default_surface_format_selector(surface_format: SurfaceFormat) -> usize {
     use Format::*;
+
+    let SurfaceFormat {
+        format,
+        color_space,
+    } = surface_format;
+
+    if color_space != ColorSpace::SrgbNonlinear {
+        return 0;
+    }
+
     match format {
         Rgba8Srgb => 90,
         Rgba8Unorm => 80,
@@ -16,7 +37,108 @@ pub fn default_surface_format_selector(format: Format) -> usize {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+///Prefers HDR10, then scRGB, then falls back to the default selector.
+///The color space actually picked can be checked with `Device::swapchain_surface_format`.
+pub fn hdr_surface_format_selector(surface_format: SurfaceFormat) -> usize {
+    use ColorSpace::*;
+    use Format::*;
+
+    let SurfaceFormat {
+        format,
+        color_space,
+    } = surface_format;
+
+    match (color_space, format) {
+        (Hdr10St2084, A2b10g10r10Unorm) => 200,
+        (ExtendedSrgbLinear, Rgba16Sfloat) => 150,
+        _ => default_surface_format_selector(surface_format),
+    }
+}
+
+///How the presentation engine interprets the values written to swapchain images.
+///Anything other than `SrgbNonlinear` requires the `VK_EXT_swapchain_colorspace` extension,
+///which is enabled whenever it is available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    SrgbNonlinear,
+    DisplayP3Nonlinear,
+    ///scRGB, usually paired with `Rgba16Sfloat`.
+    ExtendedSrgbLinear,
+    ExtendedSrgbNonlinear,
+    Bt2020Linear,
+    ///HDR10, usually paired with `A2b10g10r10Unorm`.
+    Hdr10St2084,
+    Hdr10Hlg,
+    PassThrough,
+}
+
+impl TryFrom<vk::ColorSpaceKHR> for ColorSpace {
+    type Error = ();
+
+    fn try_from(color_space: vk::ColorSpaceKHR) -> std::result::Result<Self, Self::Error> {
+        use ColorSpace::*;
+
+        Ok(match color_space {
+            vk::ColorSpaceKHR::SRGB_NONLINEAR => SrgbNonlinear,
+            vk::ColorSpaceKHR::DISPLAY_P3_NONLINEAR_EXT => DisplayP3Nonlinear,
+            vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT => ExtendedSrgbLinear,
+            vk::ColorSpaceKHR::EXTENDED_SRGB_NONLINEAR_EXT => ExtendedSrgbNonlinear,
+            vk::ColorSpaceKHR::BT2020_LINEAR_EXT => Bt2020Linear,
+            vk::ColorSpaceKHR::HDR10_ST2084_EXT => Hdr10St2084,
+            vk::ColorSpaceKHR::HDR10_HLG_EXT => Hdr10Hlg,
+            vk::ColorSpaceKHR::PASS_THROUGH_EXT => PassThrough,
+            _ => Err(())?,
+        })
+    }
+}
+
+impl From<ColorSpace> for vk::ColorSpaceKHR {
+    fn from(color_space: ColorSpace) -> Self {
+        use ColorSpace::*;
+
+        match color_space {
+            SrgbNonlinear => Self::SRGB_NONLINEAR,
+            DisplayP3Nonlinear => Self::DISPLAY_P3_NONLINEAR_EXT,
+            ExtendedSrgbLinear => Self::EXTENDED_SRGB_LINEAR_EXT,
+            ExtendedSrgbNonlinear => Self::EXTENDED_SRGB_NONLINEAR_EXT,
+            Bt2020Linear => Self::BT2020_LINEAR_EXT,
+            Hdr10St2084 => Self::HDR10_ST2084_EXT,
+            Hdr10Hlg => Self::HDR10_HLG_EXT,
+            PassThrough => Self::PASS_THROUGH_EXT,
+        }
+    }
+}
+
+///A format and color space pair a surface can present.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SurfaceFormat {
+    pub format: Format,
+    pub color_space: ColorSpace,
+}
+
+impl TryFrom<vk::SurfaceFormatKHR> for SurfaceFormat {
+    type Error = ();
+
+    fn try_from(surface_format: vk::SurfaceFormatKHR) -> std::result::Result<Self, Self::Error> {
+        Ok(Self {
+            format: surface_format.format.try_into()?,
+            color_space: surface_format.color_space.try_into()?,
+        })
+    }
+}
+
+impl From<SurfaceFormat> for vk::SurfaceFormatKHR {
+    fn from(surface_format: SurfaceFormat) -> Self {
+        Self {
+            format: surface_format.format.into(),
+            color_space: surface_format.color_space.into(),
+        }
+    }
+}
+
+///When the requested mode is not supported, `create_swapchain` falls back to the closest supported one,
+///ending with `DoubleBufferWaitForVBlank`, which every surface supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PresentMode {
     DoNotWaitForVBlank,
     TripleBufferWaitForVBlank,
@@ -24,42 +146,144 @@ pub enum PresentMode {
     DoubleBufferWaitForVBlankRelaxed,
 }
 
+impl PresentMode {
+    ///The modes to try in order, starting with this one.
+    pub(crate) fn fallbacks(self) -> &'static [PresentMode] {
+        use PresentMode::*;
+
+        match self {
+            DoNotWaitForVBlank => &[
+                DoNotWaitForVBlank,
+                TripleBufferWaitForVBlank,
+                DoubleBufferWaitForVBlank,
+            ],
+            TripleBufferWaitForVBlank => &[TripleBufferWaitForVBlank, DoubleBufferWaitForVBlank],
+            DoubleBufferWaitForVBlank => &[DoubleBufferWaitForVBlank],
+            DoubleBufferWaitForVBlankRelaxed => {
+                &[DoubleBufferWaitForVBlankRelaxed, DoubleBufferWaitForVBlank]
+            }
+        }
+    }
+}
+
+impl TryFrom<vk::PresentModeKHR> for PresentMode {
+    type Error = ();
+
+    fn try_from(present_mode: vk::PresentModeKHR) -> std::result::Result<Self, Self::Error> {
+        use PresentMode::*;
+
+        Ok(match present_mode {
+            vk::PresentModeKHR::IMMEDIATE => DoNotWaitForVBlank,
+            vk::PresentModeKHR::MAILBOX => TripleBufferWaitForVBlank,
+            vk::PresentModeKHR::FIFO => DoubleBufferWaitForVBlank,
+            vk::PresentModeKHR::FIFO_RELAXED => DoubleBufferWaitForVBlankRelaxed,
+            _ => Err(())?,
+        })
+    }
+}
+
 impl From<PresentMode> for vk::PresentModeKHR {
     fn from(present_mode: PresentMode) -> Self {
         use PresentMode::*;
 
         match present_mode {
             DoNotWaitForVBlank => Self::IMMEDIATE,
-            TripleBufferWaitForVBlank | DoubleBufferWaitForVBlank => Self::FIFO,
+            TripleBufferWaitForVBlank => Self::MAILBOX,
+            DoubleBufferWaitForVBlank => Self::FIFO,
             DoubleBufferWaitForVBlankRelaxed => Self::FIFO_RELAXED,
         }
     }
 }
 
+///What a window supports, from `Device::surface_capabilities` or `Device::swapchain_surface_capabilities`.
+#[derive(Debug, Clone)]
+pub struct SurfaceCapabilities {
+    pub formats: Vec<SurfaceFormat>,
+    pub present_modes: Vec<PresentMode>,
+    pub min_image_count: u32,
+    ///`None` when there is no limit.
+    pub max_image_count: Option<u32>,
+    ///`None` when the size of the surface is decided by the swapchain.
+    pub current_extent: Option<(u32, u32)>,
+    pub min_extent: (u32, u32),
+    pub max_extent: (u32, u32),
+}
+
 pub struct SwapchainInfo<'a> {
+    ///The window to present to, using the raw_window_handle crate.
+    ///Compatible with winit or your favorite windowing system.
+    ///Ignored when the context is headless.
+    pub display: RawDisplayHandle,
+    pub window: RawWindowHandle,
     pub present_mode: PresentMode,
+    ///The usages of the swapchain images, `ImageUsage::COLOR` to render to them.
+    ///Checked against the usages the surface supports.
     pub image_usage: ImageUsage,
+    ///Allows `Device::capture_swapchain`. Every presented image is copied back, which costs a copy per frame.
+    ///The surface format has to be 8 bit RGBA or BGRA.
+    pub allow_capture: bool,
     pub width: u32,
     pub height: u32,
-    pub surface_format_selector: &'a dyn ops::Fn(Format) -> usize,
-    pub old_swapchain: Option<Swapchain>,
+    ///Scores each supported format and color space. The highest score wins.
+    pub surface_format_selector: &'a dyn ops::Fn(SurfaceFormat) -> usize,
     pub debug_name: &'a str,
 }
 
 impl Default for SwapchainInfo<'_> {
     fn default() -> Self {
         Self {
+            #[cfg(target_os = "windows")]
+            display: RawDisplayHandle::Windows(WindowsDisplayHandle::empty()),
+            #[cfg(target_os = "windows")]
+            window: RawWindowHandle::Win32(Win32WindowHandle::empty()),
+            #[cfg(target_os = "linux")]
+            display: RawDisplayHandle::Xlib(XlibDisplayHandle::empty()),
+            #[cfg(target_os = "linux")]
+            window: RawWindowHandle::Xlib(XlibWindowHandle::empty()),
+            #[cfg(target_os = "android")]
+            display: RawDisplayHandle::Android(AndroidDisplayHandle::empty()),
+            #[cfg(target_os = "android")]
+            window: RawWindowHandle::AndroidNdk(AndroidNdkWindowHandle::empty()),
             present_mode: PresentMode::DoNotWaitForVBlank,
-            image_usage: ImageUsage::TRANSFER_DST,
+            image_usage: ImageUsage::COLOR | ImageUsage::TRANSFER_DST,
+            allow_capture: false,
             width: 960,
             height: 540,
             surface_format_selector: &default_surface_format_selector,
-            old_swapchain: None,
             debug_name: "Swapchain",
         }
     }
 }
 
+///A swapchain without a surface, for automated captures and turntable renders.
+///It owns its images and acts like any other swapchain for `acquire_next_image` and `Present`,
+///but every presented frame is copied back and written to disk instead of shown.
+pub struct VirtualSwapchainInfo<'a> {
+    ///Must be an 8 bit RGBA or BGRA format.
+    pub format: Format,
+    pub image_count: usize,
+    ///The usages of the images, `ImageUsage::COLOR` to render to them.
+    pub image_usage: ImageUsage,
+    pub width: u32,
+    pub height: u32,
+    pub capture: CaptureInfo<'a>,
+    pub debug_name: &'a str,
+}
+
+impl Default for VirtualSwapchainInfo<'_> {
+    fn default() -> Self {
+        Self {
+            format: Format::Rgba8Srgb,
+            image_count: 3,
+            image_usage: ImageUsage::COLOR | ImageUsage::TRANSFER_DST,
+            width: 960,
+            height: 540,
+            capture: Default::default(),
+            debug_name: "Virtual Swapchain",
+        }
+    }
+}
+
 pub struct InternalSwapchain {
     pub(crate) format: Format,
     pub(crate) loader: khr::Swapchain,
@@ -68,6 +292,23 @@ pub struct InternalSwapchain {
     pub(crate) last_acquisition_index: Option<u32>,
     pub(crate) current_frame: usize,
     pub(crate) allow_acquisition: bool,
+    ///Owned by the swapchain, and destroyed with it.
+    pub(crate) surface: vk::SurfaceKHR,
+    pub(crate) surface_format: vk::SurfaceFormatKHR,
+    pub(crate) present_mode: vk::PresentModeKHR,
+    ///The size the swapchain was asked for, used when the surface leaves the size up to us.
+    pub(crate) requested_extent: vk::Extent2D,
+    pub(crate) extent: vk::Extent2D,
+    ///Set when the swapchain no longer matches its surface, so it is recreated before the next acquisition.
+    pub(crate) recreate: bool,
+    ///Incremented every time the swapchain is recreated.
+    pub(crate) generation: usize,
+    pub(crate) debug_name: String,
+    pub(crate) image_usage: ImageUsage,
+    ///Set for swapchains that can be captured, including every virtual swapchain.
+    pub(crate) readback: Option<Readback>,
+    ///Set for virtual swapchains, which have no surface and write their frames to disk.
+    pub(crate) capture: Option<Capture>,
 }
 
 #[derive(Clone, Copy)]
@@ -1,5 +1,8 @@
+use crate::commands::QueueTransfer;
+#[cfg(all(feature = "bindless"))]
 use crate::context::{
-    DEVICE_ADDRESS_BUFFER_BINDING, SPECIAL_BUFFER_BINDING, SPECIAL_IMAGE_BINDING,
+    DEVICE_ADDRESS_BUFFER_BINDING, SAMPLED_IMAGE_BINDING, SAMPLER_BINDING, SPECIAL_BUFFER_BINDING,
+    SPECIAL_IMAGE_BINDING,
 };
 use crate::device::{DeviceInner, MAX_FRAMES_IN_FLIGHT};
 use crate::prelude::*;
@@ -7,34 +10,48 @@ use crate::prelude::*;
 use std::borrow::{Borrow, BorrowMut};
 use std::collections::HashMap;
 use std::marker::PhantomData;
+use std::mem;
 use std::ops;
+use std::panic;
 use std::slice;
 use std::sync::{Arc, Mutex};
+use std::thread;
 use std::time;
-use std::{mem, ptr};
 
 use ash::vk;
 
 use bitflags::bitflags;
 
+///Presents every swapchain of the render graph that an image was acquired from this frame, in one call.
 pub struct Present {
     pub wait_semaphore: BinarySemaphore,
 }
 
 pub struct Submit {
-    pub wait_semaphore: Option<BinarySemaphore>,
+    ///Waited on before any color attachment is written, usually one per acquired swapchain image.
+    pub wait_semaphores: Vec<BinarySemaphore>,
     pub signal_semaphore: Option<BinarySemaphore>,
 }
 
 pub struct RenderGraphInfo<'a> {
-    pub swapchain: Swapchain,
+    ///The swapchains the render graph acquires from and presents to.
+    ///The first one paces the frames in flight, so there must be at least one.
+    pub swapchains: Vec<Swapchain>,
+    ///The number of threads that record tasks added with `add_parallel`.
+    ///With one thread, they are recorded in order like any other task.
+    pub recording_threads: usize,
+    ///Checks every command against the resource accesses its task declared, and every binding against the declared resources.
+    ///A task that does something it did not declare fails with `Error::UndeclaredAccess`. On by default in debug builds.
+    pub validate: bool,
     pub debug_name: &'a str,
 }
 
 impl Default for RenderGraphInfo<'_> {
     fn default() -> Self {
         Self {
-            swapchain: u32::MAX.into(),
+            swapchains: vec![],
+            recording_threads: 1,
+            validate: cfg!(debug_assertions),
             debug_name: "RenderGraphBuilder",
         }
     }
@@ -42,51 +59,152 @@ impl Default for RenderGraphInfo<'_> {
 
 pub struct RenderGraphBuilder<'a, T> {
     pub(crate) device: Arc<DeviceInner>,
-    pub(crate) swapchain: Swapchain,
+    pub(crate) swapchains: Vec<Swapchain>,
     pub(crate) nodes: Vec<Node<'a, T>>,
+    pub(crate) recording_threads: usize,
+    pub(crate) record_batch: Option<RecordBatch<'a, T>>,
+    ///The names of the sub-graphs currently being inserted.
+    pub(crate) scope: Vec<String>,
+    pub(crate) on_resize: Vec<OnResize<'a, T>>,
+    pub(crate) validate: bool,
     pub(crate) debug_name: String,
 }
 
 impl<'a, T> RenderGraphBuilder<'a, T> {
-    pub fn add<'b: 'a, F: ops::FnMut(&mut T, &mut Commands) -> Result<()> + Send + Sync + 'b>(
+    ///Declares the resources of a task and adds the task it returns.
+    ///The tokens handed out by `resources` only fit the `Commands` of that task.
+    pub fn add<'b>(
         &mut self,
-        task: Task<T, F>,
+        declare: impl for<'id> FnOnce(&mut Resources<'a, 'id, T>) -> Task<'b, 'id, T>,
     ) {
-        let Task { task, resources } = task;
+        let mut resources = Resources::new();
+
+        let Task {
+            task,
+            queue,
+            debug_name,
+        } = declare(&mut resources);
 
         self.nodes.push(Node {
-            resources,
-            task: Box::new(task),
+            resources: resources.resources,
+            task: NodeTask::Serial(task),
+            queue,
+            debug_name: self.scoped_name(debug_name),
+        });
+    }
+
+    ///Adds a task that only needs shared access to `T`.
+    ///Consecutive parallel tasks are recorded into secondary command buffers on `recording_threads` threads,
+    ///and executed in the order they were added.
+    ///Each call on `Commands` briefly locks the resources of the device, so the threads only record in parallel
+    ///between those calls. Tasks that spend their time outside of commands, such as on culling, gain the most.
+    pub fn add_parallel<'b>(
+        &mut self,
+        declare: impl for<'id> FnOnce(&mut Resources<'a, 'id, T>) -> ParallelTask<'b, 'id, T>,
+    ) where
+        T: Sync,
+    {
+        let mut resources = Resources::new();
+
+        let ParallelTask {
+            task,
+            queue,
+            debug_name,
+        } = declare(&mut resources);
+
+        self.record_batch = Some(record_batch::<T>);
+
+        self.nodes.push(Node {
+            resources: resources.resources,
+            task: NodeTask::Parallel(task),
+            queue,
+            debug_name: self.scoped_name(debug_name),
         });
     }
 
+    ///Called at the start of a frame after one of the swapchains was recreated with a new size,
+    ///so that size-dependent resources such as depth buffers can be recreated to match.
+    pub fn on_resize(
+        &mut self,
+        on_resize: impl ops::FnMut(&mut T, Swapchain, (u32, u32)) + Send + Sync + 'a,
+    ) {
+        self.on_resize.push(Box::new(on_resize));
+    }
+
     pub fn complete(self) -> Result<RenderGraph<'a, T>> {
         let RenderGraphBuilder {
             device,
             nodes,
-            swapchain,
+            swapchains,
+            recording_threads,
+            record_batch,
+            on_resize,
+            validate,
+            debug_name,
             ..
         } = self;
 
         let DeviceInner {
             logical_device,
-            command_pool,
-            resources,
+            command_pools,
             ..
         } = &*device;
 
-        let resources = resources.lock().unwrap();
+        //Consecutive tasks on the same queue family share a submission.
+        let mut segments = Vec::<Segment>::new();
 
-        let command_buffer_allocate_info = vk::CommandBufferAllocateInfo {
-            command_pool: *command_pool,
-            level: vk::CommandBufferLevel::PRIMARY,
-            command_buffer_count: MAX_FRAMES_IN_FLIGHT as _,
-            ..Default::default()
-        };
+        for (i, node) in nodes.iter().enumerate() {
+            match segments.last_mut() {
+                Some(segment)
+                    if device.queue_family_index(segment.queue)
+                        == device.queue_family_index(node.queue) =>
+                {
+                    segment.nodes.end = i + 1
+                }
+                _ => segments.push(Segment {
+                    queue: node.queue,
+                    nodes: i..i + 1,
+                    command_buffers: vec![],
+                }),
+            }
+        }
 
-        let command_buffers =
-            unsafe { logical_device.allocate_command_buffers(&command_buffer_allocate_info) }
-                .map_err(|_| Error::Creation)?;
+        if segments.is_empty() {
+            segments.push(Segment {
+                queue: Queue::Graphics,
+                nodes: 0..0,
+                command_buffers: vec![],
+            });
+        }
+
+        for segment in &mut segments {
+            let command_buffer_allocate_info = vk::CommandBufferAllocateInfo {
+                command_pool: command_pools[segment.queue as usize],
+                level: vk::CommandBufferLevel::PRIMARY,
+                command_buffer_count: MAX_FRAMES_IN_FLIGHT as _,
+                ..Default::default()
+            };
+
+            segment.command_buffers =
+                unsafe { logical_device.allocate_command_buffers(&command_buffer_allocate_info) }
+                    .map_err(|_| Error::Creation)?;
+        }
+
+        let mut prologues = vec![];
+
+        for &command_pool in command_pools {
+            let command_buffer_allocate_info = vk::CommandBufferAllocateInfo {
+                command_pool,
+                level: vk::CommandBufferLevel::PRIMARY,
+                command_buffer_count: MAX_FRAMES_IN_FLIGHT as _,
+                ..Default::default()
+            };
+
+            prologues.push(
+                unsafe { logical_device.allocate_command_buffers(&command_buffer_allocate_info) }
+                    .map_err(|_| Error::Creation)?,
+            );
+        }
 
         let fence_create_info = vk::FenceCreateInfo {
             flags: vk::FenceCreateFlags::SIGNALED,
@@ -102,18 +220,58 @@ impl<'a, T> RenderGraphBuilder<'a, T> {
             fences.push(fence);
         }
 
+        let mut recorders = vec![];
+
+        for _ in 0..MAX_FRAMES_IN_FLIGHT {
+            let mut frame_recorders = vec![];
+
+            if recording_threads > 1 && record_batch.is_some() {
+                for _ in 0..recording_threads {
+                    frame_recorders.push(Recorder::new(&device)?);
+                }
+            }
+
+            recorders.push(frame_recorders);
+        }
+
+        let semaphores = vec![vec![]; MAX_FRAMES_IN_FLIGHT];
+
+        let generations = {
+            let resources = device.resources.lock().unwrap();
+
+            swapchains
+                .iter()
+                .map(|&swapchain| {
+                    resources
+                        .swapchains
+                        .get(swapchain)
+                        .map(|x| x.generation)
+                        .ok_or(Error::ResourceNotFound)
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+
         let current_instant = time::Instant::now();
 
         Ok(RenderGraph {
             inner: Arc::new(RenderGraphInner {
                 device: device.clone(),
-                command_buffers,
+                segments,
+                prologues,
                 fences,
-                swapchain,
+                swapchains,
+                validate,
+                debug_name,
                 modify: Mutex::new(RenderGraphModify {
                     nodes,
+                    recorders,
+                    record_batch,
+                    semaphores,
+                    on_resize,
+                    generations,
                     current_instant,
                     last_instant: current_instant,
+                    owners: Owners::default(),
                 }),
             }),
         })
@@ -121,7 +279,7 @@ impl<'a, T> RenderGraphBuilder<'a, T> {
 }
 
 pub struct RenderGraph<'a, T> {
-    inner: Arc<RenderGraphInner<'a, T>>,
+    pub(crate) inner: Arc<RenderGraphInner<'a, T>>,
 }
 
 impl<'a, T> Clone for RenderGraph<'a, T> {
@@ -134,9 +292,14 @@ impl<'a, T> Clone for RenderGraph<'a, T> {
 
 pub struct RenderGraphInner<'a, T> {
     pub(crate) device: Arc<DeviceInner>,
-    pub(crate) swapchain: Swapchain,
-    pub(crate) command_buffers: Vec<vk::CommandBuffer>,
+    ///The first swapchain paces the frames in flight.
+    pub(crate) swapchains: Vec<Swapchain>,
+    pub(crate) segments: Vec<Segment>,
+    ///Records the releases of resources an earlier frame left on another queue family, per queue and frame in flight.
+    pub(crate) prologues: Vec<Vec<vk::CommandBuffer>>,
     pub(crate) fences: Vec<vk::Fence>,
+    pub(crate) validate: bool,
+    pub(crate) debug_name: String,
     pub(crate) modify: Mutex<RenderGraphModify<'a, T>>,
 }
 
@@ -144,6 +307,24 @@ pub struct RenderGraphModify<'a, T> {
     pub(crate) current_instant: time::Instant,
     pub(crate) last_instant: time::Instant,
     pub(crate) nodes: Vec<Node<'a, T>>,
+    pub(crate) recorders: Vec<Vec<Recorder>>,
+    pub(crate) record_batch: Option<RecordBatch<'a, T>>,
+    ///Signaled between submissions on different queues, per frame in flight.
+    pub(crate) semaphores: Vec<Vec<vk::Semaphore>>,
+    pub(crate) on_resize: Vec<OnResize<'a, T>>,
+    ///The generation of each swapchain the `on_resize` callbacks last saw.
+    pub(crate) generations: Vec<usize>,
+    pub(crate) owners: Owners,
+}
+
+pub(crate) type OnResize<'a, T> =
+    Box<dyn ops::FnMut(&mut T, Swapchain, (u32, u32)) + Send + Sync + 'a>;
+
+///A run of consecutive nodes on the same queue family, recorded into one command buffer per frame in flight.
+pub(crate) struct Segment {
+    pub(crate) queue: Queue,
+    pub(crate) nodes: ops::Range<usize>,
+    pub(crate) command_buffers: Vec<vk::CommandBuffer>,
 }
 
 impl<T> RenderGraph<'_, T> {
@@ -156,23 +337,27 @@ impl<T> RenderGraph<'_, T> {
 
 impl<T> RenderGraph<'_, T> {
     ///Executes the render graph.
-    pub fn render(&mut self, home: &mut T) {
+    ///Fails when a command buffer cannot be recorded or submitted, usually because the device was lost.
+    pub fn render(&mut self, home: &mut T) -> Result<()> {
         profiling::scope!("RenderGraph", "ev");
 
         let RenderGraphInner {
             device,
-            command_buffers,
+            segments,
+            prologues,
             fences,
             modify,
-            swapchain,
-            ..
+            swapchains,
+            validate,
+            debug_name,
         } = &*self.inner;
 
+        let swapchain = &swapchains[0];
+
         let mut modify = modify.lock().unwrap();
 
         let DeviceInner {
             logical_device,
-            queue_family_indices,
             resources,
             #[cfg(all(feature = "bindless"))]
             bindless,
@@ -182,21 +367,13 @@ impl<T> RenderGraph<'_, T> {
         let mut submit: Option<Submit> = None;
         let mut present: Option<Present> = None;
 
-        let (swapchain_handle, current_frame) = {
-            let resources = resources.lock().unwrap();
-
-            let internal_swapchain = resources.swapchains.get(*swapchain).unwrap();
-
-            let swapchain_handle = internal_swapchain.handle;
-
-            let current_frame = internal_swapchain.current_frame;
-
-            (swapchain_handle, current_frame)
-        };
-
-        let queue_family_index = queue_family_indices[0];
-
-        let queue = unsafe { logical_device.get_device_queue(queue_family_index as _, 0) };
+        let current_frame = resources
+            .lock()
+            .unwrap()
+            .swapchains
+            .get(*swapchain)
+            .unwrap()
+            .current_frame;
 
         {
             profiling::scope!("fence", "ev");
@@ -204,33 +381,68 @@ impl<T> RenderGraph<'_, T> {
                 if let Err(vk::Result::TIMEOUT) =
                     logical_device.wait_for_fences(&[fences[current_frame]], true, 0)
                 {
-                    return;
+                    return Ok(());
                 }
             }
 
             modify.last_instant = modify.current_instant;
             modify.current_instant = time::Instant::now();
 
-            unsafe {
-                logical_device.reset_fences(&[fences[current_frame]]);
+            for recorder in &mut modify.recorders[current_frame] {
+                recorder.reset(device)?;
             }
         }
 
-        unsafe {
-            logical_device
-                .begin_command_buffer(command_buffers[current_frame], &Default::default());
+        for (i, &swapchain) in swapchains.iter().enumerate() {
+            let mut resources = resources.lock().unwrap();
+
+            if resources.swapchains.get(swapchain).unwrap().recreate {
+                device.recreate_swapchain(&mut resources, swapchain)?;
+            }
+
+            let InternalSwapchain {
+                generation, extent, ..
+            } = resources.swapchains.get(swapchain).unwrap();
+
+            let (generation, extent) = (*generation, (extent.width, extent.height));
+
+            drop(resources);
+
+            if generation != modify.generations[i] {
+                modify.generations[i] = generation;
+
+                for on_resize in &mut modify.on_resize {
+                    on_resize(home, swapchain, extent);
+                }
+            }
+        }
+
+        for segment in segments {
+            let command_buffer = segment.command_buffers[current_frame];
+
+            unsafe { logical_device.begin_command_buffer(command_buffer, &Default::default()) }
+                .map_err(|_| Error::SubmitFailed)?;
+
+            device.begin_label(command_buffer, debug_name);
         }
 
         #[cfg(all(feature = "bindless"))]
         {
             profiling::scope!("address book and descriptor set", "ev");
 
+            let crate::device::Bindless {
+                descriptor_set,
+                staging_address_buffer,
+                staging_address_memory,
+                general_address_buffer,
+                ..
+            } = bindless;
+
             let resources = resources.lock().unwrap();
 
             let mut addresses = vec![0u64; DESCRIPTOR_COUNT as usize];
 
             let mut descriptor_buffer_infos = vec![];
-            let mut descriptor_image_infos = vec![];
 
             for i in 0..resources.buffers.count() as usize {
                 if let Some(internal_buffer) = resources.buffers.get((i as u32).into()) {
@@ -258,31 +470,62 @@ impl<T> RenderGraph<'_, T> {
                 }
             }
 
-            for i in 0..resources.images.count() as usize {
-                if let Some(internal_image) = resources.images.get((i as u32).into()) {
-                    if internal_image.get_format().is_depth_or_stencil() {
-                        descriptor_image_infos.push(vk::DescriptorImageInfo {
+            let mut storage_image_infos = vec![];
+            let mut sampled_image_infos = vec![];
+            let mut sampler_infos = vec![];
+
+            for i in 0..resources.images.count() as u32 {
+                let Some(internal_image) = resources.images.get(i.into()) else {
+                    continue;
+                };
+                if let InternalImage::Swapchain { .. } = internal_image {
+                    continue;
+                }
+
+                let usage = internal_image.get_usage();
+
+                if usage.contains(vk::ImageUsageFlags::STORAGE)
+                    && !internal_image.get_format().is_depth_or_stencil()
+                {
+                    storage_image_infos.push((
+                        i,
+                        vk::DescriptorImageInfo {
+                            image_view: internal_image.get_image_view(),
+                            image_layout: vk::ImageLayout::GENERAL,
                             ..Default::default()
-                        });
-                        continue;
-                    }
-                    if let InternalImage::Swapchain { .. } = internal_image {
-                        descriptor_image_infos.push(vk::DescriptorImageInfo {
+                        },
+                    ));
+                }
+
+                //Sampled image descriptors view a single aspect, and views of depth stencil images have both.
+                let depth_stencil = vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL;
+
+                if usage.contains(vk::ImageUsageFlags::SAMPLED)
+                    && internal_image.get_format().entire_aspect() != depth_stencil
+                {
+                    sampled_image_infos.push((
+                        i,
+                        vk::DescriptorImageInfo {
+                            image_view: internal_image.get_image_view(),
+                            image_layout: vk::ImageLayout::READ_ONLY_OPTIMAL,
                             ..Default::default()
-                        });
-                        continue;
-                    }
+                        },
+                    ));
+                }
+            }
 
-                    descriptor_image_infos.push(vk::DescriptorImageInfo {
-                        image_view: internal_image.get_image_view(),
-                        image_layout: vk::ImageLayout::GENERAL,
-                        ..Default::default()
-                    });
-                } else {
-                    descriptor_image_infos.push(vk::DescriptorImageInfo {
+            for i in 0..resources.samplers.count() as u32 {
+                let Some(internal_sampler) = resources.samplers.get(i.into()) else {
+                    continue;
+                };
+
+                sampler_infos.push((
+                    i,
+                    vk::DescriptorImageInfo {
+                        sampler: internal_sampler.sampler,
                         ..Default::default()
-                    });
-                }
+                    },
+                ));
             }
 
             drop(resources);
@@ -314,7 +557,7 @@ impl<T> RenderGraph<'_, T> {
 
             unsafe {
                 logical_device.cmd_copy_buffer(
-                    command_buffers[current_frame],
+                    segments[0].command_buffers[current_frame],
                     *staging_address_buffer,
                     *general_address_buffer,
                     &regions,
@@ -357,123 +600,270 @@ impl<T> RenderGraph<'_, T> {
                 });
             }
 
-            if descriptor_image_infos.len() != 0 {
-                write_descriptor_sets.push({
-                    let p_image_info = descriptor_image_infos.as_ptr();
-
-                    vk::WriteDescriptorSet {
+            //Empty slots are left unwritten, the bindings are partially bound.
+            for (dst_binding, descriptor_type, image_infos) in [
+                (
+                    SPECIAL_IMAGE_BINDING,
+                    vk::DescriptorType::STORAGE_IMAGE,
+                    &storage_image_infos,
+                ),
+                (
+                    SAMPLED_IMAGE_BINDING,
+                    vk::DescriptorType::SAMPLED_IMAGE,
+                    &sampled_image_infos,
+                ),
+                (SAMPLER_BINDING, vk::DescriptorType::SAMPLER, &sampler_infos),
+            ] {
+                for (dst_array_element, image_info) in image_infos {
+                    write_descriptor_sets.push(vk::WriteDescriptorSet {
                         dst_set: *descriptor_set,
-                        dst_binding: SPECIAL_IMAGE_BINDING, //MAGIC NUMBER SEE context.rs or hexane.glsl
-                        descriptor_count: descriptor_image_infos.len() as _,
-                        descriptor_type: vk::DescriptorType::STORAGE_IMAGE,
-                        p_image_info,
+                        dst_binding,
+                        dst_array_element: *dst_array_element,
+                        descriptor_count: 1,
+                        descriptor_type,
+                        p_image_info: image_info,
                         ..Default::default()
-                    }
-                });
+                    });
+                }
             }
+
             unsafe {
                 logical_device.update_descriptor_sets(&write_descriptor_sets, &[]);
             }
         }
 
-        //TODO make auto sync smarter
-        let mut last_image_access = HashMap::<Image, ImageAccess>::new();
-        let mut last_buffer_access = HashMap::<Buffer, BufferAccess>::new();
+        let RenderGraphModify {
+            nodes,
+            recorders,
+            record_batch,
+            semaphores,
+            owners,
+            ..
+        } = &mut *modify;
+
+        let recorders = &mut recorders[current_frame];
 
-        for (i, node) in modify.nodes.iter_mut().enumerate() {
-            profiling::scope!("task", "ev");
-            let qualifiers = node
-                .resources
-                .iter()
-                .map(|resource| resource.resolve(home))
-                .collect::<Vec<_>>();
+        let mut plan = FramePlan::new(&device.queue_family_indices, segments);
 
-            let mut naive_barriers = vec![];
+        let family = |s: usize| device.queue_family_index(segments[s].queue);
 
-            for (i, qualifier) in qualifiers.iter().enumerate() {
-                match qualifier {
-                    Qualifier::Buffer(buffer, dst) => {
-                        let src = last_buffer_access.entry(*buffer).or_default();
+        //Ownership of resources handed to another queue family is released at the end of the segment that last used them,
+        //or before the frame on the queue an earlier frame left them on.
+        let mut releases = segments.iter().map(|_| vec![]).collect::<Vec<_>>();
+        let mut frame_releases =
+            [Queue::Graphics, Queue::Compute, Queue::Transfer].map(|queue| (queue, vec![]));
 
-                        let offset = 0;
+        //Segments waiting on segments or frame releases of other queues, and the stages that wait.
+        let mut waits = Vec::<(Release, usize, PipelineStage)>::new();
 
-                        let size = {
-                            let resources = resources.lock().unwrap();
+        for (s, segment) in segments.iter().enumerate() {
+            let command_buffer = segment.command_buffers[current_frame];
 
-                            resources.buffers.get(*buffer).unwrap().size
-                        };
+            let mut jobs = vec![];
 
-                        naive_barriers.push(PipelineBarrier {
-                            src_stage: (*src).into(),
-                            dst_stage: (*dst).into(),
-                            barriers: vec![Barrier::Buffer {
-                                buffer: i,
-                                offset,
-                                size,
-                                src_access: (*src).into(),
-                                dst_access: (*dst).into(),
-                            }],
-                        });
+            let segment_nodes = nodes[segment.nodes.clone()].iter_mut();
+
+            for (i, node) in segment.nodes.clone().zip(segment_nodes) {
+                profiling::scope!("task", &node.debug_name);
+                let qualifiers = node
+                    .resources
+                    .iter()
+                    .map(|resource| resource.resolve(home))
+                    .collect::<Vec<_>>();
+
+                let NodeSync {
+                    barriers,
+                    transfers,
+                    ..
+                } = {
+                    let resources = resources.lock().unwrap();
+
+                    plan.node(&resources, owners, i, &qualifiers)
+                };
+
+                let mut acquires = vec![];
+
+                for (transfer, release) in transfers {
+                    let src = match release {
+                        Release::Segment(producer) => family(producer),
+                        Release::Frame(queue) => device.queue_family_index(queue),
+                    };
+
+                    let queue_transfer = QueueTransfer {
+                        src,
+                        dst: family(s),
+                    };
+
+                    let released = (
+                        qualifiers[transfer.qualifier()],
+                        transfer.release(),
+                        queue_transfer,
+                    );
 
-                        last_buffer_access.insert(*buffer, *dst);
+                    match release {
+                        Release::Segment(producer) => releases[producer].push(released),
+                        Release::Frame(queue) => frame_releases[queue as usize].1.push(released),
                     }
-                    Qualifier::Image(image, dst, image_aspect) => {
-                        let src = last_image_access.entry(*image).or_default();
-
-                        u32::from(*image);
-
-                        naive_barriers.push(PipelineBarrier {
-                            src_stage: (*src).into(),
-                            dst_stage: (*dst).into(),
-                            barriers: vec![Barrier::Image {
-                                image: i,
-                                old_layout: (*src).into(),
-                                new_layout: (*dst).into(),
-                                src_access: (*src).into(),
-                                dst_access: (*dst).into(),
-                                image_aspect: (*image_aspect),
-                            }],
-                        });
 
-                        last_image_access.insert(*image, *dst);
+                    acquires.push((transfer.acquire(), queue_transfer));
+
+                    let stage = transfer.dst_stage();
+
+                    match waits
+                        .iter_mut()
+                        .find(|(from, to, _)| *from == release && *to == s)
+                    {
+                        Some((_, _, wait_stage)) => *wait_stage |= stage,
+                        None => waits.push((release, s, stage)),
                     }
                 }
-            }
 
-            let mut smart_barriers =
-                HashMap::<(PipelineStage, PipelineStage), PipelineBarrier>::new();
+                if !recorders.is_empty() && matches!(node.task, NodeTask::Parallel(_)) {
+                    let NodeTask::Parallel(task) = &node.task else {
+                        unreachable!()
+                    };
 
-            for new_barrier in naive_barriers {
-                let key = (new_barrier.src_stage, new_barrier.dst_stage);
+                    jobs.push(Job {
+                        debug_name: &node.debug_name,
+                        task: &**task,
+                        validate: *validate,
+                        qualifiers,
+                        barriers,
+                        acquires,
+                    });
+                    continue;
+                }
 
-                if smart_barriers.contains_key(&key) {
-                    smart_barriers
-                        .get_mut(&key)
-                        .unwrap()
-                        .barriers
-                        .extend(new_barrier.barriers);
-                } else {
-                    smart_barriers.insert(key, new_barrier);
+                if !jobs.is_empty() {
+                    execute_batch(
+                        (*record_batch).unwrap()(Batch {
+                            device,
+                            swapchain,
+                            home: &*home,
+                            queue: segment.queue,
+                            recorders,
+                            jobs: mem::take(&mut jobs),
+                        })?,
+                        command_buffer,
+                        device,
+                        &mut submit,
+                        &mut present,
+                    );
+                }
+
+                let mut commands = Commands {
+                    device: &device,
+                    task: &node.debug_name,
+                    validate: *validate,
+                    qualifiers: &qualifiers,
+                    swapchain: &swapchain,
+                    command_buffer: &command_buffer,
+                    submit: &mut submit,
+                    present: &mut present,
+                    brand: PhantomData,
+                };
+
+                device.begin_label(command_buffer, &node.debug_name);
+
+                for barrier in barriers {
+                    commands
+                        .pipeline_barrier(barrier)
+                        .map_err(task_failed(&node.debug_name))?;
+                }
+
+                for (barrier, queue_transfer) in acquires {
+                    commands
+                        .queue_transfer_barrier(barrier, queue_transfer)
+                        .map_err(task_failed(&node.debug_name))?;
+                }
+
+                match &mut node.task {
+                    NodeTask::Serial(task) => task(home, &mut commands),
+                    NodeTask::Parallel(task) => task(home, &mut commands),
                 }
+                .map_err(task_failed(&node.debug_name))?;
+
+                device.end_label(command_buffer);
             }
 
-            let mut commands = Commands {
-                device: &device,
-                qualifiers: &qualifiers,
-                swapchain: &swapchain,
-                command_buffer: &command_buffers[current_frame],
-                submit: &mut submit,
-                present: &mut present,
-            };
+            if !jobs.is_empty() {
+                execute_batch(
+                    (*record_batch).unwrap()(Batch {
+                        device,
+                        swapchain,
+                        home: &*home,
+                        queue: segment.queue,
+                        recorders,
+                        jobs,
+                    })?,
+                    command_buffer,
+                    device,
+                    &mut submit,
+                    &mut present,
+                );
+            }
+        }
 
-            for (_, barrier) in smart_barriers {
-                commands.pipeline_barrier(barrier).unwrap();
+        let record_releases = |command_buffer: vk::CommandBuffer,
+                               releases: Vec<(Qualifier, _, _)>| {
+            for (qualifier, barrier, queue_transfer) in releases {
+                let mut submit = None;
+                let mut present = None;
+
+                let mut commands = Commands {
+                    device: &device,
+                    task: debug_name,
+                    validate: false,
+                    qualifiers: slice::from_ref(&qualifier),
+                    swapchain: &swapchain,
+                    command_buffer: &command_buffer,
+                    submit: &mut submit,
+                    present: &mut present,
+                    brand: PhantomData,
+                };
+
+                commands
+                    .queue_transfer_barrier(barrier, queue_transfer)
+                    .map_err(task_failed(debug_name))?;
             }
-            (node.task)(home, &mut commands).unwrap();
+
+            Ok(())
+        };
+
+        for (segment, releases) in segments.iter().zip(releases) {
+            let command_buffer = segment.command_buffers[current_frame];
+
+            record_releases(command_buffer, releases)?;
+
+            device.end_label(command_buffer);
+
+            unsafe { logical_device.end_command_buffer(command_buffer) }
+                .map_err(|_| Error::SubmitFailed)?;
         }
 
-        unsafe {
-            logical_device.end_command_buffer(command_buffers[current_frame]);
+        //Submitted before the segments, after the work of earlier frames on the same queue.
+        let mut prologue_queues = vec![];
+
+        for (queue, releases) in frame_releases {
+            if releases.is_empty() {
+                continue;
+            }
+
+            let command_buffer = prologues[queue as usize][current_frame];
+
+            unsafe { logical_device.begin_command_buffer(command_buffer, &Default::default()) }
+                .map_err(|_| Error::SubmitFailed)?;
+
+            device.begin_label(command_buffer, debug_name);
+
+            record_releases(command_buffer, releases)?;
+
+            device.end_label(command_buffer);
+
+            unsafe { logical_device.end_command_buffer(command_buffer) }
+                .map_err(|_| Error::SubmitFailed)?;
+
+            prologue_queues.push(queue);
         }
 
         if let Some(submit) = submit {
@@ -481,62 +871,191 @@ impl<T> RenderGraph<'_, T> {
 
             let resources = resources.lock().unwrap();
 
-            let internal_swapchain = resources.swapchains.get(*swapchain).unwrap();
+            let semaphores = &mut semaphores[current_frame];
 
-            let wait_dst_stage_mask = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
+            let mut used_semaphores = 0;
 
-            let submit_info = {
-                let p_wait_dst_stage_mask = wait_dst_stage_mask.as_ptr();
+            let mut next_semaphore = || {
+                if used_semaphores == semaphores.len() {
+                    let semaphore =
+                        unsafe { logical_device.create_semaphore(&Default::default(), None) }
+                            .map_err(|_| Error::SubmitFailed)?;
 
-                let wait_semaphore_count = submit.wait_semaphore.is_some() as u32;
+                    semaphores.push(semaphore);
+                }
 
-                let p_wait_semaphores = submit.wait_semaphore.map(|x| {
-                    &resources.binary_semaphores.get(x).unwrap().semaphores[current_frame]
-                });
+                used_semaphores += 1;
 
-                let p_wait_semaphores = p_wait_semaphores
-                    .map(|x| x as *const _)
-                    .unwrap_or(ptr::null());
+                Ok(semaphores[used_semaphores - 1])
+            };
 
-                let signal_semaphore_count = submit.signal_semaphore.is_some() as u32;
+            //The fence is only reset once the frame is submitted, so that a frame that fails before is not waited on forever.
+            unsafe { logical_device.reset_fences(&[fences[current_frame]]) }
+                .map_err(|_| Error::SubmitFailed)?;
 
-                let p_signal_semaphores = submit.signal_semaphore.map(|x| {
-                    &resources.binary_semaphores.get(x).unwrap().semaphores[current_frame]
-                });
+            let graphics_family = device.queue_family_index(Queue::Graphics);
 
-                let p_signal_semaphores = p_signal_semaphores
-                    .map(|x| x as *const _)
-                    .unwrap_or(ptr::null());
+            //With work on other queues, a final submission on the graphics queue waits for all of it,
+            //so that the fence and the signaled semaphore cover the whole frame.
+            let join = (0..segments.len()).any(|s| family(s) != graphics_family);
 
-                let command_buffer_count = 1;
+            let first_graphics = (0..segments.len()).find(|&s| family(s) == graphics_family);
 
-                let p_command_buffers = &command_buffers[current_frame];
+            let user_wait_semaphores = submit
+                .wait_semaphores
+                .iter()
+                .map(|&x| resources.binary_semaphores.get(x).unwrap().semaphores[current_frame])
+                .collect::<Vec<_>>();
 
-                vk::SubmitInfo {
-                    p_wait_dst_stage_mask,
-                    wait_semaphore_count,
-                    p_wait_semaphores,
-                    signal_semaphore_count,
-                    p_signal_semaphores,
-                    command_buffer_count,
-                    p_command_buffers,
+            let user_signal_semaphore = submit
+                .signal_semaphore
+                .map(|x| resources.binary_semaphores.get(x).unwrap().semaphores[current_frame]);
+
+            let waits = waits
+                .into_iter()
+                .map(|(from, to, stage)| Ok((from, to, stage, next_semaphore()?)))
+                .collect::<Result<Vec<_>>>()?;
+
+            let mut join_wait_semaphores = vec![];
+
+            for queue in prologue_queues {
+                let signal_semaphores = waits
+                    .iter()
+                    .filter(|(from, ..)| *from == Release::Frame(queue))
+                    .map(|&(.., semaphore)| semaphore)
+                    .collect::<Vec<_>>();
+
+                let submit_info = vk::SubmitInfo {
+                    signal_semaphore_count: signal_semaphores.len() as _,
+                    p_signal_semaphores: signal_semaphores.as_ptr(),
+                    command_buffer_count: 1,
+                    p_command_buffers: &prologues[queue as usize][current_frame],
                     ..Default::default()
+                };
+
+                unsafe {
+                    logical_device.queue_submit(
+                        device.queue(queue),
+                        &[submit_info],
+                        vk::Fence::null(),
+                    )
                 }
-            };
+                .map_err(|_| Error::SubmitFailed)?;
+            }
 
-            unsafe {
-                logical_device.queue_submit(queue, &[submit_info], fences[current_frame]);
+            for (s, segment) in segments.iter().enumerate() {
+                let mut wait_semaphores = vec![];
+                let mut wait_dst_stage_mask = vec![];
+                let mut signal_semaphores = vec![];
+
+                for &(from, to, stage, semaphore) in &waits {
+                    if to == s {
+                        wait_semaphores.push(semaphore);
+                        wait_dst_stage_mask.push(stage.into());
+                    }
+
+                    if from == Release::Segment(s) {
+                        signal_semaphores.push(semaphore);
+                    }
+                }
+
+                if Some(s) == first_graphics {
+                    for &semaphore in &user_wait_semaphores {
+                        wait_semaphores.push(semaphore);
+                        wait_dst_stage_mask.push(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT);
+                    }
+                }
+
+                let last_of_family = (s + 1..segments.len()).all(|next| family(next) != family(s));
+
+                let fence = if join {
+                    if last_of_family && family(s) != graphics_family {
+                        let semaphore = next_semaphore()?;
+
+                        signal_semaphores.push(semaphore);
+                        join_wait_semaphores.push(semaphore);
+                    }
+
+                    vk::Fence::null()
+                } else {
+                    signal_semaphores.extend(user_signal_semaphore);
+
+                    fences[current_frame]
+                };
+
+                let submit_info = vk::SubmitInfo {
+                    wait_semaphore_count: wait_semaphores.len() as _,
+                    p_wait_semaphores: wait_semaphores.as_ptr(),
+                    p_wait_dst_stage_mask: wait_dst_stage_mask.as_ptr(),
+                    signal_semaphore_count: signal_semaphores.len() as _,
+                    p_signal_semaphores: signal_semaphores.as_ptr(),
+                    command_buffer_count: 1,
+                    p_command_buffers: &segment.command_buffers[current_frame],
+                    ..Default::default()
+                };
+
+                unsafe {
+                    logical_device.queue_submit(device.queue(segment.queue), &[submit_info], fence)
+                }
+                .map_err(|_| Error::SubmitFailed)?;
+            }
+
+            if join {
+                let mut wait_dst_stage_mask =
+                    vec![vk::PipelineStageFlags::ALL_COMMANDS; join_wait_semaphores.len()];
+
+                if first_graphics.is_none() {
+                    for &semaphore in &user_wait_semaphores {
+                        join_wait_semaphores.push(semaphore);
+                        wait_dst_stage_mask.push(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT);
+                    }
+                }
+
+                let signal_semaphores = user_signal_semaphore.into_iter().collect::<Vec<_>>();
+
+                let submit_info = vk::SubmitInfo {
+                    wait_semaphore_count: join_wait_semaphores.len() as _,
+                    p_wait_semaphores: join_wait_semaphores.as_ptr(),
+                    p_wait_dst_stage_mask: wait_dst_stage_mask.as_ptr(),
+                    signal_semaphore_count: signal_semaphores.len() as _,
+                    p_signal_semaphores: signal_semaphores.as_ptr(),
+                    ..Default::default()
+                };
+
+                unsafe {
+                    logical_device.queue_submit(
+                        device.queue(Queue::Graphics),
+                        &[submit_info],
+                        fences[current_frame],
+                    )
+                }
+                .map_err(|_| Error::SubmitFailed)?;
             }
+
+            plan.finish(&resources, owners);
         }
 
         if let Some(present) = present {
-            profiling::scope!("submit", "ev");
-
-            let resources = resources.lock().unwrap();
+            profiling::scope!("present", "ev");
 
-            let internal_swapchain = resources.swapchains.get(*swapchain).unwrap();
+            let mut resources = resources.lock().unwrap();
 
-            let image_index = internal_swapchain.last_acquisition_index.unwrap();
+            //Only the swapchains an image was acquired from this frame are presented.
+            let acquired = swapchains
+                .iter()
+                .copied()
+                .filter_map(|swapchain| {
+                    let internal_swapchain = resources.swapchains.get(swapchain).unwrap();
+
+                    let image_index = internal_swapchain.last_acquisition_index?;
+
+                    (!internal_swapchain.allow_acquisition).then_some((
+                        swapchain,
+                        internal_swapchain.handle,
+                        image_index,
+                    ))
+                })
+                .collect::<Vec<_>>();
 
             let wait_semaphore = resources
                 .binary_semaphores
@@ -544,16 +1063,60 @@ impl<T> RenderGraph<'_, T> {
                 .unwrap()
                 .semaphores[current_frame];
 
+            //Images are copied back before they are presented, as presented images can no longer be read.
+            let read_back = acquired
+                .iter()
+                .filter(|(swapchain, _, _)| {
+                    resources
+                        .swapchains
+                        .get(*swapchain)
+                        .unwrap()
+                        .readback
+                        .is_some()
+                })
+                .map(|&(swapchain, _, image_index)| (swapchain, image_index))
+                .collect::<Vec<_>>();
+
+            //Virtual swapchains are only read back.
+            let acquired = acquired
+                .into_iter()
+                .filter(|(swapchain, _, _)| {
+                    resources
+                        .swapchains
+                        .get(*swapchain)
+                        .unwrap()
+                        .capture
+                        .is_none()
+                })
+                .collect::<Vec<_>>();
+
+            if !read_back.is_empty() {
+                device.read_back(
+                    &mut resources,
+                    &read_back,
+                    wait_semaphore,
+                    !acquired.is_empty(),
+                )?;
+            }
+
+            let handles = acquired.iter().map(|x| x.1).collect::<Vec<_>>();
+
+            let image_indices = acquired.iter().map(|x| x.2).collect::<Vec<_>>();
+
+            let mut results = vec![vk::Result::SUCCESS; acquired.len()];
+
             let present_info = {
-                let swapchain_count = 1;
+                let swapchain_count = handles.len() as _;
 
-                let p_swapchains = &swapchain_handle;
+                let p_swapchains = handles.as_ptr();
 
                 let wait_semaphore_count = 1;
 
                 let p_wait_semaphores = &wait_semaphore;
 
-                let p_image_indices = &image_index;
+                let p_image_indices = image_indices.as_ptr();
+
+                let p_results = results.as_mut_ptr();
 
                 vk::PresentInfoKHR {
                     wait_semaphore_count,
@@ -561,33 +1124,54 @@ impl<T> RenderGraph<'_, T> {
                     swapchain_count,
                     p_swapchains,
                     p_image_indices,
+                    p_results,
                     ..Default::default()
                 }
             };
 
-            unsafe {
-                internal_swapchain
-                    .loader
-                    .queue_present(queue, &present_info);
+            if !acquired.is_empty() {
+                let internal_swapchain = resources.swapchains.get(acquired[0].0).unwrap();
+
+                unsafe {
+                    internal_swapchain
+                        .loader
+                        .queue_present(device.queue(Queue::Graphics), &present_info)
+                }
+                .ok();
+            }
+
+            drop(resources);
+
+            let mut resources = device.resources.lock().unwrap();
+
+            //Swapchains that no longer match their surface are recreated before the next frame acquires from them.
+            for ((swapchain, _, _), result) in acquired.into_iter().zip(results) {
+                if let vk::Result::SUBOPTIMAL_KHR | vk::Result::ERROR_OUT_OF_DATE_KHR = result {
+                    resources.swapchains.get_mut(swapchain).unwrap().recreate = true;
+                }
             }
         }
 
         {
             let mut resources = resources.lock().unwrap();
 
-            let mut internal_swapchain = resources.swapchains.get_mut(*swapchain).unwrap();
+            for &swapchain in swapchains {
+                let internal_swapchain = resources.swapchains.get_mut(swapchain).unwrap();
 
-            let current_frame = internal_swapchain.current_frame;
+                let current_frame = internal_swapchain.current_frame;
 
-            internal_swapchain.current_frame = (current_frame + 1) % MAX_FRAMES_IN_FLIGHT;
-            internal_swapchain.allow_acquisition = true;
+                internal_swapchain.current_frame = (current_frame + 1) % MAX_FRAMES_IN_FLIGHT;
+                internal_swapchain.allow_acquisition = true;
+            }
 
             drop(resources);
         }
+
+        Ok(())
     }
 }
 
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
 pub enum ImageAccess {
     #[default]
     None,
@@ -696,7 +1280,7 @@ impl From<ImageAccess> for Access {
     }
 }
 
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
 pub enum BufferAccess {
     #[default]
     None,
@@ -716,6 +1300,8 @@ pub enum BufferAccess {
     TransferWrite,
     HostTransferRead,
     HostTransferWrite,
+    ///Draw parameters read by `Commands::draw_indirect` and `Commands::draw_indexed_indirect`.
+    IndirectRead,
 }
 
 impl From<BufferAccess> for PipelineStage {
@@ -744,6 +1330,7 @@ impl From<BufferAccess> for PipelineStage {
             BufferAccess::TransferWrite => PipelineStage::TRANSFER,
             BufferAccess::HostTransferRead => PipelineStage::HOST,
             BufferAccess::HostTransferWrite => PipelineStage::HOST,
+            BufferAccess::IndirectRead => PipelineStage::DRAW_INDIRECT,
         }
     }
 }
@@ -753,6 +1340,7 @@ impl From<BufferAccess> for Access {
         match access {
             BufferAccess::None => Access::empty(),
             BufferAccess::HostTransferRead
+            | BufferAccess::IndirectRead
             | BufferAccess::TransferRead
             | BufferAccess::ShaderReadOnly
             | BufferAccess::VertexShaderReadOnly
@@ -772,6 +1360,9 @@ impl From<BufferAccess> for Access {
     }
 }
 
+///A resource used by a task, and how the task uses it.
+///The `BufferRange` and `ImageRange` variants restrict the access to part of the resource,
+///so that tasks touching disjoint ranges do not synchronize with each other.
 pub enum Resource<T> {
     Buffer(
         Box<dyn ops::Fn(&mut T) -> Buffer + Send + Sync>,
@@ -782,29 +1373,1518 @@ pub enum Resource<T> {
         ImageAccess,
         ImageAspect,
     ),
+    BufferRange(
+        Box<dyn ops::Fn(&mut T) -> Buffer + Send + Sync>,
+        BufferAccess,
+        BufferRange,
+    ),
+    ImageRange(
+        Box<dyn ops::Fn(&mut T) -> Image + Send + Sync>,
+        ImageAccess,
+        ImageAspect,
+        ImageRange,
+    ),
 }
 
 impl<T> Resource<T> {
     pub(crate) fn resolve(&self, t: &mut T) -> Qualifier {
         match self {
-            Resource::Buffer(call, access) => Qualifier::Buffer((call)(t), *access),
-            Resource::Image(call, access, aspect) => Qualifier::Image((call)(t), *access, *aspect),
+            Resource::Buffer(call, access) => {
+                Qualifier::Buffer((call)(t), *access, BufferRange::ALL)
+            }
+            Resource::Image(call, access, aspect) => {
+                Qualifier::Image((call)(t), *access, *aspect, ImageRange::ALL)
+            }
+            Resource::BufferRange(call, access, range) => {
+                Qualifier::Buffer((call)(t), *access, *range)
+            }
+            Resource::ImageRange(call, access, aspect, range) => {
+                Qualifier::Image((call)(t), *access, *aspect, *range)
+            }
         }
     }
 }
 
 #[derive(Clone, Copy)]
 pub(crate) enum Qualifier {
-    Buffer(Buffer, BufferAccess),
-    Image(Image, ImageAccess, ImageAspect),
+    Buffer(Buffer, BufferAccess, BufferRange),
+    Image(Image, ImageAccess, ImageAspect, ImageRange),
 }
 
-pub struct Task<T, F: ops::FnMut(&mut T, &mut Commands) -> Result<()> + Send + Sync> {
-    pub resources: Vec<Resource<T>>,
-    pub task: F,
+///A hazard between a task's use of a resource and the last use of the same range.
+#[derive(Clone, Copy)]
+pub(crate) enum Dependency {
+    Buffer {
+        qualifier: usize,
+        range: BufferRange,
+        src: BufferAccess,
+        dst: BufferAccess,
+        node: Option<usize>,
+    },
+    Image {
+        qualifier: usize,
+        range: ImageRange,
+        aspect: ImageAspect,
+        src: ImageAccess,
+        dst: ImageAccess,
+        node: Option<usize>,
+    },
 }
 
-pub struct Node<'a, T> {
-    pub resources: Vec<Resource<T>>,
-    pub task: Box<dyn ops::FnMut(&mut T, &mut Commands) -> Result<()> + Send + Sync + 'a>,
+impl Dependency {
+    ///The node that last used the range, if any.
+    pub(crate) fn node(&self) -> Option<usize> {
+        match *self {
+            Dependency::Buffer { node, .. } | Dependency::Image { node, .. } => node,
+        }
+    }
+
+    pub(crate) fn qualifier(&self) -> usize {
+        match *self {
+            Dependency::Buffer { qualifier, .. } | Dependency::Image { qualifier, .. } => qualifier,
+        }
+    }
+
+    pub(crate) fn dst_stage(&self) -> PipelineStage {
+        match *self {
+            Dependency::Buffer { dst, .. } => dst.into(),
+            Dependency::Image { dst, .. } => dst.into(),
+        }
+    }
+
+    ///The half of a queue family ownership transfer recorded on the queue that last used the range.
+    ///Refers to the resource as the only qualifier.
+    pub(crate) fn release(&self) -> PipelineBarrier {
+        let PipelineBarrier {
+            src_stage,
+            mut barriers,
+            ..
+        } = self.barrier();
+
+        for barrier in &mut barriers {
+            match barrier {
+                Barrier::Buffer {
+                    buffer, dst_access, ..
+                } => {
+                    *buffer = 0;
+                    *dst_access = Access::empty();
+                }
+                Barrier::Image {
+                    image, dst_access, ..
+                } => {
+                    *image = 0;
+                    *dst_access = Access::empty();
+                }
+            }
+        }
+
+        PipelineBarrier {
+            src_stage,
+            dst_stage: PipelineStage::BOTTOM_OF_PIPE,
+            barriers,
+        }
+    }
+
+    ///The half of a queue family ownership transfer recorded on the queue that uses the range next.
+    pub(crate) fn acquire(&self) -> PipelineBarrier {
+        let PipelineBarrier {
+            dst_stage,
+            mut barriers,
+            ..
+        } = self.barrier();
+
+        for barrier in &mut barriers {
+            match barrier {
+                Barrier::Buffer { src_access, .. } | Barrier::Image { src_access, .. } => {
+                    *src_access = Access::empty()
+                }
+            }
+        }
+
+        PipelineBarrier {
+            src_stage: PipelineStage::TOP_OF_PIPE,
+            dst_stage,
+            barriers,
+        }
+    }
+
+    pub(crate) fn barrier(&self) -> PipelineBarrier {
+        match *self {
+            Dependency::Buffer {
+                qualifier,
+                range,
+                src,
+                dst,
+                ..
+            } => PipelineBarrier {
+                src_stage: src.into(),
+                dst_stage: dst.into(),
+                barriers: vec![Barrier::Buffer {
+                    buffer: qualifier,
+                    offset: range.offset,
+                    size: range.size,
+                    src_access: src.into(),
+                    dst_access: dst.into(),
+                }],
+            },
+            Dependency::Image {
+                qualifier,
+                range,
+                aspect,
+                src,
+                dst,
+                ..
+            } => PipelineBarrier {
+                src_stage: src.into(),
+                dst_stage: dst.into(),
+                barriers: vec![Barrier::Image {
+                    image: qualifier,
+                    old_layout: src.into(),
+                    new_layout: dst.into(),
+                    src_access: src.into(),
+                    dst_access: dst.into(),
+                    image_aspect: aspect,
+                    range,
+                }],
+            },
+        }
+    }
+}
+
+///Names the task an error came from.
+fn task_failed(task: &str) -> impl FnOnce(Error) -> Error + '_ {
+    move |error| Error::TaskFailed {
+        task: task.to_owned(),
+        error: Box::new(error),
+    }
+}
+
+///Groups the barriers of a task's dependencies by their stages, keeping the order they were planned in.
+pub(crate) fn merge_barriers(dependencies: &[Dependency]) -> Vec<PipelineBarrier> {
+    let mut smart_barriers = Vec::<PipelineBarrier>::new();
+
+    for new_barrier in dependencies.iter().map(Dependency::barrier) {
+        let existing = smart_barriers.iter_mut().find(|barrier| {
+            barrier.src_stage == new_barrier.src_stage && barrier.dst_stage == new_barrier.dst_stage
+        });
+
+        match existing {
+            Some(barrier) => barrier.barriers.extend(new_barrier.barriers),
+            None => smart_barriers.push(new_barrier),
+        }
+    }
+
+    smart_barriers
+}
+
+///Where the release half of a queue family ownership transfer is recorded.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Release {
+    ///At the end of a segment of the frame, which last used the range.
+    Segment(usize),
+    ///At the start of the frame on a queue, because an earlier frame last used the range there.
+    Frame(Queue),
+}
+
+///How a node synchronizes with the work before it.
+pub(crate) struct NodeSync {
+    pub(crate) dependencies: Vec<Dependency>,
+    ///Recorded before the node, against earlier work on the same queue family.
+    pub(crate) barriers: Vec<PipelineBarrier>,
+    ///Ranges another queue family has to hand over first.
+    pub(crate) transfers: Vec<(Dependency, Release)>,
+}
+
+///Plans the synchronization of a frame node by node.
+///Shared by `render` and the graph exports, so that they always agree.
+pub(crate) struct FramePlan<'b> {
+    ///Indexed by `Queue`, as on the device.
+    queue_family_indices: &'b [u32],
+    segments: &'b [Segment],
+    node_segments: Vec<usize>,
+    tracker: AccessTracker,
+}
+
+impl<'b> FramePlan<'b> {
+    pub(crate) fn new(queue_family_indices: &'b [u32], segments: &'b [Segment]) -> Self {
+        let node_segments = segments
+            .iter()
+            .enumerate()
+            .flat_map(|(s, segment)| segment.nodes.clone().map(move |_| s))
+            .collect();
+
+        Self {
+            queue_family_indices,
+            segments,
+            node_segments,
+            tracker: AccessTracker::default(),
+        }
+    }
+
+    pub(crate) fn family(&self, segment: usize) -> u32 {
+        self.queue_family_indices[self.segments[segment].queue as usize]
+    }
+
+    ///Records the accesses of `node` and splits what it has to synchronize against
+    ///into barriers on its own queue family and ownership transfers from others.
+    pub(crate) fn node(
+        &mut self,
+        resources: &DeviceResources,
+        owners: &Owners,
+        node: usize,
+        qualifiers: &[Qualifier],
+    ) -> NodeSync {
+        let s = self.node_segments[node];
+
+        let family = self.family(s);
+
+        let mut dependencies = vec![];
+        let mut transfers = vec![];
+
+        for dependency in self.tracker.plan(resources, node, qualifiers) {
+            match dependency.node() {
+                Some(last) if self.family(self.node_segments[last]) != family => {
+                    transfers.push((dependency, Release::Segment(self.node_segments[last])))
+                }
+                Some(_) => dependencies.push(dependency),
+                None => {
+                    for (dependency, owner) in owners.claim(
+                        self.queue_family_indices,
+                        resources,
+                        qualifiers,
+                        dependency,
+                        family,
+                    ) {
+                        match owner {
+                            Some(queue) => transfers.push((dependency, Release::Frame(queue))),
+                            None => dependencies.push(dependency),
+                        }
+                    }
+                }
+            }
+        }
+
+        let barriers = merge_barriers(&dependencies);
+
+        NodeSync {
+            dependencies,
+            barriers,
+            transfers,
+        }
+    }
+
+    ///Hands the state the frame leaves every range in to the next frame.
+    pub(crate) fn finish(self, resources: &DeviceResources, owners: &mut Owners) {
+        let FramePlan {
+            segments,
+            node_segments,
+            tracker,
+            ..
+        } = self;
+
+        owners.update(resources, &tracker, |node| {
+            segments[node_segments[node]].queue
+        });
+    }
+}
+
+///The queue and access every range was left in by the last frame that used it,
+///so that ownership moves between queue families from one frame to the next too.
+///Entries remember the Vulkan handle, and are ignored once the resource is destroyed and its handle reused.
+#[derive(Default)]
+pub(crate) struct Owners {
+    buffers: HashMap<Buffer, (vk::Buffer, Vec<(BufferRange, BufferAccess, Queue)>)>,
+    images: HashMap<(Image, u32, u32), (vk::Image, ImageAccess, Queue)>,
+}
+
+impl Owners {
+    ///Splits a dependency on a range first used in the frame by the queue that owns each part of it.
+    ///Parts owned by another queue family come with that queue, the rest with `None`.
+    fn claim(
+        &self,
+        queue_family_indices: &[u32],
+        resources: &DeviceResources,
+        qualifiers: &[Qualifier],
+        dependency: Dependency,
+        family: u32,
+    ) -> Vec<(Dependency, Option<Queue>)> {
+        let foreign = |queue: Queue| queue_family_indices[queue as usize] != family;
+
+        match dependency {
+            Dependency::Buffer {
+                qualifier,
+                range,
+                dst,
+                ..
+            } => {
+                let Qualifier::Buffer(buffer, ..) = qualifiers[qualifier] else {
+                    unreachable!()
+                };
+
+                let raw = resources.buffers.get(buffer).unwrap().buffer;
+
+                let mut owned = match self.buffers.get(&buffer) {
+                    Some((owned_raw, states)) if *owned_raw == raw => states
+                        .iter()
+                        .filter(|(_, _, queue)| foreign(*queue))
+                        .filter_map(|(state_range, access, queue)| {
+                            let offset = state_range.offset.max(range.offset);
+                            let end = state_range.end().min(range.end());
+
+                            (offset < end).then(|| {
+                                (
+                                    BufferRange {
+                                        offset,
+                                        size: end - offset,
+                                    },
+                                    *access,
+                                    *queue,
+                                )
+                            })
+                        })
+                        .collect::<Vec<_>>(),
+                    _ => vec![],
+                };
+
+                if owned.is_empty() {
+                    return vec![(dependency, None)];
+                }
+
+                owned.sort_by_key(|(owned, _, _)| owned.offset);
+
+                let mut result = vec![];
+                let mut cursor = range.offset;
+
+                let part = |range, src| Dependency::Buffer {
+                    qualifier,
+                    range,
+                    src,
+                    dst,
+                    node: None,
+                };
+
+                for (owned, access, queue) in owned {
+                    if cursor < owned.offset {
+                        let gap = BufferRange {
+                            offset: cursor,
+                            size: owned.offset - cursor,
+                        };
+
+                        result.push((part(gap, BufferAccess::None), None));
+                    }
+
+                    cursor = owned.end();
+
+                    result.push((part(owned, access), Some(queue)));
+                }
+
+                if cursor < range.end() {
+                    let gap = BufferRange {
+                        offset: cursor,
+                        size: range.end() - cursor,
+                    };
+
+                    result.push((part(gap, BufferAccess::None), None));
+                }
+
+                result
+            }
+            Dependency::Image {
+                qualifier,
+                range,
+                aspect,
+                dst,
+                ..
+            } => {
+                let Qualifier::Image(image, ..) = qualifiers[qualifier] else {
+                    unreachable!()
+                };
+
+                let internal_image = resources.images.get(image).unwrap();
+
+                let tracked = internal_image.get_parent().unwrap_or(image);
+
+                let raw = resources.images.get(tracked).unwrap().get_image();
+
+                let parent_range = internal_image.to_parent(range);
+
+                let owners = (0..parent_range.mip_level_count)
+                    .flat_map(|mip| {
+                        (0..parent_range.array_layer_count).map(move |layer| {
+                            (
+                                parent_range.base_mip_level + mip,
+                                parent_range.base_array_layer + layer,
+                            )
+                        })
+                    })
+                    .map(|(mip_level, array_layer)| {
+                        let owner = self
+                            .images
+                            .get(&(tracked, mip_level, array_layer))
+                            .filter(|(owned_raw, _, queue)| *owned_raw == raw && foreign(*queue))
+                            .map(|(_, access, queue)| (*access, *queue));
+
+                        (mip_level, array_layer, owner)
+                    })
+                    .collect::<Vec<_>>();
+
+                if owners.iter().all(|(_, _, owner)| owner.is_none()) {
+                    return vec![(dependency, None)];
+                }
+
+                //Handed over one subresource at a time, this only happens when a frame starts on another queue family.
+                owners
+                    .into_iter()
+                    .map(|(mip_level, array_layer, owner)| {
+                        let range = internal_image.from_parent(ImageRange {
+                            base_mip_level: mip_level,
+                            mip_level_count: 1,
+                            base_array_layer: array_layer,
+                            array_layer_count: 1,
+                        });
+
+                        let src = owner.map_or(ImageAccess::None, |(access, _)| access);
+
+                        (
+                            Dependency::Image {
+                                qualifier,
+                                range,
+                                aspect,
+                                src,
+                                dst,
+                                node: None,
+                            },
+                            owner.map(|(_, queue)| queue),
+                        )
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    ///Takes over the last access of every range the frame used.
+    fn update(
+        &mut self,
+        resources: &DeviceResources,
+        tracker: &AccessTracker,
+        queue: impl Fn(usize) -> Queue,
+    ) {
+        for (&buffer, states) in &tracker.buffers {
+            let raw = resources.buffers.get(buffer).unwrap().buffer;
+
+            let (owned_raw, owned) = self.buffers.entry(buffer).or_insert((raw, vec![]));
+
+            if *owned_raw != raw {
+                *owned_raw = raw;
+                owned.clear();
+            }
+
+            for &(range, access, node) in states {
+                let mut remaining = vec![];
+
+                for (owned_range, owned_access, owned_queue) in owned.drain(..) {
+                    if owned_range.offset < range.offset {
+                        remaining.push((
+                            BufferRange {
+                                offset: owned_range.offset,
+                                size: owned_range.end().min(range.offset) - owned_range.offset,
+                            },
+                            owned_access,
+                            owned_queue,
+                        ));
+                    }
+
+                    if owned_range.end() > range.end() {
+                        let offset = owned_range.offset.max(range.end());
+
+                        remaining.push((
+                            BufferRange {
+                                offset,
+                                size: owned_range.end() - offset,
+                            },
+                            owned_access,
+                            owned_queue,
+                        ));
+                    }
+                }
+
+                remaining.push((range, access, queue(node)));
+
+                *owned = remaining;
+            }
+        }
+
+        for (&(image, mip_level, array_layer), &(access, node)) in &tracker.images {
+            let internal_image = resources.images.get(image).unwrap();
+
+            //Presented images are handed back by the presentation engine, not by a release.
+            if matches!(internal_image, InternalImage::Swapchain { .. })
+                || access == ImageAccess::Present
+            {
+                self.images.remove(&(image, mip_level, array_layer));
+                continue;
+            }
+
+            self.images.insert(
+                (image, mip_level, array_layer),
+                (internal_image.get_image(), access, queue(node)),
+            );
+        }
+    }
+}
+
+///Tracks the last access of every buffer range and image subresource during a frame,
+///along with the node that made it.
+#[derive(Default)]
+pub(crate) struct AccessTracker {
+    buffers: HashMap<Buffer, Vec<(BufferRange, BufferAccess, usize)>>,
+    images: HashMap<(Image, u32, u32), (ImageAccess, usize)>,
+}
+
+impl AccessTracker {
+    ///Records the accesses `node` makes through its qualifiers, returning what it has to synchronize against.
+    pub(crate) fn plan(
+        &mut self,
+        resources: &DeviceResources,
+        node: usize,
+        qualifiers: &[Qualifier],
+    ) -> Vec<Dependency> {
+        let mut dependencies = vec![];
+
+        for (qualifier, resource) in qualifiers.iter().enumerate() {
+            match *resource {
+                Qualifier::Buffer(buffer, dst, range) => {
+                    let range = range.resolve(resources.buffers.get(buffer).unwrap().size);
+
+                    for (range, src, last) in self.access_buffer(buffer, range, dst, node) {
+                        dependencies.push(Dependency::Buffer {
+                            qualifier,
+                            range,
+                            src,
+                            dst,
+                            node: last,
+                        });
+                    }
+                }
+                Qualifier::Image(image, dst, aspect, range) => {
+                    let internal_image = resources.images.get(image).unwrap();
+
+                    let range = range.resolve(
+                        internal_image.get_mip_levels(),
+                        internal_image.get_array_layers(),
+                    );
+
+                    //Views are tracked as the part of their image they cover.
+                    let tracked = internal_image.get_parent().unwrap_or(image);
+
+                    let range = internal_image.to_parent(range);
+
+                    for (range, src, last) in self.access_image(tracked, range, dst, node) {
+                        dependencies.push(Dependency::Image {
+                            qualifier,
+                            range: internal_image.from_parent(range),
+                            aspect,
+                            src,
+                            dst,
+                            node: last,
+                        });
+                    }
+                }
+            }
+        }
+
+        dependencies
+    }
+
+    ///Records an access to a resolved buffer range.
+    ///Returns the previous access of every part of the range, which need to be synchronized against.
+    pub(crate) fn access_buffer(
+        &mut self,
+        buffer: Buffer,
+        range: BufferRange,
+        dst: BufferAccess,
+        node: usize,
+    ) -> Vec<(BufferRange, BufferAccess, Option<usize>)> {
+        //An empty range touches no memory, and cannot be a barrier.
+        if range.size == 0 {
+            return vec![];
+        }
+
+        let states = self.buffers.entry(buffer).or_default();
+
+        let mut overlaps = states
+            .iter()
+            .filter_map(|(state_range, access, last)| {
+                let offset = state_range.offset.max(range.offset);
+                let end = state_range.end().min(range.end());
+
+                (offset < end).then(|| {
+                    (
+                        BufferRange {
+                            offset,
+                            size: end - offset,
+                        },
+                        *access,
+                        Some(*last),
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+
+        overlaps.sort_by_key(|(overlap, _, _)| overlap.offset);
+
+        let mut result = vec![];
+        let mut cursor = range.offset;
+
+        for (overlap, access, last) in overlaps {
+            if cursor < overlap.offset {
+                result.push((
+                    BufferRange {
+                        offset: cursor,
+                        size: overlap.offset - cursor,
+                    },
+                    BufferAccess::None,
+                    None,
+                ));
+            }
+
+            cursor = overlap.end();
+
+            result.push((overlap, access, last));
+        }
+
+        if cursor < range.end() {
+            result.push((
+                BufferRange {
+                    offset: cursor,
+                    size: range.end() - cursor,
+                },
+                BufferAccess::None,
+                None,
+            ));
+        }
+
+        let mut remaining = vec![];
+
+        for (state_range, access, last) in states.drain(..) {
+            if state_range.offset < range.offset {
+                remaining.push((
+                    BufferRange {
+                        offset: state_range.offset,
+                        size: state_range.end().min(range.offset) - state_range.offset,
+                    },
+                    access,
+                    last,
+                ));
+            }
+
+            if state_range.end() > range.end() {
+                let offset = state_range.offset.max(range.end());
+
+                remaining.push((
+                    BufferRange {
+                        offset,
+                        size: state_range.end() - offset,
+                    },
+                    access,
+                    last,
+                ));
+            }
+        }
+
+        remaining.push((range, dst, node));
+
+        *states = remaining;
+
+        result
+    }
+
+    ///Records an access to a resolved image range.
+    ///Returns the previous access of every part of the range, grouped into ranges, which need to be synchronized against.
+    pub(crate) fn access_image(
+        &mut self,
+        image: Image,
+        range: ImageRange,
+        dst: ImageAccess,
+        node: usize,
+    ) -> Vec<(ImageRange, ImageAccess, Option<usize>)> {
+        let mut result = Vec::<(ImageRange, ImageAccess, Option<usize>)>::new();
+
+        let mip_levels = range.base_mip_level..range.base_mip_level + range.mip_level_count;
+
+        for mip_level in mip_levels {
+            let mut layers = Vec::<(ImageRange, ImageAccess, Option<usize>)>::new();
+
+            let array_layers =
+                range.base_array_layer..range.base_array_layer + range.array_layer_count;
+
+            for array_layer in array_layers {
+                let (src, last) = match self
+                    .images
+                    .insert((image, mip_level, array_layer), (dst, node))
+                {
+                    Some((src, last)) => (src, Some(last)),
+                    None => (ImageAccess::None, None),
+                };
+
+                match layers.last_mut() {
+                    Some((range, access, previous)) if *access == src && *previous == last => {
+                        range.array_layer_count += 1
+                    }
+                    _ => layers.push((
+                        ImageRange {
+                            base_mip_level: mip_level,
+                            mip_level_count: 1,
+                            base_array_layer: array_layer,
+                            array_layer_count: 1,
+                        },
+                        src,
+                        last,
+                    )),
+                }
+            }
+
+            //Merge with the previous mip level if it transitioned the same layers from the same access.
+            for (layer_range, src, last) in layers {
+                let previous = result.iter_mut().find(|(previous, access, previous_last)| {
+                    *access == src
+                        && *previous_last == last
+                        && previous.base_array_layer == layer_range.base_array_layer
+                        && previous.array_layer_count == layer_range.array_layer_count
+                        && previous.base_mip_level + previous.mip_level_count == mip_level
+                });
+
+                match previous {
+                    Some((previous, _, _)) => previous.mip_level_count += 1,
+                    None => result.push((layer_range, src, last)),
+                }
+            }
+        }
+
+        result
+    }
+}
+
+pub struct Task<'a, 'id, T> {
+    ///Records the task, using the tokens of the resources declared for it.
+    pub task: Box<dyn ops::FnMut(&mut T, &mut Commands<'_, 'id>) -> Result<()> + Send + Sync + 'id>,
+    ///The queue the task is submitted to. Ownership of resources moves between queues as needed.
+    pub queue: Queue,
+    ///Shown in graph exports, profiling scopes, error messages and as a command buffer label.
+    pub debug_name: &'a str,
+}
+
+pub struct ParallelTask<'a, 'id, T> {
+    ///Records the task, using the tokens of the resources declared for it.
+    pub task: Box<dyn ops::Fn(&T, &mut Commands<'_, 'id>) -> Result<()> + Send + Sync + 'id>,
+    ///The queue the task is submitted to. Ownership of resources moves between queues as needed.
+    pub queue: Queue,
+    ///Shown in graph exports, profiling scopes, error messages and as a command buffer label.
+    pub debug_name: &'a str,
+}
+
+pub struct Node<'a, T> {
+    pub resources: Vec<Resource<T>>,
+    pub task: NodeTask<'a, T>,
+    pub queue: Queue,
+    pub debug_name: String,
+}
+
+pub enum NodeTask<'a, T> {
+    Serial(Box<dyn ops::FnMut(&mut T, &mut Commands<'_, 'a>) -> Result<()> + Send + Sync + 'a>),
+    Parallel(Box<dyn ops::Fn(&T, &mut Commands<'_, 'a>) -> Result<()> + Send + Sync + 'a>),
+}
+
+///Records a batch of parallel tasks. Only exists for graphs whose `T` is `Sync`.
+pub(crate) type RecordBatch<'a, T> = for<'b> fn(Batch<'b, 'a, T>) -> Result<Vec<Recorded<'b>>>;
+
+///A parallel task waiting to be recorded, with its resources and barriers already planned.
+pub(crate) struct Job<'b, 'a, T> {
+    debug_name: &'b str,
+    task: &'b (dyn ops::Fn(&T, &mut Commands<'_, 'a>) -> Result<()> + Send + Sync + 'a),
+    validate: bool,
+    qualifiers: Vec<Qualifier>,
+    barriers: Vec<PipelineBarrier>,
+    acquires: Vec<(PipelineBarrier, QueueTransfer)>,
+}
+
+pub(crate) struct Batch<'b, 'a, T> {
+    device: &'b DeviceInner,
+    swapchain: &'b Swapchain,
+    home: &'b T,
+    queue: Queue,
+    recorders: &'b mut [Recorder],
+    jobs: Vec<Job<'b, 'a, T>>,
+}
+
+///A secondary command buffer holding a recorded parallel task.
+pub(crate) struct Recorded<'b> {
+    debug_name: &'b str,
+    command_buffer: vk::CommandBuffer,
+    submit: Option<Submit>,
+    present: Option<Present>,
+}
+
+///The secondary command buffers of one recording thread for one frame in flight.
+///Pools and command buffers are indexed by `Queue`.
+pub(crate) struct Recorder {
+    command_pools: Vec<vk::CommandPool>,
+    command_buffers: Vec<Vec<vk::CommandBuffer>>,
+    used: Vec<usize>,
+}
+
+impl Recorder {
+    fn new(device: &DeviceInner) -> Result<Self> {
+        let DeviceInner {
+            logical_device,
+            queue_family_indices,
+            ..
+        } = device;
+
+        let mut command_pools = vec![];
+
+        for &queue_family_index in queue_family_indices {
+            let command_pool_create_info = vk::CommandPoolCreateInfo {
+                flags: vk::CommandPoolCreateFlags::TRANSIENT,
+                queue_family_index,
+                ..Default::default()
+            };
+
+            let command_pool =
+                unsafe { logical_device.create_command_pool(&command_pool_create_info, None) }
+                    .map_err(|_| Error::CreateCommandPool)?;
+
+            command_pools.push(command_pool);
+        }
+
+        Ok(Self {
+            command_buffers: vec![vec![]; command_pools.len()],
+            used: vec![0; command_pools.len()],
+            command_pools,
+        })
+    }
+
+    ///Makes every command buffer available again. The frame using them must have finished.
+    fn reset(&mut self, device: &DeviceInner) -> Result<()> {
+        for &command_pool in &self.command_pools {
+            unsafe {
+                device
+                    .logical_device
+                    .reset_command_pool(command_pool, vk::CommandPoolResetFlags::empty())
+            }
+            .map_err(|_| Error::SubmitFailed)?;
+        }
+
+        self.used.fill(0);
+
+        Ok(())
+    }
+
+    fn next_command_buffer(
+        &mut self,
+        device: &DeviceInner,
+        queue: Queue,
+    ) -> Result<vk::CommandBuffer> {
+        let queue = queue as usize;
+
+        let command_buffers = &mut self.command_buffers[queue];
+        let used = &mut self.used[queue];
+
+        if *used == command_buffers.len() {
+            let command_buffer_allocate_info = vk::CommandBufferAllocateInfo {
+                command_pool: self.command_pools[queue],
+                level: vk::CommandBufferLevel::SECONDARY,
+                command_buffer_count: 1,
+                ..Default::default()
+            };
+
+            let new_command_buffers = unsafe {
+                device
+                    .logical_device
+                    .allocate_command_buffers(&command_buffer_allocate_info)
+            }
+            .map_err(|_| Error::SubmitFailed)?;
+
+            command_buffers.extend(new_command_buffers);
+        }
+
+        *used += 1;
+
+        Ok(command_buffers[*used - 1])
+    }
+
+    fn record<'b, T>(
+        &mut self,
+        device: &DeviceInner,
+        swapchain: &Swapchain,
+        home: &T,
+        queue: Queue,
+        jobs: Vec<Job<'b, '_, T>>,
+    ) -> Result<Vec<Recorded<'b>>> {
+        let mut recorded = Vec::with_capacity(jobs.len());
+
+        for job in jobs {
+            profiling::scope!("task", job.debug_name);
+
+            let Job {
+                debug_name,
+                task,
+                validate,
+                qualifiers,
+                barriers,
+                acquires,
+            } = job;
+
+            let command_buffer = self.next_command_buffer(device, queue)?;
+
+            let inheritance_info = vk::CommandBufferInheritanceInfo::default();
+
+            let command_buffer_begin_info = vk::CommandBufferBeginInfo {
+                flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
+                p_inheritance_info: &inheritance_info,
+                ..Default::default()
+            };
+
+            unsafe {
+                device
+                    .logical_device
+                    .begin_command_buffer(command_buffer, &command_buffer_begin_info)
+            }
+            .map_err(|_| Error::SubmitFailed)?;
+
+            let mut submit = None;
+            let mut present = None;
+
+            let mut commands = Commands {
+                device,
+                task: debug_name,
+                validate,
+                qualifiers: &qualifiers,
+                swapchain,
+                command_buffer: &command_buffer,
+                submit: &mut submit,
+                present: &mut present,
+                brand: PhantomData,
+            };
+
+            device.begin_label(command_buffer, debug_name);
+
+            for barrier in barriers {
+                commands
+                    .pipeline_barrier(barrier)
+                    .map_err(task_failed(debug_name))?;
+            }
+
+            for (barrier, queue_transfer) in acquires {
+                commands
+                    .queue_transfer_barrier(barrier, queue_transfer)
+                    .map_err(task_failed(debug_name))?;
+            }
+
+            task(home, &mut commands).map_err(task_failed(debug_name))?;
+
+            device.end_label(command_buffer);
+
+            unsafe { device.logical_device.end_command_buffer(command_buffer) }
+                .map_err(|_| Error::SubmitFailed)?;
+
+            recorded.push(Recorded {
+                debug_name,
+                command_buffer,
+                submit,
+                present,
+            });
+        }
+
+        Ok(recorded)
+    }
+}
+
+///Splits a batch of parallel tasks over the recorders, each on its own thread.
+///The recorded command buffers are returned in the order of the tasks, or the first error of a thread.
+fn record_batch<'b, 'a, T: Sync>(batch: Batch<'b, 'a, T>) -> Result<Vec<Recorded<'b>>> {
+    profiling::scope!("parallel recording", "ev");
+
+    let Batch {
+        device,
+        swapchain,
+        home,
+        queue,
+        recorders,
+        jobs,
+    } = batch;
+
+    let chunk_size = jobs.len().div_ceil(recorders.len());
+
+    let mut jobs = jobs.into_iter();
+
+    thread::scope(|scope| {
+        let handles = recorders
+            .iter_mut()
+            .map(|recorder| {
+                let chunk = jobs.by_ref().take(chunk_size).collect::<Vec<_>>();
+
+                scope.spawn(move || recorder.record(device, swapchain, home, queue, chunk))
+            })
+            .collect::<Vec<_>>();
+
+        let mut recorded = vec![];
+
+        //Every thread is joined before returning, even after one of them failed.
+        let results = handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|payload| panic::resume_unwind(payload))
+            })
+            .collect::<Vec<_>>();
+
+        for result in results {
+            recorded.extend(result?);
+        }
+
+        Ok(recorded)
+    })
+}
+
+///Executes recorded parallel tasks in order, keeping the last submit and present they requested.
+fn execute_batch(
+    recorded: Vec<Recorded<'_>>,
+    command_buffer: vk::CommandBuffer,
+    device: &DeviceInner,
+    submit: &mut Option<Submit>,
+    present: &mut Option<Present>,
+) {
+    for recorded in recorded {
+        let Recorded {
+            command_buffer: secondary,
+            submit: task_submit,
+            present: task_present,
+            ..
+        } = recorded;
+
+        unsafe {
+            device
+                .logical_device
+                .cmd_execute_commands(command_buffer, &[secondary])
+        };
+
+        if task_submit.is_some() {
+            *submit = task_submit;
+        }
+
+        if task_present.is_some() {
+            *present = task_present;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ash::vk::Handle;
+
+    const fn buffer_range(offset: usize, size: usize) -> BufferRange {
+        BufferRange { offset, size }
+    }
+
+    const fn image_range(
+        base_mip_level: u32,
+        mip_level_count: u32,
+        base_array_layer: u32,
+        array_layer_count: u32,
+    ) -> ImageRange {
+        ImageRange {
+            base_mip_level,
+            mip_level_count,
+            base_array_layer,
+            array_layer_count,
+        }
+    }
+
+    #[test]
+    fn overlapping_buffer_ranges_depend_on_the_overlap() {
+        let mut tracker = AccessTracker::default();
+
+        assert_eq!(
+            tracker.access_buffer(
+                Buffer(0),
+                buffer_range(0, 256),
+                BufferAccess::TransferWrite,
+                0
+            ),
+            [(buffer_range(0, 256), BufferAccess::None, None)]
+        );
+
+        assert_eq!(
+            tracker.access_buffer(
+                Buffer(0),
+                buffer_range(128, 256),
+                BufferAccess::ShaderReadOnly,
+                1
+            ),
+            [
+                (buffer_range(128, 128), BufferAccess::TransferWrite, Some(0)),
+                (buffer_range(256, 128), BufferAccess::None, None),
+            ]
+        );
+
+        assert_eq!(
+            tracker.access_buffer(
+                Buffer(0),
+                buffer_range(0, 512),
+                BufferAccess::TransferRead,
+                2
+            ),
+            [
+                (buffer_range(0, 128), BufferAccess::TransferWrite, Some(0)),
+                (
+                    buffer_range(128, 256),
+                    BufferAccess::ShaderReadOnly,
+                    Some(1)
+                ),
+                (buffer_range(384, 128), BufferAccess::None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn adjacent_buffer_ranges_are_independent() {
+        let mut tracker = AccessTracker::default();
+
+        tracker.access_buffer(
+            Buffer(0),
+            buffer_range(0, 128),
+            BufferAccess::TransferWrite,
+            0,
+        );
+
+        assert_eq!(
+            tracker.access_buffer(
+                Buffer(0),
+                buffer_range(128, 128),
+                BufferAccess::TransferWrite,
+                1
+            ),
+            [(buffer_range(128, 128), BufferAccess::None, None)]
+        );
+
+        //Other buffers are tracked on their own.
+        assert_eq!(
+            tracker.access_buffer(
+                Buffer(1),
+                buffer_range(0, 128),
+                BufferAccess::TransferRead,
+                2
+            ),
+            [(buffer_range(0, 128), BufferAccess::None, None)]
+        );
+    }
+
+    #[test]
+    fn empty_ranges_are_not_tracked() {
+        let mut tracker = AccessTracker::default();
+
+        tracker.access_buffer(
+            Buffer(0),
+            buffer_range(0, 256),
+            BufferAccess::TransferWrite,
+            0,
+        );
+
+        assert!(tracker
+            .access_buffer(
+                Buffer(0),
+                buffer_range(64, 0),
+                BufferAccess::TransferRead,
+                1
+            )
+            .is_empty());
+
+        assert_eq!(
+            tracker.access_buffer(
+                Buffer(0),
+                buffer_range(0, 256),
+                BufferAccess::TransferRead,
+                2
+            ),
+            [(buffer_range(0, 256), BufferAccess::TransferWrite, Some(0))]
+        );
+
+        assert!(tracker
+            .access_image(
+                Image(0),
+                image_range(0, 0, 0, 1),
+                ImageAccess::TransferWrite,
+                3
+            )
+            .is_empty());
+
+        assert!(tracker
+            .access_image(
+                Image(0),
+                image_range(0, 1, 0, 0),
+                ImageAccess::TransferWrite,
+                3
+            )
+            .is_empty());
+    }
+
+    #[test]
+    fn partial_image_ranges_are_grouped() {
+        let mut tracker = AccessTracker::default();
+
+        assert_eq!(
+            tracker.access_image(
+                Image(0),
+                image_range(0, 4, 0, 2),
+                ImageAccess::TransferWrite,
+                0
+            ),
+            [(image_range(0, 4, 0, 2), ImageAccess::None, None)]
+        );
+
+        assert_eq!(
+            tracker.access_image(
+                Image(0),
+                image_range(1, 2, 1, 1),
+                ImageAccess::ShaderReadOnly,
+                1
+            ),
+            [(image_range(1, 2, 1, 1), ImageAccess::TransferWrite, Some(0))]
+        );
+
+        assert_eq!(
+            tracker.access_image(
+                Image(0),
+                image_range(0, 4, 0, 2),
+                ImageAccess::TransferRead,
+                2
+            ),
+            [
+                (image_range(0, 1, 0, 2), ImageAccess::TransferWrite, Some(0)),
+                (image_range(1, 2, 0, 1), ImageAccess::TransferWrite, Some(0)),
+                (
+                    image_range(1, 2, 1, 1),
+                    ImageAccess::ShaderReadOnly,
+                    Some(1)
+                ),
+                (image_range(3, 1, 0, 2), ImageAccess::TransferWrite, Some(0)),
+            ]
+        );
+    }
+
+    ///Separate graphics, compute and transfer families, and a device with only one.
+    const DEDICATED: [u32; 3] = [0, 1, 2];
+    const SHARED: [u32; 3] = [0, 0, 0];
+
+    fn resources() -> DeviceResources {
+        let mut resources = DeviceResources::new();
+
+        resources.buffers.add(InternalBuffer {
+            buffer: vk::Buffer::from_raw(1),
+            size: 256,
+            usage: BufferUsage::TRANSFER_DST,
+            memory: InternalMemory {
+                memory: vk::DeviceMemory::null(),
+                properties: Memory::DEVICE_LOCAL,
+            },
+            debug_name: "buffer".to_owned(),
+        });
+
+        resources.images.add(InternalImage::Managed {
+            image: vk::Image::from_raw(2),
+            memory: vk::DeviceMemory::null(),
+            view: vk::ImageView::null(),
+            view_type: vk::ImageViewType::TYPE_2D,
+            format: Format::Rgba8Unorm,
+            extent: vk::Extent3D {
+                width: 1,
+                height: 1,
+                depth: 1,
+            },
+            mip_levels: 2,
+            array_layers: 1,
+            samples: vk::SampleCountFlags::TYPE_1,
+            usage: vk::ImageUsageFlags::TRANSFER_DST,
+            view_formats: vec![],
+            debug_name: "image".to_owned(),
+        });
+
+        resources
+    }
+
+    ///Plans a frame of a single node on `queue`, like `render` does.
+    fn frame(
+        queue_family_indices: &[u32],
+        resources: &DeviceResources,
+        owners: &mut Owners,
+        queue: Queue,
+        qualifiers: &[Qualifier],
+    ) -> NodeSync {
+        let segments = [Segment {
+            queue,
+            nodes: 0..1,
+            command_buffers: vec![],
+        }];
+
+        let mut plan = FramePlan::new(queue_family_indices, &segments);
+
+        let sync = plan.node(resources, owners, 0, qualifiers);
+
+        plan.finish(resources, owners);
+
+        sync
+    }
+
+    #[test]
+    fn ownership_is_released_by_the_previous_frame() {
+        let resources = resources();
+        let mut owners = Owners::default();
+
+        let write = [Qualifier::Buffer(
+            Buffer(0),
+            BufferAccess::TransferWrite,
+            BufferRange::ALL,
+        )];
+        let read = [Qualifier::Buffer(
+            Buffer(0),
+            BufferAccess::ShaderReadOnly,
+            buffer_range(64, 64),
+        )];
+
+        let sync = frame(&DEDICATED, &resources, &mut owners, Queue::Transfer, &write);
+
+        assert!(sync.transfers.is_empty());
+
+        let sync = frame(&DEDICATED, &resources, &mut owners, Queue::Graphics, &read);
+
+        assert!(sync.dependencies.is_empty());
+        assert!(matches!(
+            sync.transfers[..],
+            [(
+                Dependency::Buffer {
+                    range: BufferRange {
+                        offset: 64,
+                        size: 64
+                    },
+                    src: BufferAccess::TransferWrite,
+                    node: None,
+                    ..
+                },
+                Release::Frame(Queue::Transfer),
+            )]
+        ));
+
+        //The graphics family owns the range now, the rest is still owned by the transfer family.
+        let sync = frame(&DEDICATED, &resources, &mut owners, Queue::Graphics, &read);
+
+        assert!(sync.transfers.is_empty());
+
+        let sync = frame(&DEDICATED, &resources, &mut owners, Queue::Graphics, &write);
+
+        assert_eq!(sync.transfers.len(), 2);
+        assert!(sync
+            .transfers
+            .iter()
+            .all(|(_, release)| *release == Release::Frame(Queue::Transfer)));
+    }
+
+    #[test]
+    fn image_ownership_is_released_per_subresource() {
+        let resources = resources();
+        let mut owners = Owners::default();
+
+        let qualifiers = |access| {
+            [Qualifier::Image(
+                Image(0),
+                access,
+                ImageAspect::COLOR,
+                ImageRange::ALL,
+            )]
+        };
+
+        frame(
+            &DEDICATED,
+            &resources,
+            &mut owners,
+            Queue::Compute,
+            &qualifiers(ImageAccess::ComputeShaderWriteOnly),
+        );
+
+        let sync = frame(
+            &DEDICATED,
+            &resources,
+            &mut owners,
+            Queue::Graphics,
+            &qualifiers(ImageAccess::FragmentShaderReadOnly),
+        );
+
+        assert!(sync.dependencies.is_empty());
+        assert_eq!(sync.transfers.len(), 2);
+
+        for (mip_level, (dependency, release)) in sync.transfers.iter().enumerate() {
+            assert!(matches!(
+                dependency,
+                Dependency::Image {
+                    range: ImageRange {
+                        base_mip_level,
+                        mip_level_count: 1,
+                        ..
+                    },
+                    src: ImageAccess::ComputeShaderWriteOnly,
+                    ..
+                } if *base_mip_level == mip_level as u32
+            ));
+            assert_eq!(*release, Release::Frame(Queue::Compute));
+        }
+    }
+
+    #[test]
+    fn shared_families_need_no_transfers() {
+        let resources = resources();
+        let mut owners = Owners::default();
+
+        let write = [Qualifier::Buffer(
+            Buffer(0),
+            BufferAccess::TransferWrite,
+            BufferRange::ALL,
+        )];
+        let read = [Qualifier::Buffer(
+            Buffer(0),
+            BufferAccess::ShaderReadOnly,
+            BufferRange::ALL,
+        )];
+
+        frame(&SHARED, &resources, &mut owners, Queue::Transfer, &write);
+
+        let sync = frame(&SHARED, &resources, &mut owners, Queue::Graphics, &read);
+
+        assert!(sync.transfers.is_empty());
+        assert_eq!(sync.dependencies.len(), 1);
+    }
+
+    #[test]
+    fn reused_handles_are_not_released() {
+        let mut resources = resources();
+        let mut owners = Owners::default();
+
+        let write = [Qualifier::Buffer(
+            Buffer(0),
+            BufferAccess::TransferWrite,
+            BufferRange::ALL,
+        )];
+
+        frame(&DEDICATED, &resources, &mut owners, Queue::Transfer, &write);
+
+        //Destroyed and created again, getting the same handle.
+        let mut internal_buffer = resources.buffers.remove(Buffer(0)).unwrap();
+        internal_buffer.buffer = vk::Buffer::from_raw(3);
+        resources.buffers.add(internal_buffer);
+
+        let sync = frame(&DEDICATED, &resources, &mut owners, Queue::Graphics, &write);
+
+        assert!(sync.transfers.is_empty());
+    }
 }
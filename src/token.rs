@@ -0,0 +1,304 @@
+use crate::prelude::*;
+
+use std::marker::PhantomData;
+use std::ops;
+
+///Ties a token to the task that declared it. Every task is declared with its own `'id`,
+///and since the lifetime is invariant, a token only fits the `Commands` of that task.
+pub(crate) type Brand<'id> = PhantomData<fn(&'id ()) -> &'id ()>;
+
+///Declares how a token may be used by `Commands`.
+pub trait Kind {}
+
+///The resource is only read, by shaders, transfers or the host.
+pub struct ReadOnly;
+///The resource is only written, by shaders, transfers or the host.
+pub struct WriteOnly;
+///The resource is read and written by shaders.
+pub struct ReadWrite;
+///The image is rendered to as a color or depth attachment.
+pub struct RenderTarget;
+
+impl Kind for ReadOnly {}
+impl Kind for WriteOnly {}
+impl Kind for ReadWrite {}
+impl Kind for RenderTarget {}
+
+///A buffer access that can be declared, which decides the kind of token handed out for it.
+pub trait BufferDeclaration: Copy + Into<BufferAccess> {
+    type Kind: Kind;
+}
+
+///An image access that can be declared, which decides the kind of token handed out for it.
+pub trait ImageDeclaration: Copy + Into<ImageAccess> {
+    type Kind: Kind;
+}
+
+macro_rules! declaration {
+    ($(#[$doc:meta])* $name:ident: $declaration:ident<$access:ident, $kind:ident> { $($variant:ident,)* }) => {
+        $(#[$doc])*
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+        pub enum $name {
+            $($variant,)*
+        }
+
+        impl From<$name> for $access {
+            fn from(access: $name) -> Self {
+                match access {
+                    $($name::$variant => $access::$variant,)*
+                }
+            }
+        }
+
+        impl $declaration for $name {
+            type Kind = $kind;
+        }
+    };
+}
+
+declaration! {
+    ///The buffer accesses of a `ReadOnly` token.
+    ReadBufferAccess: BufferDeclaration<BufferAccess, ReadOnly> {
+        ShaderReadOnly,
+        VertexShaderReadOnly,
+        FragmentShaderReadOnly,
+        ComputeShaderReadOnly,
+        TransferRead,
+        HostTransferRead,
+        IndirectRead,
+    }
+}
+
+declaration! {
+    ///The buffer accesses of a `WriteOnly` token.
+    WriteBufferAccess: BufferDeclaration<BufferAccess, WriteOnly> {
+        ShaderWriteOnly,
+        VertexShaderWriteOnly,
+        FragmentShaderWriteOnly,
+        ComputeShaderWriteOnly,
+        TransferWrite,
+        HostTransferWrite,
+    }
+}
+
+declaration! {
+    ///The buffer accesses of a `ReadWrite` token.
+    ReadWriteBufferAccess: BufferDeclaration<BufferAccess, ReadWrite> {
+        ShaderReadWrite,
+        VertexShaderReadWrite,
+        FragmentShaderReadWrite,
+        ComputeShaderReadWrite,
+    }
+}
+
+declaration! {
+    ///The image accesses of a `ReadOnly` token.
+    ReadImageAccess: ImageDeclaration<ImageAccess, ReadOnly> {
+        ShaderReadOnly,
+        VertexShaderReadOnly,
+        FragmentShaderReadOnly,
+        ComputeShaderReadOnly,
+        TransferRead,
+        Present,
+    }
+}
+
+declaration! {
+    ///The image accesses of a `WriteOnly` token.
+    WriteImageAccess: ImageDeclaration<ImageAccess, WriteOnly> {
+        ShaderWriteOnly,
+        VertexShaderWriteOnly,
+        FragmentShaderWriteOnly,
+        ComputeShaderWriteOnly,
+        TransferWrite,
+    }
+}
+
+declaration! {
+    ///The image accesses of a `ReadWrite` token.
+    ReadWriteImageAccess: ImageDeclaration<ImageAccess, ReadWrite> {
+        ShaderReadWrite,
+        VertexShaderReadWrite,
+        FragmentShaderReadWrite,
+        ComputeShaderReadWrite,
+    }
+}
+
+declaration! {
+    ///The image accesses of a `RenderTarget` token.
+    AttachmentAccess: ImageDeclaration<ImageAccess, RenderTarget> {
+        ColorAttachment,
+        DepthAttachment,
+        DepthAttachmentReadOnly,
+        DepthStencilAttachment,
+    }
+}
+
+///A buffer declared by a task, used in place of the buffer in its `Commands`.
+pub struct BufferToken<'id, K> {
+    pub(crate) index: usize,
+    brand: Brand<'id>,
+    marker: PhantomData<K>,
+}
+
+///An image declared by a task, used in place of the image in its `Commands`.
+pub struct ImageToken<'id, K> {
+    pub(crate) index: usize,
+    brand: Brand<'id>,
+    marker: PhantomData<K>,
+}
+
+impl<K> Clone for BufferToken<'_, K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K> Copy for BufferToken<'_, K> {}
+
+impl<K> Clone for ImageToken<'_, K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K> Copy for ImageToken<'_, K> {}
+
+impl<'id> From<BufferToken<'id, ReadWrite>> for BufferToken<'id, ReadOnly> {
+    fn from(token: BufferToken<'id, ReadWrite>) -> Self {
+        Self {
+            index: token.index,
+            brand: PhantomData,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'id> From<BufferToken<'id, ReadWrite>> for BufferToken<'id, WriteOnly> {
+    fn from(token: BufferToken<'id, ReadWrite>) -> Self {
+        Self {
+            index: token.index,
+            brand: PhantomData,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'id> From<ImageToken<'id, ReadWrite>> for ImageToken<'id, ReadOnly> {
+    fn from(token: ImageToken<'id, ReadWrite>) -> Self {
+        Self {
+            index: token.index,
+            brand: PhantomData,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'id> From<ImageToken<'id, ReadWrite>> for ImageToken<'id, WriteOnly> {
+    fn from(token: ImageToken<'id, ReadWrite>) -> Self {
+        Self {
+            index: token.index,
+            brand: PhantomData,
+            marker: PhantomData,
+        }
+    }
+}
+
+///The resources a task uses. Declaring one hands out a token, which is the only way to refer to it in `Commands`.
+///Only lent out while `RenderGraphBuilder::add` declares a task, so the tokens cannot outlive the declaration
+///except inside the task itself.
+pub struct Resources<'a, 'id, T> {
+    pub(crate) resources: Vec<Resource<T>>,
+    brand: Brand<'id>,
+    ///Lets the task hold on to anything that outlives the render graph.
+    scope: PhantomData<&'id &'a ()>,
+}
+
+impl<'a, 'id, T> Resources<'a, 'id, T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            resources: vec![],
+            brand: PhantomData,
+            scope: PhantomData,
+        }
+    }
+
+    ///Declares an access to a whole buffer.
+    pub fn buffer<A: BufferDeclaration>(
+        &mut self,
+        buffer: impl ops::Fn(&mut T) -> Buffer + Send + Sync + 'static,
+        access: A,
+    ) -> BufferToken<'id, A::Kind> {
+        self.declare_buffer(Resource::Buffer(Box::new(buffer), access.into()))
+    }
+
+    ///Declares an access to a range of a buffer.
+    pub fn buffer_range<A: BufferDeclaration>(
+        &mut self,
+        buffer: impl ops::Fn(&mut T) -> Buffer + Send + Sync + 'static,
+        access: A,
+        range: BufferRange,
+    ) -> BufferToken<'id, A::Kind> {
+        self.declare_buffer(Resource::BufferRange(
+            Box::new(buffer),
+            access.into(),
+            range,
+        ))
+    }
+
+    ///Declares an access to a whole image.
+    pub fn image<A: ImageDeclaration>(
+        &mut self,
+        image: impl ops::Fn(&mut T) -> Image + Send + Sync + 'static,
+        access: A,
+        aspect: ImageAspect,
+    ) -> ImageToken<'id, A::Kind> {
+        self.declare_image(Resource::Image(Box::new(image), access.into(), aspect))
+    }
+
+    ///Declares an access to some mip levels and array layers of an image.
+    pub fn image_range<A: ImageDeclaration>(
+        &mut self,
+        image: impl ops::Fn(&mut T) -> Image + Send + Sync + 'static,
+        access: A,
+        aspect: ImageAspect,
+        range: ImageRange,
+    ) -> ImageToken<'id, A::Kind> {
+        self.declare_image(Resource::ImageRange(
+            Box::new(image),
+            access.into(),
+            aspect,
+            range,
+        ))
+    }
+
+    ///Declares an image rendered to with `Commands::start_rendering`.
+    pub fn attachment(
+        &mut self,
+        image: impl ops::Fn(&mut T) -> Image + Send + Sync + 'static,
+        access: AttachmentAccess,
+        aspect: ImageAspect,
+    ) -> ImageToken<'id, RenderTarget> {
+        self.image(image, access, aspect)
+    }
+
+    fn declare_buffer<K: Kind>(&mut self, resource: Resource<T>) -> BufferToken<'id, K> {
+        self.resources.push(resource);
+
+        BufferToken {
+            index: self.resources.len() - 1,
+            brand: PhantomData,
+            marker: PhantomData,
+        }
+    }
+
+    fn declare_image<K: Kind>(&mut self, resource: Resource<T>) -> ImageToken<'id, K> {
+        self.resources.push(resource);
+
+        ImageToken {
+            index: self.resources.len() - 1,
+            brand: PhantomData,
+            marker: PhantomData,
+        }
+    }
+}
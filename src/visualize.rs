@@ -0,0 +1,641 @@
+use crate::commands::{Access, Barrier};
+use crate::device::DeviceInner;
+use crate::prelude::*;
+use crate::task::{Dependency, FramePlan, NodeSync, Qualifier, Release, RenderGraphInner};
+
+use std::fmt::Write;
+
+use ash::vk;
+
+///What a single node of the render graph does in a frame.
+struct NodePlan {
+    name: String,
+    queue: Queue,
+    qualifiers: Vec<Qualifier>,
+    ///On the same queue family, synchronized with barriers.
+    dependencies: Vec<Dependency>,
+    barriers: Vec<PipelineBarrier>,
+    ///From other queue families, synchronized with an ownership transfer and a semaphore,
+    ///along with the queue that releases the range.
+    ///Without a node, an earlier frame left the range there and it is released before the frame.
+    transfers: Vec<(Dependency, Queue)>,
+}
+
+impl<T> RenderGraph<'_, T> {
+    ///Plans the next frame the same way `render` does, without recording anything.
+    fn plan(&self, home: &mut T) -> Vec<NodePlan> {
+        let RenderGraphInner {
+            device,
+            segments,
+            modify,
+            ..
+        } = &*self.inner;
+
+        let DeviceInner { resources, .. } = &**device;
+
+        let modify = modify.lock().unwrap();
+
+        let mut plan = FramePlan::new(&device.queue_family_indices, segments);
+
+        modify
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| {
+                let qualifiers = node
+                    .resources
+                    .iter()
+                    .map(|resource| resource.resolve(home))
+                    .collect::<Vec<_>>();
+
+                let NodeSync {
+                    dependencies,
+                    barriers,
+                    transfers,
+                } = {
+                    let resources = resources.lock().unwrap();
+
+                    plan.node(&resources, &modify.owners, i, &qualifiers)
+                };
+
+                let transfers = transfers
+                    .into_iter()
+                    .map(|(transfer, release)| match release {
+                        Release::Segment(segment) => (transfer, segments[segment].queue),
+                        Release::Frame(queue) => (transfer, queue),
+                    })
+                    .collect();
+
+                NodePlan {
+                    name: node.debug_name.clone(),
+                    queue: node.queue,
+                    qualifiers,
+                    dependencies,
+                    barriers,
+                    transfers,
+                }
+            })
+            .collect()
+    }
+
+    ///Describes the graph in the Graphviz dot language.
+    ///Edges point from the task that last used a resource to the task that has to wait for it,
+    ///dashed when the resource moves to another queue family.
+    ///The resources are resolved with `home`, just like they are when rendering.
+    pub fn to_dot(&self, home: &mut T) -> String {
+        dot(&self.plan(home))
+    }
+
+    ///Describes every node of the graph as JSON: its resources and their access,
+    ///the barriers recorded before it, the ownership transfers it waits for
+    ///and the image layout transitions they perform.
+    ///The resources are resolved with `home`, just like they are when rendering.
+    pub fn to_json(&self, home: &mut T) -> String {
+        json(&self.plan(home))
+    }
+}
+
+fn dot(plans: &[NodePlan]) -> String {
+    let mut dot = String::new();
+
+    writeln!(dot, "digraph render_graph {{").unwrap();
+    writeln!(dot, "    node [shape=box];").unwrap();
+
+    for (i, plan) in plans.iter().enumerate() {
+        let mut label = vec![format!("{} ({:?})", plan.name, plan.queue)];
+
+        for qualifier in &plan.qualifiers {
+            label.push(describe_qualifier(qualifier));
+        }
+
+        let dependencies = plan
+            .dependencies
+            .iter()
+            .chain(plan.transfers.iter().map(|(transfer, _)| transfer));
+
+        for dependency in dependencies {
+            if let Dependency::Image {
+                qualifier,
+                src,
+                dst,
+                ..
+            } = *dependency
+            {
+                let (old_layout, new_layout) = (ImageLayout::from(src), ImageLayout::from(dst));
+
+                if !same_layout(old_layout, new_layout) {
+                    label.push(format!(
+                        "{}: {old_layout:?} -> {new_layout:?}",
+                        describe_resource(&plan.qualifiers[qualifier])
+                    ));
+                }
+            }
+        }
+
+        for (transfer, queue) in &plan.transfers {
+            if transfer.node().is_none() {
+                label.push(format!(
+                    "{}: released by {queue:?} before the frame",
+                    describe_resource(&plan.qualifiers[transfer.qualifier()])
+                ));
+            }
+        }
+
+        writeln!(dot, "    n{i} [label=\"{}\"];", dot_label(&label)).unwrap();
+    }
+
+    for (i, plan) in plans.iter().enumerate() {
+        let dependencies = plan
+            .dependencies
+            .iter()
+            .map(|dependency| (dependency, false))
+            .chain(plan.transfers.iter().map(|(transfer, _)| (transfer, true)));
+
+        for (dependency, transfer) in dependencies {
+            let (qualifier, node, src, dst) = match *dependency {
+                Dependency::Buffer {
+                    qualifier,
+                    node,
+                    src,
+                    dst,
+                    ..
+                } => (qualifier, node, format!("{src:?}"), format!("{dst:?}")),
+                Dependency::Image {
+                    qualifier,
+                    node,
+                    src,
+                    dst,
+                    ..
+                } => (qualifier, node, format!("{src:?}"), format!("{dst:?}")),
+            };
+
+            let Some(node) = node else {
+                continue;
+            };
+
+            let label = [
+                describe_resource(&plan.qualifiers[qualifier]),
+                format!("{src} -> {dst}"),
+            ];
+
+            let style = if transfer { ", style=dashed" } else { "" };
+
+            writeln!(
+                dot,
+                "    n{node} -> n{i} [label=\"{}\"{style}];",
+                dot_label(&label)
+            )
+            .unwrap();
+        }
+    }
+
+    writeln!(dot, "}}").unwrap();
+
+    dot
+}
+
+fn json(plans: &[NodePlan]) -> String {
+    let mut json = String::new();
+
+    json.push_str("{\n  \"nodes\": [");
+
+    for (i, plan) in plans.iter().enumerate() {
+        if i != 0 {
+            json.push(',');
+        }
+
+        write!(
+            json,
+            "\n    {{\n      \"index\": {i},\n      \"name\": {},\n      \"queue\": \"{:?}\",\n      \"resources\": [",
+            escape_json(&plan.name),
+            plan.queue
+        )
+        .unwrap();
+
+        for (j, qualifier) in plan.qualifiers.iter().enumerate() {
+            if j != 0 {
+                json.push(',');
+            }
+
+            json.push_str("\n        ");
+            json.push_str(&qualifier_json(qualifier));
+        }
+
+        json.push_str("\n      ],\n      \"dependencies\": [");
+
+        for (j, dependency) in plan.dependencies.iter().enumerate() {
+            if j != 0 {
+                json.push(',');
+            }
+
+            let (qualifier, node) = match *dependency {
+                Dependency::Buffer {
+                    qualifier, node, ..
+                } => (qualifier, node),
+                Dependency::Image {
+                    qualifier, node, ..
+                } => (qualifier, node),
+            };
+
+            let node = node.map_or("null".to_owned(), |node| node.to_string());
+
+            write!(
+                json,
+                "\n        {{ \"resource\": {qualifier}, \"node\": {node} }}"
+            )
+            .unwrap();
+        }
+
+        json.push_str("\n      ],\n      \"transfers\": [");
+
+        for (j, (transfer, queue)) in plan.transfers.iter().enumerate() {
+            if j != 0 {
+                json.push(',');
+            }
+
+            let node = transfer
+                .node()
+                .map_or("null".to_owned(), |node| node.to_string());
+
+            write!(
+                json,
+                "\n        {{ \"resource\": {}, \"node\": {node}, \"released_by\": \"{queue:?}\" }}",
+                transfer.qualifier()
+            )
+            .unwrap();
+        }
+
+        json.push_str("\n      ],\n      \"barriers\": [");
+
+        for (j, barrier) in plan.barriers.iter().enumerate() {
+            if j != 0 {
+                json.push(',');
+            }
+
+            write!(
+                json,
+                "\n        {{\n          \"src_stage\": {},\n          \"dst_stage\": {},\n          \"barriers\": [",
+                flags_json(barrier.src_stage.iter_names()),
+                flags_json(barrier.dst_stage.iter_names()),
+            )
+            .unwrap();
+
+            for (k, barrier) in barrier.barriers.iter().enumerate() {
+                if k != 0 {
+                    json.push(',');
+                }
+
+                json.push_str("\n            ");
+                json.push_str(&barrier_json(barrier));
+            }
+
+            json.push_str("\n          ]\n        }");
+        }
+
+        json.push_str("\n      ],\n      \"transitions\": [");
+
+        let acquires = plan
+            .transfers
+            .iter()
+            .map(|(transfer, _)| transfer.acquire())
+            .collect::<Vec<_>>();
+
+        let transitions = plan
+            .barriers
+            .iter()
+            .chain(&acquires)
+            .flat_map(|barrier| &barrier.barriers)
+            .filter_map(|barrier| match barrier {
+                Barrier::Image {
+                    image,
+                    old_layout,
+                    new_layout,
+                    range,
+                    ..
+                } if !same_layout(*old_layout, *new_layout) => Some(format!(
+                    "{{ \"resource\": {image}, \"old_layout\": \"{old_layout:?}\", \"new_layout\": \"{new_layout:?}\", \"range\": {} }}",
+                    image_range_json(range)
+                )),
+                _ => None,
+            });
+
+        for (j, transition) in transitions.enumerate() {
+            if j != 0 {
+                json.push(',');
+            }
+
+            json.push_str("\n        ");
+            json.push_str(&transition);
+        }
+
+        json.push_str("\n      ]\n    }");
+    }
+
+    json.push_str("\n  ]\n}\n");
+
+    json
+}
+
+fn same_layout(a: ImageLayout, b: ImageLayout) -> bool {
+    vk::ImageLayout::from(a) == vk::ImageLayout::from(b)
+}
+
+fn describe_resource(qualifier: &Qualifier) -> String {
+    match qualifier {
+        Qualifier::Buffer(buffer, ..) => format!("buffer {}", buffer.0),
+        Qualifier::Image(image, ..) => format!("image {}", image.0),
+    }
+}
+
+fn describe_qualifier(qualifier: &Qualifier) -> String {
+    match qualifier {
+        Qualifier::Buffer(_, access, range) if *range == BufferRange::ALL => {
+            format!("{} ({access:?})", describe_resource(qualifier))
+        }
+        Qualifier::Buffer(_, access, range) => format!(
+            "{} [{}, +{}] ({access:?})",
+            describe_resource(qualifier),
+            range.offset,
+            range.size
+        ),
+        Qualifier::Image(_, access, _, range) if *range == ImageRange::ALL => {
+            format!("{} ({access:?})", describe_resource(qualifier))
+        }
+        Qualifier::Image(_, access, _, range) => format!(
+            "{} mips {}+{} layers {}+{} ({access:?})",
+            describe_resource(qualifier),
+            range.base_mip_level,
+            range.mip_level_count,
+            range.base_array_layer,
+            range.array_layer_count
+        ),
+    }
+}
+
+fn qualifier_json(qualifier: &Qualifier) -> String {
+    match qualifier {
+        Qualifier::Buffer(buffer, access, range) => format!(
+            "{{ \"type\": \"buffer\", \"handle\": {}, \"access\": \"{access:?}\", \"offset\": {}, \"size\": {} }}",
+            buffer.0, range.offset, range.size
+        ),
+        Qualifier::Image(image, access, aspect, range) => format!(
+            "{{ \"type\": \"image\", \"handle\": {}, \"access\": \"{access:?}\", \"aspect\": {}, \"range\": {} }}",
+            image.0,
+            flags_json(aspect.iter_names()),
+            image_range_json(range)
+        ),
+    }
+}
+
+fn barrier_json(barrier: &Barrier) -> String {
+    match barrier {
+        Barrier::Buffer {
+            buffer,
+            offset,
+            size,
+            src_access,
+            dst_access,
+        } => format!(
+            "{{ \"type\": \"buffer\", \"resource\": {buffer}, \"offset\": {offset}, \"size\": {size}, \"src_access\": {}, \"dst_access\": {} }}",
+            access_json(src_access),
+            access_json(dst_access)
+        ),
+        Barrier::Image {
+            image,
+            old_layout,
+            new_layout,
+            src_access,
+            dst_access,
+            image_aspect,
+            range,
+        } => format!(
+            "{{ \"type\": \"image\", \"resource\": {image}, \"old_layout\": \"{old_layout:?}\", \"new_layout\": \"{new_layout:?}\", \"src_access\": {}, \"dst_access\": {}, \"aspect\": {}, \"range\": {} }}",
+            access_json(src_access),
+            access_json(dst_access),
+            flags_json(image_aspect.iter_names()),
+            image_range_json(range)
+        ),
+    }
+}
+
+fn access_json(access: &Access) -> String {
+    flags_json(access.iter_names())
+}
+
+fn image_range_json(range: &ImageRange) -> String {
+    format!(
+        "{{ \"base_mip_level\": {}, \"mip_level_count\": {}, \"base_array_layer\": {}, \"array_layer_count\": {} }}",
+        range.base_mip_level, range.mip_level_count, range.base_array_layer, range.array_layer_count
+    )
+}
+
+fn flags_json<'a, F>(names: impl Iterator<Item = (&'a str, F)>) -> String {
+    let names = names.map(|(name, _)| escape_json(name)).collect::<Vec<_>>();
+
+    format!("[{}]", names.join(", "))
+}
+
+fn escape_json(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len() + 2);
+
+    escaped.push('"');
+
+    for c in string.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+
+    escaped
+}
+
+///Escapes every line of a label and joins them with line breaks.
+fn dot_label(lines: &[String]) -> String {
+    let lines = lines
+        .iter()
+        .map(|line| escape_dot(line))
+        .collect::<Vec<_>>();
+
+    lines.join("\\n")
+}
+
+fn escape_dot(string: &str) -> String {
+    string.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::merge_barriers;
+
+    const RANGE: BufferRange = BufferRange {
+        offset: 0,
+        size: 256,
+    };
+
+    const MIP: ImageRange = ImageRange {
+        base_mip_level: 0,
+        mip_level_count: 1,
+        base_array_layer: 0,
+        array_layer_count: 1,
+    };
+
+    ///A transfer into a buffer, then a task reading it into an image it renders for the first time.
+    fn two_nodes() -> Vec<NodePlan> {
+        let upload = NodePlan {
+            name: "upload".to_owned(),
+            queue: Queue::Graphics,
+            qualifiers: vec![Qualifier::Buffer(
+                Buffer(0),
+                BufferAccess::TransferWrite,
+                RANGE,
+            )],
+            dependencies: vec![],
+            barriers: vec![],
+            transfers: vec![],
+        };
+
+        let dependencies = vec![
+            Dependency::Buffer {
+                qualifier: 0,
+                range: RANGE,
+                src: BufferAccess::TransferWrite,
+                dst: BufferAccess::FragmentShaderReadOnly,
+                node: Some(0),
+            },
+            Dependency::Image {
+                qualifier: 1,
+                range: MIP,
+                aspect: ImageAspect::COLOR,
+                src: ImageAccess::None,
+                dst: ImageAccess::ColorAttachment,
+                node: None,
+            },
+        ];
+
+        let draw = NodePlan {
+            name: "draw".to_owned(),
+            queue: Queue::Graphics,
+            qualifiers: vec![
+                Qualifier::Buffer(Buffer(0), BufferAccess::FragmentShaderReadOnly, RANGE),
+                Qualifier::Image(
+                    Image(1),
+                    ImageAccess::ColorAttachment,
+                    ImageAspect::COLOR,
+                    ImageRange::ALL,
+                ),
+            ],
+            barriers: merge_barriers(&dependencies),
+            dependencies,
+            transfers: vec![],
+        };
+
+        vec![upload, draw]
+    }
+
+    #[test]
+    fn two_nodes_to_dot() {
+        let expected = r#"digraph render_graph {
+    node [shape=box];
+    n0 [label="upload (Graphics)\nbuffer 0 [0, +256] (TransferWrite)"];
+    n1 [label="draw (Graphics)\nbuffer 0 [0, +256] (FragmentShaderReadOnly)\nimage 1 (ColorAttachment)\nimage 1: Undefined -> AttachmentOptimal"];
+    n0 -> n1 [label="buffer 0\nTransferWrite -> FragmentShaderReadOnly"];
+}
+"#;
+
+        assert_eq!(dot(&two_nodes()), expected);
+    }
+
+    #[test]
+    fn two_nodes_to_json() {
+        let expected = r#"{
+  "nodes": [
+    {
+      "index": 0,
+      "name": "upload",
+      "queue": "Graphics",
+      "resources": [
+        { "type": "buffer", "handle": 0, "access": "TransferWrite", "offset": 0, "size": 256 }
+      ],
+      "dependencies": [
+      ],
+      "transfers": [
+      ],
+      "barriers": [
+      ],
+      "transitions": [
+      ]
+    },
+    {
+      "index": 1,
+      "name": "draw",
+      "queue": "Graphics",
+      "resources": [
+        { "type": "buffer", "handle": 0, "access": "FragmentShaderReadOnly", "offset": 0, "size": 256 },
+        { "type": "image", "handle": 1, "access": "ColorAttachment", "aspect": ["COLOR"], "range": { "base_mip_level": 0, "mip_level_count": 4294967295, "base_array_layer": 0, "array_layer_count": 4294967295 } }
+      ],
+      "dependencies": [
+        { "resource": 0, "node": 0 },
+        { "resource": 1, "node": null }
+      ],
+      "transfers": [
+      ],
+      "barriers": [
+        {
+          "src_stage": ["TRANSFER"],
+          "dst_stage": ["FRAGMENT_SHADER"],
+          "barriers": [
+            { "type": "buffer", "resource": 0, "offset": 0, "size": 256, "src_access": ["WRITE"], "dst_access": ["READ"] }
+          ]
+        },
+        {
+          "src_stage": [],
+          "dst_stage": ["COLOR_ATTACHMENT_OUTPUT"],
+          "barriers": [
+            { "type": "image", "resource": 1, "old_layout": "Undefined", "new_layout": "AttachmentOptimal", "src_access": [], "dst_access": ["WRITE", "READ"], "aspect": ["COLOR"], "range": { "base_mip_level": 0, "mip_level_count": 1, "base_array_layer": 0, "array_layer_count": 1 } }
+          ]
+        }
+      ],
+      "transitions": [
+        { "resource": 1, "old_layout": "Undefined", "new_layout": "AttachmentOptimal", "range": { "base_mip_level": 0, "mip_level_count": 1, "base_array_layer": 0, "array_layer_count": 1 } }
+      ]
+    }
+  ]
+}
+"#;
+
+        assert_eq!(json(&two_nodes()), expected);
+    }
+
+    #[test]
+    fn transfers_are_dashed() {
+        let mut plans = two_nodes();
+
+        plans[1].queue = Queue::Compute;
+        plans[1].transfers = vec![(plans[1].dependencies.remove(0), Queue::Graphics)];
+        plans[1].barriers = merge_barriers(&plans[1].dependencies);
+
+        assert!(dot(&plans).contains(
+            "n0 -> n1 [label=\"buffer 0\\nTransferWrite -> FragmentShaderReadOnly\", style=dashed];"
+        ));
+        assert!(json(&plans).contains(r#"{ "resource": 0, "node": 0, "released_by": "Graphics" }"#));
+    }
+
+    #[test]
+    fn names_are_escaped() {
+        let mut plans = two_nodes();
+
+        plans[0].name = r#"C:\upload "all""#.to_owned();
+
+        assert!(dot(&plans).contains(r#"n0 [label="C:\\upload \"all\" (Graphics)\nbuffer"#));
+        assert!(json(&plans).contains(r#""name": "C:\\upload \"all\"","#));
+    }
+}